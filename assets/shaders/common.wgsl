@@ -0,0 +1,446 @@
+const PI = 3.141592653589793238462643383279;
+
+// see skybox::SkyboxSource and sample_skybox
+@group(2) @binding(0) var skybox_cube: texture_cube<f32>;
+@group(2) @binding(1) var skybox_cube_sampler: sampler;
+@group(2) @binding(2) var skybox_panorama: texture_2d<f32>;
+@group(2) @binding(3) var skybox_panorama_sampler: sampler;
+@group(2) @binding(4) var<uniform> skybox_projection: u32; // see SkyboxProjection
+
+@group(2) @binding(5) var<uniform> skybox_intensity: f32;
+
+@group(2) @binding(6) var<uniform> fov: f32;
+
+// assuming cam_x, cam_y, cam_z is normalized
+@group(2) @binding(7) var<uniform> cam_pos: vec3<f32>;
+@group(2) @binding(8) var<uniform> cam_x: vec3<f32>; // cam right
+@group(2) @binding(9) var<uniform> cam_y: vec3<f32>; // cam up
+@group(2) @binding(10) var<uniform> cam_z: vec3<f32>; // the way the camera is facing
+
+@group(2) @binding(11) var accretion_disc_texture: texture_2d<f32>;
+@group(2) @binding(12) var accretion_disc_sampler: sampler;
+@group(2) @binding(13) var<uniform> accretion_disc_r: f32;
+@group(2) @binding(14) var<uniform> accretion_disc_width: f32;
+@group(2) @binding(15) var<uniform> accretion_disc_intensity: f32;
+@group(2) @binding(16) var<uniform> accretion_disc_time: f32; // coordinate time the disc has been turning for, modulo DISC_TIME_PERIOD of main.rs
+
+struct Spacetime {
+    a: f32, // spin
+    q: f32, // charge, a^2 + q^2 <= 1
+    lambda: f32, // cosmological constant, >= 0
+
+    // derived on the cpu
+    horizon: f32, // outer event horizon
+    cosmological_horizon: f32, // 0 when lambda = 0
+
+    // ellis wormhole instead of a black hole when > 0
+    throat_radius: f32,
+    camera_side: f32, // 1 in the universe of the first skybox, -1 in the other one
+
+    // r range of the user-defined metric table
+    table_r_min: f32,
+    table_r_max: f32,
+}
+@group(2) @binding(17) var<uniform> spacetime: Spacetime;
+
+// skybox of the universe on the other side of the wormhole throat
+@group(2) @binding(18) var other_skybox_cube: texture_cube<f32>;
+@group(2) @binding(19) var other_skybox_cube_sampler: sampler;
+@group(2) @binding(20) var other_skybox_panorama: texture_2d<f32>;
+@group(2) @binding(21) var other_skybox_panorama_sampler: sampler;
+@group(2) @binding(22) var<uniform> other_skybox_projection: u32;
+
+// user-defined metric and its christoffel symbols, see UserDefined::metric_table
+@group(2) @binding(23) var metric_table: texture_2d<f32>;
+
+// velocity of the camera relative to the observer the metrics set the rays up for, 0 unless it is free-falling
+@group(2) @binding(24) var<uniform> cam_velocity: vec3<f32>;
+
+// see DiscEmission and disc::temperature_scale
+@group(2) @binding(25) var<uniform> accretion_disc_emission: u32;
+@group(2) @binding(26) var<uniform> accretion_disc_temperature: f32;
+@group(2) @binding(27) var<uniform> accretion_disc_inner_edge: f32;
+
+// see DiscShape
+@group(2) @binding(28) var<uniform> accretion_disc_shape: u32;
+@group(2) @binding(29) var<uniform> accretion_torus_thickness: f32; // half height over half width
+@group(2) @binding(30) var<uniform> accretion_torus_opacity: f32; // per M at full density
+@group(2) @binding(31) var<uniform> accretion_torus_noise: f32; // 0 for a smooth torus
+
+// see Jets
+@group(2) @binding(32) var<uniform> jet_opening_angle: f32; // half of it, in radians
+@group(2) @binding(33) var<uniform> jet_length: f32;
+@group(2) @binding(34) var<uniform> jet_lorentz_factor: f32;
+@group(2) @binding(35) var<uniform> jet_emissivity: f32; // 0 without jets
+
+// see Integrator
+@group(2) @binding(36) var<uniform> integrator: u32;
+
+// see RayTracing
+@group(2) @binding(37) var<uniform> step_budget: u32; // steps per ray
+@group(2) @binding(38) var<uniform> max_orbits: f32; // how far around the rays stepping in phi are followed
+
+// see deflection::deflection_table
+@group(2) @binding(39) var deflection_table: texture_2d<f32>;
+@group(2) @binding(40) var<uniform> deflection_table_r: f32; // camera radius the table is for, 0 when it can't be used
+
+// the budget the kerr and user-defined shaders were tuned for
+const DEFAULT_STEP_BUDGET = 200u;
+
+// the step in phi of a ray that goes around max_orbits times within the step budget
+fn default_step_size() -> f32 {
+    return max_orbits * 2. * PI / f32(step_budget);
+}
+
+// the shaders stepping in the affine parameter take this many times their default number of steps
+fn step_budget_scale() -> f32 {
+    return f32(step_budget) / f32(DEFAULT_STEP_BUDGET);
+}
+// relative error per step the adaptive integrator aims for
+const INTEGRATOR_TOLERANCE = 1e-5;
+
+const UP = vec3(0., 1., 0.);
+const DOWN = vec3(0., -1., 0.);
+const LEFT = vec3(-1., 0., 0.);
+const RIGHT = vec3(1., 0., 0.);
+const FORWARD = vec3(0., 0., -1.);
+const BACKWARD = vec3(0., 0., 1.);
+struct IntegrationStep {
+    u: f32,
+    v: f32,
+}
+
+struct Intersection {
+    intersects: bool,
+    point: vec3<f32>
+}
+
+fn ray_plane_intersect(ray_direction: vec3<f32>, ray_origin: vec3<f32>, normal_: vec3<f32>, plane_center: vec3<f32>) -> Intersection {
+    var normal = normal_;
+    if dot(ray_direction, normal) < 0. {
+        normal = -normal;
+    }
+
+    // source: https://www.scratchapixel.com/lessons/3d-basic-rendering/minimal-ray-tracer-rendering-simple-shapes/ray-plane-and-ray-disk-intersection.html
+    let denom = dot(ray_direction, normal);
+    if denom > 1e-6 {
+        let t = dot(plane_center - ray_origin, normal) / denom;
+        return Intersection(t >= 0., ray_origin + t * ray_direction);
+    }
+
+    return Intersection(false, vec3(0., 0., 0.));
+}
+
+// longitude across and latitude down, -z in the middle and +x a quarter of the width to the right of it
+fn to_equirectangular(v: vec3<f32>) -> vec2<f32> {
+    let d = normalize(v);
+    return vec2(0.5 + atan2(d.x, -d.z) / (2. * PI), 0.5 - asin(clamp(d.y, -1., 1.)) / PI);
+}
+
+// the cube maps are left-handed with the forward face on +z, like the ones of bevy's skybox, see skybox::CUBE_FACES
+fn to_cube_direction(v: vec3<f32>) -> vec3<f32> {
+    return vec3(v.xy, -v.z);
+}
+
+// the rays bend, so the derivatives of neighbouring pixels say nothing about the mip level
+fn sample_skybox(ray: vec3<f32>) -> vec4<f32> {
+    if skybox_projection == 1u {
+        return textureSampleLevel(skybox_panorama, skybox_panorama_sampler, to_equirectangular(ray), 0.) * skybox_intensity;
+    }
+    return textureSampleLevel(skybox_cube, skybox_cube_sampler, to_cube_direction(ray), 0.) * skybox_intensity;
+}
+
+fn sample_other_skybox(ray: vec3<f32>) -> vec4<f32> {
+    if other_skybox_projection == 1u {
+        return textureSampleLevel(other_skybox_panorama, other_skybox_panorama_sampler, to_equirectangular(ray), 0.) * skybox_intensity;
+    }
+    return textureSampleLevel(other_skybox_cube, other_skybox_cube_sampler, to_cube_direction(ray), 0.) * skybox_intensity;
+}
+
+// returns transparent black outside of the disc, every ring of it turns with its own angular velocity omega
+fn sample_accretion_disc(point: vec3<f32>, omega: f32) -> vec4<f32> {
+    let accretion_disc_max_r = accretion_disc_r + accretion_disc_width;
+    let point_r = length(point);
+
+    if (
+        point_r <= accretion_disc_r ||
+        point_r >= accretion_disc_max_r
+    ) {
+        return vec4(0., 0., 0., 0.);
+    }
+
+    var accretion_disc_texture_phi = atan2(point.z, point.x);
+    accretion_disc_texture_phi += (omega * accretion_disc_time) % (2 * PI);
+    accretion_disc_texture_phi += PI;
+    accretion_disc_texture_phi %= 2 * PI;
+
+    let coords = vec2(accretion_disc_texture_phi / (2 * PI), (accretion_disc_max_r - point_r) / accretion_disc_width);
+
+    return textureSample(accretion_disc_texture, accretion_disc_sampler, coords) * (accretion_disc_max_r - point_r) / accretion_disc_width * accretion_disc_intensity;
+}
+
+// with a cosmological horizon the rays never reach flat infinity,
+// the sky is then painted on the horizon where the ray crosses it
+fn sky_direction(ray: vec3<f32>, pos: vec3<f32>) -> vec3<f32> {
+    if spacetime.cosmological_horizon > 0. {
+        return normalize(pos);
+    }
+
+    return ray;
+}
+
+// the rgb channels are taken as samples of the spectrum at these wavelengths in nm
+const CHANNEL_WAVELENGTHS = vec3(610., 550., 465.);
+const MAX_FREQUENCY_SHIFT = 10.;
+
+// linear between the channels and falling off over 100 nm beyond them
+fn spectrum(color: vec3<f32>, wavelength: f32) -> f32 {
+    let w = CHANNEL_WAVELENGTHS;
+    if wavelength >= w.x {
+        return color.r * max(1. - (wavelength - w.x) / 100., 0.);
+    }
+    if wavelength >= w.y {
+        return mix(color.g, color.r, (wavelength - w.y) / (w.x - w.y));
+    }
+    if wavelength >= w.z {
+        return mix(color.b, color.g, (wavelength - w.z) / (w.y - w.z));
+    }
+    return color.b * max(1. - (w.z - wavelength) / 100., 0.);
+}
+
+// colour of a source seen with g = observed / emitted frequency; the spectrum is stretched by g
+// and I_nu / nu^3 is invariant, which beams the intensity by g^3
+fn frequency_shift(color: vec4<f32>, g_: f32) -> vec4<f32> {
+    let g = clamp(g_, 0., MAX_FREQUENCY_SHIFT);
+    let w = CHANNEL_WAVELENGTHS * g;
+    let shifted = vec3(spectrum(color.rgb, w.x), spectrum(color.rgb, w.y), spectrum(color.rgb, w.z));
+    return vec4(shifted * g * g * g, color.a);
+}
+
+// the colour matching functions of cie 1931 as sums of piecewise gaussians, fitted by wyman, sloan and shirley
+fn lobe(wavelength: f32, center: f32, width_below: f32, width_above: f32) -> f32 {
+    let t = (wavelength - center) / select(width_above, width_below, wavelength < center);
+    return exp(-0.5 * t * t);
+}
+
+fn color_matching(w: f32) -> vec3<f32> {
+    return vec3(
+        1.056 * lobe(w, 599.8, 37.9, 31.0) + 0.362 * lobe(w, 442.0, 16.0, 26.7) - 0.065 * lobe(w, 501.1, 20.4, 26.2),
+        0.821 * lobe(w, 568.8, 46.9, 40.5) + 0.286 * lobe(w, 530.9, 16.3, 31.1),
+        1.217 * lobe(w, 437.0, 11.8, 36.0) + 0.681 * lobe(w, 459.0, 26.0, 13.8)
+    );
+}
+
+const XYZ_TO_SRGB = mat3x3(
+    vec3(3.2406, -0.9689, 0.0557),
+    vec3(-1.5372, 1.8758, -0.2040),
+    vec3(-0.4986, 0.0415, 1.0570)
+);
+
+// linear srgb colour of a blackbody at the temperature in kelvin, the brightest channel is 1;
+// the colour hardly changes beyond the range
+fn blackbody_color(temperature_: f32) -> vec3<f32> {
+    let temperature = clamp(temperature_, 500., 1e6);
+
+    var xyz = vec3(0., 0., 0.);
+    for (var i = 0; i <= 40; i++) {
+        let wavelength = 380. + 10. * f32(i);
+        // planck's law with the wavelength in um, hc / k = 14388 um K
+        let um = wavelength / 1000.;
+        let radiance = 1. / (um * um * um * um * um * (exp(14388. / (um * temperature)) - 1.));
+        xyz += radiance * color_matching(wavelength);
+    }
+
+    let rgb = max(XYZ_TO_SRGB * xyz, vec3(0., 0., 0.));
+    return rgb / max(max(rgb.r, rgb.g), rgb.b);
+}
+
+// shakura-sunyaev temperature profile in the frame of the gas, 0 inside the inner edge
+fn disc_temperature(r: f32) -> f32 {
+    return accretion_disc_temperature * pow(max(1. - sqrt(accretion_disc_inner_edge / r), 0.) / (r * r * r), 0.25);
+}
+
+// the disc at the point as the camera sees it, with g = observed / emitted frequency
+fn shade_accretion_disc(point: vec3<f32>, omega: f32, g: f32) -> vec4<f32> {
+    if accretion_disc_emission == 0u {
+        return frequency_shift(sample_accretion_disc(point, omega), g);
+    }
+
+    let point_r = length(point);
+    if point_r <= accretion_disc_r || point_r >= accretion_disc_r + accretion_disc_width {
+        return vec4(0., 0., 0., 0.);
+    }
+
+    // a blackbody seen with the shift g looks like one at g times the temperature, the bolometric intensity
+    // goes as T^4; the hottest ring, at 49/36 of the inner edge, is shown with accretion_disc_intensity at rest
+    let peak = disc_temperature(49. / 36. * accretion_disc_inner_edge);
+    let observed = clamp(g, 0., MAX_FREQUENCY_SHIFT) * disc_temperature(point_r);
+    if peak <= 0. || observed <= 0. {
+        return vec4(0., 0., 0., 0.);
+    }
+
+    let brightness = pow(observed / peak, 4.) * accretion_disc_intensity;
+    return vec4(blackbody_color(observed) * brightness, 1.);
+}
+
+/* #region torus */
+// samples of the torus on each step of the ray
+const TORUS_SAMPLES = 4;
+
+fn hash(p: vec3<f32>) -> f32 {
+    return fract(sin(dot(p, vec3(127.1, 311.7, 74.7))) * 43758.5453);
+}
+
+// smoothly interpolated random values on the integer lattice, between 0 and 1
+fn value_noise(p: vec3<f32>) -> f32 {
+    let i = floor(p);
+    let t = fract(p);
+    let s = t * t * (3. - 2. * t);
+
+    let x00 = mix(hash(i), hash(i + vec3(1., 0., 0.)), s.x);
+    let x10 = mix(hash(i + vec3(0., 1., 0.)), hash(i + vec3(1., 1., 0.)), s.x);
+    let x01 = mix(hash(i + vec3(0., 0., 1.)), hash(i + vec3(1., 0., 1.)), s.x);
+    let x11 = mix(hash(i + vec3(0., 1., 1.)), hash(i + vec3(1., 1., 1.)), s.x);
+    return mix(mix(x00, x10, s.y), mix(x01, x11, s.y), s.z);
+}
+
+fn fractal_noise(p: vec3<f32>) -> f32 {
+    var sum = 0.;
+    var amplitude = 0.5;
+    var frequency = 1.;
+    for (var i = 0; i < 4; i++) {
+        sum += amplitude * value_noise(p * frequency);
+        amplitude /= 2.;
+        frequency *= 2.;
+    }
+    return sum / 0.9375;
+}
+
+fn torus_half_height() -> f32 {
+    return accretion_torus_thickness * accretion_disc_width / 2.;
+}
+
+// gas density, 1 on the ring halfway across the disc and falling to 0 on the ellipse around it,
+// which is as wide as the disc; the noise turns with the gas like the texture of the disc does
+fn torus_density(point: vec3<f32>, omega: f32) -> f32 {
+    let half_width = accretion_disc_width / 2.;
+    let radius = length(point.xz);
+    let across = (radius - accretion_disc_r - half_width) / half_width;
+    let up = point.y / torus_half_height();
+    let q = across * across + up * up;
+    if q >= 1. {
+        return 0.;
+    }
+
+    var density = (1. - q) * (1. - q);
+    if accretion_torus_noise > 0. {
+        let angle = atan2(point.z, point.x) + (omega * accretion_disc_time) % (2 * PI);
+        let carried = vec3(radius * cos(angle), point.y, radius * sin(angle));
+        density *= max(mix(1., 2. * fractal_noise(carried), accretion_torus_noise), 0.);
+    }
+    return density;
+}
+
+// cheap test for the steps of the ray that pass too far from the torus to be sampled
+fn segment_near_torus(start: vec3<f32>, end: vec3<f32>) -> bool {
+    let half_width = accretion_disc_width / 2.;
+    let mid = (start + end) / 2.;
+    let offset = vec2(length(mid.xz) - accretion_disc_r - half_width, mid.y);
+    return length(offset) < length(end - start) / 2. + max(half_width, torus_half_height());
+}
+
+struct TorusStep {
+    color: vec4<f32>,
+    transmittance: f32,
+}
+
+// light emitted over a piece ds of the ray at the point and the fraction of the light from behind that gets through,
+// for gas seen with g = observed / emitted frequency; the gas glows like the thin disc below it,
+// spread over the height of the torus
+fn integrate_torus(point: vec3<f32>, omega: f32, g: f32, ds: f32) -> TorusStep {
+    let density = torus_density(point, omega);
+    if density <= 0. || g <= 0. {
+        return TorusStep(vec4(0., 0., 0., 0.), 1.);
+    }
+
+    let emission = shade_accretion_disc(vec3(point.x, 0., point.z), omega, g).rgb * density / torus_half_height();
+
+    // the light emitted along the piece is partly absorbed before it leaves it
+    let optical_depth = accretion_torus_opacity * density * ds;
+    var emitted = emission * ds;
+    if optical_depth > 1e-4 {
+        emitted *= (1. - exp(-optical_depth)) / optical_depth;
+    }
+    return TorusStep(vec4(emitted, 0.), exp(-optical_depth));
+}
+/* #endregion */
+
+/* #region jets */
+// samples of the jets on each step of the ray
+const JET_SAMPLES = 4;
+const JET_COLOR = vec3(0.5, 0.7, 1.);
+
+fn jets_enabled() -> bool {
+    return jet_emissivity > 0. && jet_opening_angle > 0. && jet_length > 0.;
+}
+
+// brightest on the axis, fading towards the walls of the cones and their far ends
+fn jet_density(point: vec3<f32>) -> f32 {
+    let height = abs(point.y);
+    if height <= 0. || height >= jet_length {
+        return 0.;
+    }
+
+    let across = length(point.xz) / (height * tan(jet_opening_angle));
+    if across >= 1. {
+        return 0.;
+    }
+    return (1. - across * across) * (1. - height / jet_length);
+}
+
+// cheap test for the steps of the ray that pass too far from the jets to be sampled
+fn segment_near_jets(start: vec3<f32>, end: vec3<f32>) -> bool {
+    let mid = (start + end) / 2.;
+    let reach = length(end - start) / 2.;
+    return length(mid.xz) < jet_length * tan(jet_opening_angle) + reach && abs(mid.y) < jet_length + reach;
+}
+
+// light emitted over a piece ds of the ray at the point, static_shift is the observed over emitted frequency
+// for an emitter at rest there; the gas streams away from the black hole along the axis and the photon
+// leaves against the ray, so the jet coming towards the camera is boosted and the other one dimmed
+fn integrate_jets(point: vec3<f32>, ray: vec3<f32>, static_shift: f32, ds: f32) -> vec4<f32> {
+    let density = jet_density(point);
+    if density <= 0. || static_shift <= 0. {
+        return vec4(0., 0., 0., 0.);
+    }
+
+    let beta = sqrt(1. - 1. / (jet_lorentz_factor * jet_lorentz_factor));
+    let cos_angle = -ray.y * sign(point.y);
+    let doppler = 1. / (jet_lorentz_factor * (1. - beta * cos_angle));
+    return frequency_shift(vec4(JET_COLOR * jet_emissivity * density * ds, 0.), static_shift * doppler);
+}
+/* #endregion */
+
+// frequency seen by the camera over the one seen by the observer at rest, for the ray in the frame of the latter
+fn camera_frequency_shift(ray: vec3<f32>) -> f32 {
+    let gamma = 1. / sqrt(1. - dot(cam_velocity, cam_velocity));
+    return gamma * (1. + dot(cam_velocity, ray));
+}
+
+// the direction the ray has for the observer at rest, the photon arriving against it is boosted out of the camera frame
+fn aberrate(ray: vec3<f32>, velocity: vec3<f32>) -> vec3<f32> {
+    let v2 = dot(velocity, velocity);
+    if v2 == 0. {
+        return ray;
+    }
+
+    let gamma = 1. / sqrt(1. - v2);
+    let n = -ray;
+    let n_parallel = dot(n, velocity) / v2 * velocity;
+    return -normalize(n + (gamma - 1.) * n_parallel + gamma * velocity);
+}
+
+fn construct_ray(uv: vec2<f32>) -> vec3<f32> {
+    let fov_mult = 1. / tan(fov / 2.);
+    return aberrate(normalize((uv.x * cam_x) + (uv.y * cam_y) + (fov_mult * cam_z)), cam_velocity);
+}
@@ -0,0 +1,329 @@
+#import "shaders/common.wgsl"::{
+    PI, spacetime, cam_pos, accretion_disc_shape, UP, ray_plane_intersect,
+    sample_skybox, shade_accretion_disc, sky_direction, frequency_shift, camera_frequency_shift,
+    segment_near_torus, integrate_torus, TORUS_SAMPLES, step_budget_scale,
+    jets_enabled, segment_near_jets, integrate_jets, JET_SAMPLES,
+}
+
+// kerr rays are integrated in the affine parameter with steps proportional to r, for the default step budget
+const KERR_STEP_CNT = 500;
+const KERR_STEP_FACTOR = 0.03;
+const KERR_ESCAPE_R = 100.;
+
+// kerr-newman-de sitter in carter's form, reduces to kerr for q = lambda = 0;
+// the rays are integrated in ingoing coordinates (v, r, theta, phi) with
+// dv = dt + xi (r^2 + a^2) / delta dr and dphi = dphi_BL + xi a / delta dr, which stay regular at the horizons.
+// r and theta are the Boyer-Lindquist ones, the spin axis is +y and phi is measured right-handedly about it
+fn to_boyer_lindquist(p: vec3<f32>) -> vec3<f32> {
+    let a2 = spacetime.a * spacetime.a;
+    let w = dot(p, p) - a2;
+    let r = sqrt((w + sqrt(w * w + 4. * a2 * p.y * p.y)) / 2.);
+    let theta = acos(clamp(p.y / r, -1., 1.));
+    let phi = atan2(-p.z, p.x);
+    return vec3(r, theta, phi);
+}
+
+fn from_boyer_lindquist(c: vec3<f32>) -> vec3<f32> {
+    let big_r = sqrt(c.x * c.x + spacetime.a * spacetime.a);
+    return vec3(big_r * sin(c.y) * cos(c.z), c.x * cos(c.y), -big_r * sin(c.y) * sin(c.z));
+}
+
+fn kerr_delta(r: f32) -> f32 {
+    let a2 = spacetime.a * spacetime.a;
+    return (r * r + a2) * (1. - spacetime.lambda * r * r / 3.) - 2. * r + spacetime.q * spacetime.q;
+}
+
+fn kerr_delta_derivative(r: f32) -> f32 {
+    let a2 = spacetime.a * spacetime.a;
+    return 2. * r * (1. - spacetime.lambda * r * r / 3.) - 2. * spacetime.lambda * r * (r * r + a2) / 3. - 2.;
+}
+
+fn kerr_delta_theta(cos_theta: f32) -> f32 {
+    return 1. + spacetime.lambda * spacetime.a * spacetime.a * cos_theta * cos_theta / 3.;
+}
+
+fn kerr_xi() -> f32 {
+    return 1. + spacetime.lambda * spacetime.a * spacetime.a / 3.;
+}
+
+// angular velocity of prograde circular orbits in the equatorial plane
+fn kerr_keplerian_angular_velocity(r: f32) -> f32 {
+    let k = sqrt(max((r - spacetime.q * spacetime.q) / (r * r * r * r) - spacetime.lambda / 3., 0.));
+    return k / (1. + spacetime.a * k);
+}
+
+// emitted over received energy of a photon with the angular momentum l over energy, for an emitter circling
+// the spin axis at (r, theta) with the angular velocity omega; 0 where that would be faster than light
+fn kerr_emitter_shift(r: f32, theta: f32, omega: f32, l: f32) -> f32 {
+    let a = spacetime.a;
+    let cos_theta = cos(theta);
+    let sin2 = 1. - cos_theta * cos_theta;
+    let delta = kerr_delta(r);
+    let delta_theta = kerr_delta_theta(cos_theta);
+    let big_a = r * r + a * a;
+    let xi2_sigma = kerr_xi() * kerr_xi() * (r * r + a * a * cos_theta * cos_theta);
+    let g_tt = -(delta - delta_theta * a * a * sin2) / xi2_sigma;
+    let g_tphi = a * sin2 * (delta - delta_theta * big_a) / xi2_sigma;
+    let g_phiphi = sin2 * (delta_theta * big_a * big_a - delta * a * a * sin2) / xi2_sigma;
+
+    let norm = -(g_tt + 2. * g_tphi * omega + g_phiphi * omega * omega);
+    if norm <= 0. {
+        return 0.;
+    }
+    return sqrt(norm) / (1. - omega * l);
+}
+
+// sigma g^{mu nu} alpha_mu beta_nu for covectors (v, r, theta, phi) in ingoing coordinates
+fn kerr_sigma_dot(alpha: vec4<f32>, beta: vec4<f32>, r: f32, theta: f32) -> f32 {
+    let a = spacetime.a;
+    let sin_theta = sin(theta);
+    let cos_theta = cos(theta);
+    let sin2 = max(sin_theta * sin_theta, 1e-8);
+    let delta_theta = kerr_delta_theta(cos_theta);
+    let xi = kerr_xi();
+    let big_a = r * r + a * a;
+
+    let g_vv = xi * xi * a * a * sin2 / delta_theta;
+    let g_vr = xi * big_a;
+    let g_vphi = xi * xi * a / delta_theta;
+    let g_rr = kerr_delta(r);
+    let g_rphi = xi * a;
+    let g_thetatheta = delta_theta;
+    let g_phiphi = xi * xi / (delta_theta * sin2);
+
+    return g_vv * alpha.x * beta.x
+        + g_vr * (alpha.x * beta.y + alpha.y * beta.x)
+        + g_vphi * (alpha.x * beta.w + alpha.w * beta.x)
+        + g_rr * alpha.y * beta.y
+        + g_rphi * (alpha.y * beta.w + alpha.w * beta.y)
+        + g_thetatheta * alpha.z * beta.z
+        + g_phiphi * alpha.w * beta.w;
+}
+
+// photon with E = 1, x = (r, theta, phi), p = (p_r, p_theta)
+struct GeodesicState {
+    x: vec3<f32>,
+    p: vec2<f32>,
+}
+
+fn geodesic_add(s: GeodesicState, d: GeodesicState, h: f32) -> GeodesicState {
+    return GeodesicState(s.x + d.x * h, s.p + d.p * h);
+}
+
+// hamilton's equations for H = K / (2 sigma), using K = 0 along null geodesics, where
+// K = delta p_r^2 - 2 xi P p_r + delta_theta p_theta^2 + xi^2 W^2 / (delta_theta sin^2)
+fn kerr_derivative(s: GeodesicState, l: f32) -> GeodesicState {
+    let a = spacetime.a;
+    let r = s.x.x;
+    let sin_theta = sin(s.x.y);
+    let cos_theta = cos(s.x.y);
+    // keeps the sign, so that the equations continue smoothly over the poles
+    let sin_safe = select(-1., 1., sin_theta >= 0.) * max(abs(sin_theta), 1e-4);
+    let sin2 = sin_safe * sin_safe;
+
+    let sigma = r * r + a * a * cos_theta * cos_theta;
+    let delta = kerr_delta(r);
+    let d_delta = kerr_delta_derivative(r);
+    let delta_theta = kerr_delta_theta(cos_theta);
+    let d_delta_theta = -2. * spacetime.lambda * a * a * cos_theta * sin_theta / 3.;
+    let xi = kerr_xi();
+    let xi2 = xi * xi;
+    let big_p = r * r + a * a - a * l;
+    let w = l - a * sin2;
+
+    let dr = (delta * s.p.x - xi * big_p) / sigma;
+    let dtheta = delta_theta * s.p.y / sigma;
+    let dphi = (xi * a * s.p.x + xi2 * w / (delta_theta * sin2)) / sigma;
+
+    let dk_dr = d_delta * s.p.x * s.p.x - 4. * xi * r * s.p.x;
+    let dk_dtheta = d_delta_theta * s.p.y * s.p.y
+        - xi2 * w * (4. * a * sin2 * cos_theta * delta_theta + w * d_delta_theta * sin_safe + 2. * w * delta_theta * cos_theta)
+        / (delta_theta * delta_theta * sin2 * sin_safe);
+    let dp_r = -dk_dr / (2. * sigma);
+    let dp_theta = -dk_dtheta / (2. * sigma);
+
+    return GeodesicState(vec3(dr, dtheta, dphi), vec2(dp_r, dp_theta));
+}
+
+fn kerr_rk4(s: GeodesicState, l: f32, h: f32) -> GeodesicState {
+    let k1 = kerr_derivative(s, l);
+    let k2 = kerr_derivative(geodesic_add(s, k1, h / 2.), l);
+    let k3 = kerr_derivative(geodesic_add(s, k2, h / 2.), l);
+    let k4 = kerr_derivative(geodesic_add(s, k3, h), l);
+
+    return GeodesicState(
+        s.x + h / 6. * (k1.x + 2. * k2.x + 2. * k3.x + k4.x),
+        s.p + h / 6. * (k1.p + 2. * k2.p + 2. * k3.p + k4.p),
+    );
+}
+
+struct KerrRay {
+    state: GeodesicState,
+    l: f32,
+    shift: f32, // frequency seen by the observer over the one at infinity
+    valid: bool, // false when the photon can't be traced back into our universe
+}
+
+// 4-velocity of the observer at the camera as a covector in ingoing coordinates;
+// the zero angular momentum observer outside of the horizon, between the horizons, where it doesn't exist,
+// the one falling in from rest at infinity with zero angular momentum, which is regular across the horizon
+fn kerr_observer(r: f32, theta: f32) -> vec4<f32> {
+    let a = spacetime.a;
+    let delta = kerr_delta(r);
+
+    if delta > 0. {
+        // -alpha dt, where dt = dv - xi (r^2 + a^2) / delta dr
+        let dt = vec4(1., -kerr_xi() * (r * r + a * a) / delta, 0., 0.);
+        let sigma = r * r + a * a * cos(theta) * cos(theta);
+        let alpha = sqrt(-sigma / kerr_sigma_dot(dt, dt, r, theta));
+        return -alpha * dt;
+    }
+
+    // u_r solves the normalization delta u_r^2 - 2 xi (r^2 + a^2) u_r + c = 0
+    let sin_theta = sin(theta);
+    let cos_theta = cos(theta);
+    let xi = kerr_xi();
+    let big_a = r * r + a * a;
+    let c = xi * xi * a * a * sin_theta * sin_theta / kerr_delta_theta(cos_theta) + r * r + a * a * cos_theta * cos_theta;
+    let u_r = c / (xi * big_a + sqrt(max(xi * xi * big_a * big_a - delta * c, 0.)));
+    return vec4(-1., u_r, 0., 0.);
+}
+
+// the ray direction is taken in the orthonormal frame of the observer at the camera,
+// built by gram-schmidt from the observer and dr, dtheta, dphi
+fn kerr_initial_ray(pos: vec3<f32>, dir: vec3<f32>) -> KerrRay {
+    let x = to_boyer_lindquist(pos);
+    let r = x.x;
+    let theta = x.y;
+    let a = spacetime.a;
+    let sigma = r * r + a * a * cos(theta) * cos(theta);
+
+    let u = kerr_observer(r, theta);
+
+    var e_r = vec4(0., 1., 0., 0.);
+    e_r += kerr_sigma_dot(e_r, u, r, theta) / sigma * u;
+    e_r /= sqrt(kerr_sigma_dot(e_r, e_r, r, theta) / sigma);
+
+    var e_theta = vec4(0., 0., 1., 0.);
+    e_theta += kerr_sigma_dot(e_theta, u, r, theta) / sigma * u;
+    e_theta -= kerr_sigma_dot(e_theta, e_r, r, theta) / sigma * e_r;
+    e_theta /= sqrt(kerr_sigma_dot(e_theta, e_theta, r, theta) / sigma);
+
+    var e_phi = vec4(0., 0., 0., 1.);
+    e_phi += kerr_sigma_dot(e_phi, u, r, theta) / sigma * u;
+    e_phi -= kerr_sigma_dot(e_phi, e_r, r, theta) / sigma * e_r;
+    e_phi -= kerr_sigma_dot(e_phi, e_theta, r, theta) / sigma * e_theta;
+    e_phi /= sqrt(kerr_sigma_dot(e_phi, e_phi, r, theta) / sigma);
+
+    // cartesian directions of the frame, the same as the coordinate directions far away
+    let sin_theta = sin(theta);
+    let cos_theta = cos(theta);
+    let sin_phi = sin(x.z);
+    let cos_phi = cos(x.z);
+    let big_r = sqrt(r * r + a * a);
+    let n_r = normalize(vec3(r / big_r * sin_theta * cos_phi, cos_theta, -r / big_r * sin_theta * sin_phi));
+    let n_theta = normalize(vec3(big_r * cos_theta * cos_phi, -r * sin_theta, -big_r * cos_theta * sin_phi));
+    let n_phi = vec3(-sin_phi, 0., -cos_phi);
+
+    // we trace the photon that arrives at the camera backwards, so it is moving against the ray
+    let n = -dir;
+    let p = u + dot(n, n_r) * e_r + dot(n, n_theta) * e_theta + dot(n, n_phi) * e_phi;
+
+    // photons with negative energy inside the horizon come out of the white hole, not from our sky
+    let e = -p.x;
+    if e <= 0. {
+        return KerrRay(GeodesicState(x, vec2(0., 0.)), 0., 0., false);
+    }
+
+    return KerrRay(GeodesicState(x, p.yz / e), p.w / e, 1. / e, true);
+}
+
+fn trace(ray_: vec3<f32>) -> vec4<f32> {
+    var ray = ray_;
+    var out_color = vec4(0., 0., 0., 1.);
+
+    let kerr_ray = kerr_initial_ray(cam_pos, ray);
+    if !kerr_ray.valid {
+        return out_color;
+    }
+
+    var state = kerr_ray.state;
+    let l = kerr_ray.l;
+    let sky_shift = camera_frequency_shift(ray_) * kerr_ray.shift;
+    let horizon = spacetime.horizon;
+    var escape_r = max(KERR_ESCAPE_R, 2. * state.x.x);
+    if spacetime.cosmological_horizon > 0. {
+        escape_r = spacetime.cosmological_horizon * 0.99;
+    }
+
+    var prev_pos = cam_pos;
+    var pos = cam_pos;
+    var transmittance = 1.; // of the torus in front
+    // a larger step budget buys proportionally more and shorter steps
+    let step_cnt = i32(f32(KERR_STEP_CNT) * step_budget_scale());
+    let step_factor = KERR_STEP_FACTOR / step_budget_scale();
+    for (var i = 0; i < step_cnt; i++) {
+        let prev_r = state.x.x;
+        state = kerr_rk4(state, l, -step_factor * prev_r);
+
+        // traced back in time, rays only approach the horizon from outside, asymptotically
+        if state.x.x <= horizon * 1.01 && state.x.x < prev_r {
+            return out_color;
+        }
+
+        // p_r diverges on rays that come from the past horizon
+        if !(abs(state.p.x) < 1e6) {
+            return out_color;
+        }
+
+        prev_pos = pos;
+        pos = from_boyer_lindquist(state.x);
+        ray = normalize(pos - prev_pos);
+
+        if accretion_disc_shape == 0u && sign(prev_pos.y) != sign(pos.y) {
+            let point = ray_plane_intersect(ray, prev_pos, UP, vec3(0., 0., 0.));
+            if point.intersects {
+                // the disc is made of emitters on keplerian orbits
+                let r = to_boyer_lindquist(point.point).x;
+                let omega = kerr_keplerian_angular_velocity(r);
+                let disc_shift = sky_shift * kerr_emitter_shift(r, PI / 2., omega, l);
+                if disc_shift > 0. {
+                    out_color += shade_accretion_disc(point.point, omega, disc_shift);
+                }
+            }
+        }
+
+        if accretion_disc_shape == 1u && segment_near_torus(prev_pos, pos) {
+            let ds = length(pos - prev_pos) / f32(TORUS_SAMPLES);
+            for (var k = 0; k < TORUS_SAMPLES; k++) {
+                let point = mix(prev_pos, pos, (f32(k) + 0.5) / f32(TORUS_SAMPLES));
+                // the gas turns on cylinders, as fast as the keplerian orbit in the plane below
+                let x = to_boyer_lindquist(point);
+                let omega = kerr_keplerian_angular_velocity(x.x * sin(x.y));
+                let torus = integrate_torus(point, omega, sky_shift * kerr_emitter_shift(x.x, x.y, omega, l), ds);
+                out_color += transmittance * torus.color;
+                transmittance *= torus.transmittance;
+            }
+        }
+
+        if jets_enabled() && segment_near_jets(prev_pos, pos) {
+            let ds = length(pos - prev_pos) / f32(JET_SAMPLES);
+            for (var k = 0; k < JET_SAMPLES; k++) {
+                let point = mix(prev_pos, pos, (f32(k) + 0.5) / f32(JET_SAMPLES));
+                let x = to_boyer_lindquist(point);
+                out_color += transmittance * integrate_jets(point, ray, sky_shift * kerr_emitter_shift(x.x, x.y, 0., l), ds);
+            }
+        }
+
+        if state.x.x > escape_r && state.x.x > prev_r {
+            break;
+        }
+    }
+
+    // still inside of the black hole
+    if state.x.x < horizon {
+        return out_color;
+    }
+
+    return out_color + transmittance * frequency_shift(sample_skybox(sky_direction(ray, pos)), sky_shift);
+}
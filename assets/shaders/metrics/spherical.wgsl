@@ -0,0 +1,341 @@
+#import "shaders/common.wgsl"::{
+    spacetime, cam_pos, cam_z, accretion_disc_r, accretion_disc_width, accretion_disc_shape,
+    PI, step_budget, max_orbits, default_step_size, IntegrationStep, integrator, INTEGRATOR_TOLERANCE, ray_plane_intersect,
+    sample_skybox, shade_accretion_disc, sky_direction, frequency_shift, camera_frequency_shift,
+    segment_near_torus, integrate_torus, TORUS_SAMPLES,
+    jets_enabled, segment_near_jets, integrate_jets, JET_SAMPLES,
+    deflection_table, deflection_table_r,
+}
+
+// reissner-nordstrom orbit equation, reduces to schwarzschild for q = 0; the cosmological constant drops out
+fn second_derivative(u: f32) -> f32 {
+    return u * (3. * u - 1.) - 2. * spacetime.q * spacetime.q * u * u * u;
+}
+
+// 1 - 2 / r + q^2 / r^2 - lambda r^2 / 3, the static observers only exist where it is positive
+fn f(u: f32) -> f32 {
+    return 1. - 2. * u + spacetime.q * spacetime.q * u * u - spacetime.lambda / (3. * u * u);
+}
+
+// angular velocity of circular orbits, reissner-nordstrom-de sitter has the same one as in newtonian gravity
+// with the charge and the cosmological constant as extra forces
+fn keplerian_angular_velocity(u: f32) -> f32 {
+    return sqrt(max(u * u * u - spacetime.q * spacetime.q * u * u * u * u - spacetime.lambda / 3., 0.));
+}
+
+// emitted over received energy of a photon with the angular momentum l over energy, for an emitter circling
+// the spin axis at the point with the angular velocity omega; 0 where that would be faster than light
+fn emitter_shift(point: vec3<f32>, omega: f32, l: f32) -> f32 {
+    let norm = f(1. / length(point)) - omega * omega * dot(point.xz, point.xz);
+    if norm <= 0. {
+        return 0.;
+    }
+    return sqrt(norm) / (1. - omega * l);
+}
+
+fn euler(u: f32, v: f32, delta: f32) -> IntegrationStep {
+    let a = second_derivative(u);
+
+    let new_v = v + a * delta;
+    let new_u = u + v * delta;
+
+    return IntegrationStep(new_u, new_v);
+}
+
+fn leapfrog(u: f32, v: f32, delta: f32) -> IntegrationStep {
+    let v_intermediate = v + second_derivative(u) * delta / 2;
+    let new_u = u + v_intermediate * delta;        
+    let new_v = v_intermediate + second_derivative(new_u) * delta / 2;
+
+    return IntegrationStep(new_u, new_v);
+}
+
+// steps in phi shrink where the orbit bends away from a straight line, for which u'' + u = 0,
+// and grow far away, where it hardly bends at all
+const BENDING_GAIN = 4.;
+
+fn bending_step_size(u: f32) -> f32 {
+    let bending = abs(second_derivative(u) + u) / max(u, 1e-6);
+    return max(2. * default_step_size() / (1. + BENDING_GAIN * bending), default_step_size() / 16.);
+}
+
+// (u, v)' of the orbit equation
+fn orbit_derivative(y: vec2<f32>) -> vec2<f32> {
+    return vec2(y.y, second_derivative(y.x));
+}
+
+fn rk4(u: f32, v: f32, delta: f32) -> IntegrationStep {
+    let y = vec2(u, v);
+    let k1 = orbit_derivative(y);
+    let k2 = orbit_derivative(y + k1 * delta / 2.);
+    let k3 = orbit_derivative(y + k2 * delta / 2.);
+    let k4 = orbit_derivative(y + k3 * delta);
+    let new_y = y + (k1 + 2. * k2 + 2. * k3 + k4) * delta / 6.;
+
+    return IntegrationStep(new_y.x, new_y.y);
+}
+
+struct DormandPrinceStep {
+    step: IntegrationStep,
+    error: vec2<f32>, // difference of the 5th and 4th order solutions
+}
+
+fn dormand_prince(u: f32, v: f32, delta: f32) -> DormandPrinceStep {
+    let y = vec2(u, v);
+    let k1 = orbit_derivative(y);
+    let k2 = orbit_derivative(y + delta * (k1 / 5.));
+    let k3 = orbit_derivative(y + delta * (3. / 40. * k1 + 9. / 40. * k2));
+    let k4 = orbit_derivative(y + delta * (44. / 45. * k1 - 56. / 15. * k2 + 32. / 9. * k3));
+    let k5 = orbit_derivative(y + delta * (19372. / 6561. * k1 - 25360. / 2187. * k2 + 64448. / 6561. * k3 - 212. / 729. * k4));
+    let k6 = orbit_derivative(y + delta * (9017. / 3168. * k1 - 355. / 33. * k2 + 46732. / 5247. * k3 + 49. / 176. * k4 - 5103. / 18656. * k5));
+    let new_y = y + delta * (35. / 384. * k1 + 500. / 1113. * k3 + 125. / 192. * k4 - 2187. / 6784. * k5 + 11. / 84. * k6);
+    let k7 = orbit_derivative(new_y);
+
+    let error = delta * (71. / 57600. * k1 - 71. / 16695. * k3 + 71. / 1920. * k4 - 17253. / 339200. * k5 + 22. / 525. * k6 - 1. / 40. * k7);
+    return DormandPrinceStep(IntegrationStep(new_y.x, new_y.y), error);
+}
+
+struct OrbitStep {
+    step: IntegrationStep,
+    delta: f32, // the step in phi that was taken
+    next_delta: f32, // the one to try next
+}
+
+// the step size of dormand-prince follows the error estimate, the others keep theirs
+fn integrate_step(u: f32, v: f32, delta: f32) -> OrbitStep {
+    switch integrator {
+        case 0u: {
+            return OrbitStep(euler(u, v, delta), delta, delta);
+        }
+        case 2u: {
+            return OrbitStep(rk4(u, v, delta), delta, delta);
+        }
+        case 3u: {
+            var h = delta;
+            var attempt = dormand_prince(u, v, h);
+            for (var i = 0; i < 8; i++) {
+                let scale = INTEGRATOR_TOLERANCE * (abs(vec2(u, v)) + 1e-3);
+                let error = max(max(abs(attempt.error.x) / scale.x, abs(attempt.error.y) / scale.y), 1e-6);
+                if error <= 1. {
+                    return OrbitStep(attempt.step, h, h * min(0.9 * pow(error, -0.2), 5.));
+                }
+                h *= max(0.9 * pow(error, -0.25), 0.2);
+                attempt = dormand_prince(u, v, h);
+            }
+            return OrbitStep(attempt.step, h, h);
+        }
+        default: {
+            return OrbitStep(leapfrog(u, v, delta), delta, delta);
+        }
+    }
+}
+
+/* #region deflection table */
+const DEFLECTION_TABLE_PHI_MAX = 4. * PI; // see deflection::TABLE_PHI_MAX
+
+// u on the orbit of the rays leaving with the angle alpha to the radial direction, bilinear in the table
+fn deflection_table_u(alpha: f32, phi: f32) -> f32 {
+    let size = textureDimensions(deflection_table);
+    let rows = size.y - 1u;
+
+    let x = clamp(alpha / PI * f32(size.x) - 0.5, 0., f32(size.x - 1u));
+    let y = clamp(phi / DEFLECTION_TABLE_PHI_MAX * f32(rows - 1u), 0., f32(rows - 1u));
+    let x0 = u32(x);
+    let y0 = u32(y);
+    let x1 = min(x0 + 1u, size.x - 1u);
+    let y1 = min(y0 + 1u, rows - 1u);
+
+    let bottom = mix(textureLoad(deflection_table, vec2(x0, y0 + 1u), 0).x, textureLoad(deflection_table, vec2(x1, y0 + 1u), 0).x, x - f32(x0));
+    let top = mix(textureLoad(deflection_table, vec2(x0, y1 + 1u), 0).x, textureLoad(deflection_table, vec2(x1, y1 + 1u), 0).x, x - f32(x0));
+    return mix(bottom, top, y - f32(y0));
+}
+
+// the end of the orbit like the first row of the table, linear in alpha between columns that end the same way and
+// from the nearer one at the edge of the shadow, where one is captured and the other one isn't
+fn deflection_table_end(alpha: f32) -> vec2<f32> {
+    let size = textureDimensions(deflection_table);
+
+    let x = clamp(alpha / PI * f32(size.x) - 0.5, 0., f32(size.x - 1u));
+    let x0 = u32(x);
+    let x1 = min(x0 + 1u, size.x - 1u);
+    let left = textureLoad(deflection_table, vec2(x0, 0u), 0).xy;
+    let right = textureLoad(deflection_table, vec2(x1, 0u), 0).xy;
+
+    if left.y != right.y {
+        return select(left, right, x - f32(x0) >= 0.5);
+    }
+    return vec2(mix(left.x, right.x, x - f32(x0)), left.y);
+}
+
+// the same as the loop in trace for a schwarzschild camera outside of the horizon, but with the orbit looked up;
+// the disc is crossed where the plane of the orbit meets the equatorial plane
+fn trace_table(ray: vec3<f32>, cam_normal: vec3<f32>, cam_tangent: vec3<f32>, sky_shift: f32, l: f32) -> vec4<f32> {
+    var out_color = vec4(0., 0., 0., 1.);
+
+    let alpha = acos(clamp(dot(ray, cam_normal), -1., 1.));
+    let end = deflection_table_end(alpha);
+
+    // y along the orbit goes as cos(phi - beta), the crossings from the side of the camera to the other one,
+    // the only ones trace counts, are a full turn apart
+    let accretion_disc_max_r = accretion_disc_r + accretion_disc_width;
+    if cam_pos.y != 0. {
+        let beta = atan2(cam_tangent.y, cam_normal.y);
+        // the first zero after the camera, y has the sign of the camera's until there
+        for (var phi = beta + PI / 2. - PI * floor((beta + PI / 2.) / PI); phi < end.x; phi += 2. * PI) {
+            let u = deflection_table_u(alpha, phi);
+            let point = (cos(phi) * cam_normal + sin(phi) * cam_tangent) / u;
+
+            // the same condition as in trace, which keeps the disc from appearing behind the camera
+            if dot(-normalize(cam_pos), cam_z) > 0. || u > 1. / accretion_disc_max_r {
+                let omega = keplerian_angular_velocity(u);
+                let disc_shift = sky_shift * emitter_shift(point, omega, l);
+                if disc_shift > 0. {
+                    out_color += shade_accretion_disc(point, omega, disc_shift);
+                }
+            }
+        }
+    }
+
+    if end.y > 0.5 {
+        return out_color;
+    }
+
+    let direction = cos(end.x) * cam_normal + sin(end.x) * cam_tangent;
+    return out_color + frequency_shift(sample_skybox(direction), sky_shift);
+}
+/* #endregion */
+
+// spherically symmetric spacetimes, the orbit stays in the plane of the camera position and the ray
+fn trace(ray_: vec3<f32>) -> vec4<f32> {
+    var ray = ray_;
+
+    let cam_normal = normalize(cam_pos);
+    let cam_tangent = normalize(cross(cross(cam_normal, ray), cam_normal));
+
+    let u0 = 1. / length(cam_pos);
+    var v0 = -u0 * (dot(ray, cam_normal) / dot(ray, cam_tangent));
+
+    var prev_pos = cam_pos;
+    var pos = cam_pos;
+    var phi: f32 = 0.;
+    var out_color = vec4(0., 0., 0., 1.);
+    var transmittance = 1.; // of the torus in front
+
+    let horizon_u = 1. / spacetime.horizon;
+
+    // frequency at the camera over the one at infinity, the static observer sees it shifted by 1 / sqrt(f)
+    var sky_shift = camera_frequency_shift(ray) / sqrt(f(u0));
+
+    // inside of the horizon the camera falls radially with unit energy (from rest at infinity for lambda = 0),
+    // the ray is aberrated by its velocity; where f > 1 inside of the inner horizon of a charged hole it would have
+    // turned around already and is at rest
+    if u0 >= horizon_u {
+        let fall_speed = sqrt(max(1. - f(u0), 0.));
+
+        // photons with negative energy come out of the white hole, not from our sky
+        let energy = 1. + dot(ray, cam_normal) * fall_speed;
+        if energy <= 0. {
+            return out_color;
+        }
+        sky_shift = camera_frequency_shift(ray) / energy;
+
+        v0 = u0 * (-fall_speed - dot(ray, cam_normal)) / dot(ray, cam_tangent);
+    }
+
+    var u = u0;
+    var v = v0;
+
+    // angular momentum about the spin axis over the energy of the photon, which moves against the traced orbit
+    let impact_parameter = 1. / sqrt(max(v0 * v0 + u0 * u0 * f(u0), 1e-12));
+    let l = -impact_parameter * cross(cam_normal, cam_tangent).y;
+
+    // schwarzschild orbits seen from a static camera are looked up once the table is switched on, in place of the
+    // integrator, step budget and max orbits, the volumes still need the full march
+    if deflection_table_r > 0. && u0 < horizon_u && accretion_disc_shape == 0u && !jets_enabled() {
+        return trace_table(ray, cam_normal, cam_tangent, sky_shift, l);
+    }
+
+    let accretion_disc_max_r = accretion_disc_r + accretion_disc_width;
+
+    var step_size = bending_step_size(u0);
+
+    var escape_u = 0.;
+    if spacetime.cosmological_horizon > 0. {
+        escape_u = 1. / spacetime.cosmological_horizon;
+
+        // there is no static observer beyond the cosmological horizon
+        if u0 <= escape_u {
+            return out_color;
+        }
+    }
+
+    for (var i = 0; i < i32(step_budget); i++) {
+        // the rays that are falling in, inside of the horizon they can only go on falling
+        if u >= horizon_u && v > 0. {
+            out_color += vec4(0., 0., 0., 1.);
+            return out_color;
+        }
+
+        if u <= escape_u || phi >= max_orbits * 2. * PI {
+            break;
+        }
+
+        prev_pos = pos;
+
+        let integration_step = integrate_step(u, v, step_size);
+        u = integration_step.step.u;
+        v = integration_step.step.v;
+        phi += integration_step.delta;
+        if integrator == 3u {
+            // long steps would skip over the disc and the torus
+            step_size = clamp(integration_step.next_delta, default_step_size() / 64., default_step_size() * 2.);
+        } else {
+            step_size = bending_step_size(u);
+        }
+        pos = (cos(phi) * cam_normal + sin(phi) * cam_tangent) / u;
+        ray = normalize(pos - prev_pos);
+
+        // accretion disc
+        if accretion_disc_shape == 0u && (
+            (
+                (cam_pos.y > 0. && prev_pos.y > 0. && pos.y < 0.) ||
+                (cam_pos.y < 0. && prev_pos.y < 0. && pos.y > 0.)
+            ) && ( // this branch prevents the accretion disc to appear behind the camera
+                dot(-normalize(cam_pos), cam_z) > 0. || u > 1. / accretion_disc_max_r
+            )
+        ) {
+            let point = ray_plane_intersect(ray, prev_pos, vec3(0., 1., 0.), vec3(0., 0., 0.)).point;
+
+            // the disc is made of emitters on keplerian orbits, which see the photon at u^t (1 - omega l) times its energy
+            let omega = keplerian_angular_velocity(1. / length(point));
+            let disc_shift = sky_shift * emitter_shift(point, omega, l);
+            if disc_shift > 0. {
+                out_color += shade_accretion_disc(point, omega, disc_shift);
+            }
+        }
+
+        // accretion torus
+        if accretion_disc_shape == 1u && segment_near_torus(prev_pos, pos) {
+            let ds = length(pos - prev_pos) / f32(TORUS_SAMPLES);
+            for (var k = 0; k < TORUS_SAMPLES; k++) {
+                let point = mix(prev_pos, pos, (f32(k) + 0.5) / f32(TORUS_SAMPLES));
+                // the gas turns on cylinders, as fast as the keplerian orbit in the plane below
+                let omega = keplerian_angular_velocity(1. / length(point.xz));
+                let torus = integrate_torus(point, omega, sky_shift * emitter_shift(point, omega, l), ds);
+                out_color += transmittance * torus.color;
+                transmittance *= torus.transmittance;
+            }
+        }
+
+        // jets
+        if jets_enabled() && segment_near_jets(prev_pos, pos) {
+            let ds = length(pos - prev_pos) / f32(JET_SAMPLES);
+            for (var k = 0; k < JET_SAMPLES; k++) {
+                let point = mix(prev_pos, pos, (f32(k) + 0.5) / f32(JET_SAMPLES));
+                out_color += transmittance * integrate_jets(point, ray, sky_shift * emitter_shift(point, 0., l), ds);
+            }
+        }
+    }
+
+    return out_color + transmittance * frequency_shift(sample_skybox(sky_direction(ray, pos)), sky_shift);
+}
@@ -0,0 +1,196 @@
+#import "shaders/common.wgsl"::{
+    PI, spacetime, cam_pos, metric_table, accretion_disc_shape, UP, ray_plane_intersect,
+    sample_skybox, shade_accretion_disc, sky_direction, frequency_shift, camera_frequency_shift,
+    segment_near_torus, integrate_torus, TORUS_SAMPLES, step_budget_scale,
+    jets_enabled, segment_near_jets, integrate_jets, JET_SAMPLES,
+}
+
+// rays are integrated in the affine parameter with steps proportional to r, for the default step budget
+const USER_STEP_CNT = 300;
+const USER_STEP_FACTOR = 0.03;
+const USER_ESCAPE_R = 100.;
+
+// bilinear interpolation in the table, r is sampled logarithmically and theta at cell centers;
+// tile 0 holds g_tt, g_rr, g_thetatheta, g_phiphi and the others the christoffel symbols
+fn table(r: f32, theta: f32, tile: u32) -> vec4<f32> {
+    let size = textureDimensions(metric_table);
+    let rows = size.y / 5u;
+
+    let x = clamp(log(r / spacetime.table_r_min) / log(spacetime.table_r_max / spacetime.table_r_min) * f32(size.x - 1u), 0., f32(size.x - 1u));
+    let y = clamp(theta / PI * f32(rows) - 0.5, 0., f32(rows - 1u));
+    let x0 = u32(x);
+    let y0 = u32(y);
+    let x1 = min(x0 + 1u, size.x - 1u);
+    let y1 = min(y0 + 1u, rows - 1u);
+    let offset = tile * rows;
+
+    let bottom = mix(textureLoad(metric_table, vec2(x0, y0 + offset), 0), textureLoad(metric_table, vec2(x1, y0 + offset), 0), x - f32(x0));
+    let top = mix(textureLoad(metric_table, vec2(x0, y1 + offset), 0), textureLoad(metric_table, vec2(x1, y1 + offset), 0), x - f32(x0));
+    return mix(bottom, top, y - f32(y0));
+}
+
+// the spin axis is +y and phi is measured right-handedly about it, same as for kerr
+fn to_spherical(p: vec3<f32>) -> vec3<f32> {
+    let r = length(p);
+    return vec3(r, acos(clamp(p.y / r, -1., 1.)), atan2(-p.z, p.x));
+}
+
+// angular velocity of the circular orbit at r in the equatorial plane, where gamma^r_tt + gamma^r_phiphi omega^2 = 0
+fn user_keplerian_angular_velocity(r: f32) -> f32 {
+    return sqrt(max(-table(r, PI / 2., 1u).z / table(r, PI / 2., 2u).z, 0.));
+}
+
+// emitted over received energy of a photon with the angular momentum l over energy, for an emitter circling
+// the spin axis at (r, theta) with the angular velocity omega; 0 where that would be faster than light
+fn user_emitter_shift(r: f32, theta: f32, omega: f32, l: f32) -> f32 {
+    let g = table(r, theta, 0u);
+    let norm = -(g.x + g.w * omega * omega);
+    if norm <= 0. {
+        return 0.;
+    }
+    return sqrt(norm) / (1. - omega * l);
+}
+
+fn from_spherical(c: vec3<f32>) -> vec3<f32> {
+    return c.x * vec3(sin(c.y) * cos(c.z), cos(c.y), -sin(c.y) * sin(c.z));
+}
+
+// x = (r, theta, phi), v = dx^mu / dlambda including t
+struct UserState {
+    x: vec3<f32>,
+    v: vec4<f32>,
+}
+
+fn user_add(s: UserState, d: UserState, h: f32) -> UserState {
+    return UserState(s.x + d.x * h, s.v + d.v * h);
+}
+
+// the geodesic equation, only the christoffel symbols of a diagonal metric depending on r and theta are non-zero
+fn user_derivative(s: UserState) -> UserState {
+    let v = s.v;
+    let c1 = table(s.x.x, s.x.y, 1u); // t_tr, t_ttheta, r_tt, r_rr
+    let c2 = table(s.x.x, s.x.y, 2u); // r_rtheta, r_thetatheta, r_phiphi, theta_tt
+    let c3 = table(s.x.x, s.x.y, 3u); // theta_rr, theta_rtheta, theta_thetatheta, theta_phiphi
+    let c4 = table(s.x.x, s.x.y, 4u); // phi_rphi, phi_thetaphi
+
+    let a_t = -2. * (c1.x * v.x * v.y + c1.y * v.x * v.z);
+    let a_r = -(c1.z * v.x * v.x + c1.w * v.y * v.y + 2. * c2.x * v.y * v.z + c2.y * v.z * v.z + c2.z * v.w * v.w);
+    let a_theta = -(c2.w * v.x * v.x + c3.x * v.y * v.y + 2. * c3.y * v.y * v.z + c3.z * v.z * v.z + c3.w * v.w * v.w);
+    let a_phi = -2. * (c4.x * v.y * v.w + c4.y * v.z * v.w);
+
+    return UserState(v.yzw, vec4(a_t, a_r, a_theta, a_phi));
+}
+
+fn user_rk4(s: UserState, h: f32) -> UserState {
+    let k1 = user_derivative(s);
+    let k2 = user_derivative(user_add(s, k1, h / 2.));
+    let k3 = user_derivative(user_add(s, k2, h / 2.));
+    let k4 = user_derivative(user_add(s, k3, h));
+
+    return UserState(
+        s.x + h / 6. * (k1.x + 2. * k2.x + 2. * k3.x + k4.x),
+        s.v + h / 6. * (k1.v + 2. * k2.v + 2. * k3.v + k4.v),
+    );
+}
+
+fn trace(ray_: vec3<f32>) -> vec4<f32> {
+    var ray = ray_;
+    var out_color = vec4(0., 0., 0., 1.);
+
+    let x = to_spherical(cam_pos);
+    let g = table(x.x, x.y, 0u);
+
+    // there is no static observer beyond a horizon
+    if g.x >= 0. || g.y <= 0. {
+        return out_color;
+    }
+
+    let sin_theta = sin(x.y);
+    let cos_theta = cos(x.y);
+    let sin_phi = sin(x.z);
+    let cos_phi = cos(x.z);
+    let e_r = normalize(cam_pos);
+    let e_theta = vec3(cos_theta * cos_phi, -sin_theta, -cos_theta * sin_phi);
+    let e_phi = vec3(-sin_phi, 0., -cos_phi);
+
+    // the direction is taken in the frame of the static observer; the photon arriving at the camera
+    // is traced backwards, which is the same curve as the geodesic leaving along the ray
+    var state = UserState(x, vec4(
+        1. / sqrt(-g.x),
+        dot(ray, e_r) / sqrt(g.y),
+        dot(ray, e_theta) / sqrt(g.z),
+        dot(ray, e_phi) / sqrt(g.w),
+    ));
+
+    // the static observer sees the frequency from infinity shifted by 1 / sqrt(-g_tt)
+    let sky_shift = camera_frequency_shift(ray) / sqrt(-g.x);
+
+    // angular momentum about the spin axis over the energy of the photon, which moves against the traced geodesic
+    let l = g.w * state.v.w / (g.x * state.v.x);
+
+    let r_min = max(spacetime.horizon * 1.01, spacetime.table_r_min);
+    var escape_r = min(max(USER_ESCAPE_R, 2. * x.x), spacetime.table_r_max * 0.99);
+    if spacetime.cosmological_horizon > 0. {
+        escape_r = spacetime.table_r_max * 0.99;
+    }
+
+    var prev_pos = cam_pos;
+    var pos = cam_pos;
+    var transmittance = 1.; // of the torus in front
+    // a larger step budget buys proportionally more and shorter steps
+    let step_cnt = i32(f32(USER_STEP_CNT) * step_budget_scale());
+    let step_factor = USER_STEP_FACTOR / step_budget_scale();
+    for (var i = 0; i < step_cnt; i++) {
+        let prev_r = state.x.x;
+        state = user_rk4(state, step_factor * prev_r);
+
+        if state.x.x <= r_min {
+            return out_color;
+        }
+
+        prev_pos = pos;
+        pos = from_spherical(state.x);
+        ray = normalize(pos - prev_pos);
+
+        if accretion_disc_shape == 0u && sign(prev_pos.y) != sign(pos.y) {
+            let point = ray_plane_intersect(ray, prev_pos, UP, vec3(0., 0., 0.));
+            if point.intersects {
+                // the disc is made of emitters on circular orbits
+                let disc_r = length(point.point);
+                let omega = user_keplerian_angular_velocity(disc_r);
+                let disc_shift = sky_shift * user_emitter_shift(disc_r, PI / 2., omega, l);
+                if disc_shift > 0. {
+                    out_color += shade_accretion_disc(point.point, omega, disc_shift);
+                }
+            }
+        }
+
+        if accretion_disc_shape == 1u && segment_near_torus(prev_pos, pos) {
+            let ds = length(pos - prev_pos) / f32(TORUS_SAMPLES);
+            for (var k = 0; k < TORUS_SAMPLES; k++) {
+                let point = mix(prev_pos, pos, (f32(k) + 0.5) / f32(TORUS_SAMPLES));
+                // the gas turns on cylinders, as fast as the circular orbit in the plane below
+                let r = length(point);
+                let omega = user_keplerian_angular_velocity(length(point.xz));
+                let torus = integrate_torus(point, omega, sky_shift * user_emitter_shift(r, acos(point.y / r), omega, l), ds);
+                out_color += transmittance * torus.color;
+                transmittance *= torus.transmittance;
+            }
+        }
+
+        if jets_enabled() && segment_near_jets(prev_pos, pos) {
+            let ds = length(pos - prev_pos) / f32(JET_SAMPLES);
+            for (var k = 0; k < JET_SAMPLES; k++) {
+                let point = mix(prev_pos, pos, (f32(k) + 0.5) / f32(JET_SAMPLES));
+                let r = length(point);
+                out_color += transmittance * integrate_jets(point, ray, sky_shift * user_emitter_shift(r, acos(point.y / r), 0., l), ds);
+            }
+        }
+
+        if state.x.x > escape_r && state.x.x > prev_r {
+            break;
+        }
+    }
+
+    return out_color + transmittance * frequency_shift(sample_skybox(sky_direction(ray, pos)), sky_shift);
+}
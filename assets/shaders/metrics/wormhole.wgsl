@@ -0,0 +1,119 @@
+#import "shaders/common.wgsl"::{
+    spacetime, cam_pos, UP, default_step_size, step_budget, IntegrationStep,
+    ray_plane_intersect, sample_skybox, sample_other_skybox, shade_accretion_disc,
+    frequency_shift, camera_frequency_shift, accretion_disc_shape,
+    segment_near_torus, integrate_torus, TORUS_SAMPLES,
+    jets_enabled, segment_near_jets, integrate_jets, JET_SAMPLES,
+}
+
+// ellis wormhole ds^2 = -dt^2 + dl^2 + (l^2 + b^2) dOmega^2 with the throat radius b;
+// both universes share one chart where the distance from the origin is |l| + b
+
+// orbit equation for u = 1 / r, with the impact parameter h
+fn wormhole_second_derivative(u: f32, h: f32) -> f32 {
+    let b = spacetime.throat_radius;
+    return -u * (1. + b * b / (h * h)) + 2. * b * b * u * u * u;
+}
+
+fn wormhole_leapfrog(u: f32, v: f32, h: f32, delta: f32) -> IntegrationStep {
+    let v_intermediate = v + wormhole_second_derivative(u, h) * delta / 2;
+    let new_u = u + v_intermediate * delta;
+    let new_v = v_intermediate + wormhole_second_derivative(new_u, h) * delta / 2;
+
+    return IntegrationStep(new_u, new_v);
+}
+
+// |l| for the areal radius 1 / u
+fn wormhole_l(u: f32) -> f32 {
+    let b = spacetime.throat_radius;
+    return sqrt(max(1. / (u * u) - b * b, 0.));
+}
+
+fn trace(ray_: vec3<f32>) -> vec4<f32> {
+    var ray = ray_;
+    let b = spacetime.throat_radius;
+
+    let cam_normal = normalize(cam_pos);
+    let cam_tangent = normalize(cross(cross(cam_normal, ray), cam_normal));
+
+    let l0 = max(length(cam_pos) - b, 0.);
+    let r0 = sqrt(l0 * l0 + b * b);
+    let cos_alpha = dot(ray, cam_normal);
+    let sin_alpha = dot(ray, cam_tangent);
+
+    // the static observer is geodesic here, so the local angle gives the impact parameter directly
+    let h = r0 * sin_alpha;
+    var u = 1. / r0;
+    var v = -l0 / (r0 * r0) * cos_alpha / sin_alpha;
+
+    // without gravity only the motion of the camera shifts the frequency
+    let shift = camera_frequency_shift(ray_);
+
+    // 1 while the ray is in the universe of the camera
+    var side = 1.;
+
+    var prev_pos = cam_pos;
+    var pos = cam_pos;
+    var phi: f32 = 0.;
+    var out_color = vec4(0., 0., 0., 1.);
+    var transmittance = 1.; // of the torus in front
+
+    // the orbit oscillates in phi with the frequency sqrt(1 + b^2 / h^2),
+    // so rays aimed at the throat need proportionally smaller steps
+    let step_size = default_step_size() / sqrt(1. + b * b / (h * h));
+
+    for (var i = 0; i < i32(step_budget); i++) {
+        if u <= 0. {
+            break;
+        }
+
+        prev_pos = pos;
+
+        let prev_v = v;
+        let integration_step = wormhole_leapfrog(u, v, h, step_size);
+        u = integration_step.u;
+        v = integration_step.v;
+        phi += step_size;
+
+        // rays with h < b only turn around at the throat, which takes them to the other side
+        if h < b && prev_v > 0. && v <= 0. {
+            side = -side;
+        }
+
+        pos = (cos(phi) * cam_normal + sin(phi) * cam_tangent) * (wormhole_l(u) + b);
+        ray = normalize(pos - prev_pos);
+
+        // the accretion disc only exists in the universe of the first skybox
+        if side * spacetime.camera_side > 0. && accretion_disc_shape == 0u && sign(prev_pos.y) != sign(pos.y) {
+            let point = ray_plane_intersect(ray, prev_pos, UP, vec3(0., 0., 0.));
+            if point.intersects {
+                // and it doesn't turn
+                out_color += shade_accretion_disc(point.point, 0., shift);
+            }
+        }
+
+        if side * spacetime.camera_side > 0. && accretion_disc_shape == 1u && segment_near_torus(prev_pos, pos) {
+            let ds = length(pos - prev_pos) / f32(TORUS_SAMPLES);
+            for (var k = 0; k < TORUS_SAMPLES; k++) {
+                let point = mix(prev_pos, pos, (f32(k) + 0.5) / f32(TORUS_SAMPLES));
+                let torus = integrate_torus(point, 0., shift, ds);
+                out_color += transmittance * torus.color;
+                transmittance *= torus.transmittance;
+            }
+        }
+
+        if side * spacetime.camera_side > 0. && jets_enabled() && segment_near_jets(prev_pos, pos) {
+            let ds = length(pos - prev_pos) / f32(JET_SAMPLES);
+            for (var k = 0; k < JET_SAMPLES; k++) {
+                let point = mix(prev_pos, pos, (f32(k) + 0.5) / f32(JET_SAMPLES));
+                out_color += transmittance * integrate_jets(point, ray, shift, ds);
+            }
+        }
+    }
+
+    if side * spacetime.camera_side > 0. {
+        return out_color + transmittance * frequency_shift(sample_skybox(ray), shift);
+    }
+
+    return out_color + transmittance * frequency_shift(sample_other_skybox(ray), shift);
+}
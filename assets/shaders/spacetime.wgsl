@@ -0,0 +1,27 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+#import "shaders/common.wgsl"::construct_ray
+
+// the metric is picked on the cpu, see Metric::shader_def
+#ifdef METRIC_SPHERICAL
+#import "shaders/metrics/spherical.wgsl"::trace
+#endif
+#ifdef METRIC_KERR
+#import "shaders/metrics/kerr.wgsl"::trace
+#endif
+#ifdef METRIC_WORMHOLE
+#import "shaders/metrics/wormhole.wgsl"::trace
+#endif
+#ifdef METRIC_USER
+#import "shaders/metrics/user.wgsl"::trace
+#endif
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    var uv = mesh.uv;
+    uv.y = 1. - uv.y;
+    uv *= 2.;
+    uv -= 1.;
+
+    let ray = construct_ray(uv);
+    return trace(ray);
+}
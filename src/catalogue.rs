@@ -0,0 +1,222 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use bevy::math::DVec3;
+use serde::{Deserialize, Serialize};
+
+use crate::{reference::blackbody_color, starfield::color_index_temperature};
+
+// real stars drawn by the cpu tracer as points on top of the skybox, see reference::render_stars; a texture
+// smears a star over the texels it falls on, which the lensing magnifies into a blur near the einstein ring
+
+const CELL_SIZE: f64 = 0.02; // of the grid the directions are sorted into, about a degree
+const DEFAULT_COLOR_INDEX: f64 = 0.65; // like the sun, for stars without one
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StarCatalogue {
+    pub path: String, // csv relative to the assets directory, no stars when it's empty
+    pub brightness: f64, // what a star of magnitude 0 adds to the pixel it falls on without lensing
+}
+
+impl Default for StarCatalogue {
+    fn default() -> Self {
+        StarCatalogue { path: String::new(), brightness: 1. }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Star {
+    pub direction: DVec3,
+    pub magnitude: f64,
+    pub b_v: f64,
+}
+
+impl Star {
+    // linear, a star of magnitude 0 has the brightest channel at brightness
+    pub fn color(&self, brightness: f64) -> DVec3 {
+        blackbody_color(color_index_temperature(self.b_v)) * brightness * 10f64.powf(-0.4 * self.magnitude)
+    }
+}
+
+// the celestial north pole is up and the vernal equinox, right ascension 0, forward like the middle of an
+// equirectangular skybox; right ascension grows to the left, the way the sky looks from the inside
+pub fn equatorial_direction(ra: f64, dec: f64) -> DVec3 {
+    DVec3::new(-dec.cos() * ra.sin(), dec.sin(), -dec.cos() * ra.cos())
+}
+
+fn cell(direction: DVec3) -> [i32; 3] {
+    (direction / CELL_SIZE).floor().as_ivec3().to_array()
+}
+
+// the fields of a csv line, quoted ones may hold commas and "" for a quote; fields can't go on over a line break
+fn split_fields(line: &str) -> Result<Vec<String>, &'static str> {
+    let mut fields = vec![];
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') if chars.next_if_eq(&'"').is_some() => field.push('"'),
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("a quoted field that isn't closed"),
+                }
+            }
+            while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+            if chars.peek().is_some_and(|c| *c != ',') {
+                return Err("text after a quoted field");
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| *c != ',') {
+                if c == '"' {
+                    return Err("a quote inside of a field that isn't quoted");
+                }
+                field.push(c);
+            }
+        }
+        fields.push(field.trim().to_string());
+
+        if chars.next().is_none() {
+            return Ok(fields);
+        }
+    }
+}
+
+// the stars of a catalogue sorted into a grid over the directions so the ones around a direction are found
+// without going through all of them
+pub struct Stars {
+    stars: Vec<Star>,
+    cells: HashMap<[i32; 3], Vec<usize>>,
+}
+
+impl Stars {
+    pub fn new(stars: Vec<Star>) -> Self {
+        let mut cells: HashMap<[i32; 3], Vec<usize>> = HashMap::new();
+        for (i, star) in stars.iter().enumerate() {
+            cells.entry(cell(star.direction)).or_default().push(i);
+        }
+        Stars { stars, cells }
+    }
+
+    // a header row names the columns, which can be in any order and among others: ra and dec in degrees, or
+    // rarad and decrad in radians like the hyg database has them, mag and optionally the b - v colour index as
+    // ci, bv or b_v; lines starting with # are comments
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut lines = source.lines().enumerate().filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+        let (i, header) = lines.next().ok_or("The star catalogue is empty.")?;
+        let fields = |i: usize, line: &str| split_fields(line).map_err(|error| format!("Line {} of the star catalogue has {error}.", i + 1));
+        let columns: Vec<String> = fields(i, header)?.iter().map(|column| column.to_lowercase()).collect();
+        let column = |names: &[&str]| names.iter().find_map(|name| columns.iter().position(|column| column == name));
+
+        let (ra, dec, scale) = match (column(&["rarad"]), column(&["decrad"])) {
+            (Some(ra), Some(dec)) => (ra, dec, 1.),
+            _ => match (column(&["ra"]), column(&["dec"])) {
+                (Some(ra), Some(dec)) => (ra, dec, std::f64::consts::PI / 180.),
+                _ => return Err("The star catalogue needs the columns ra and dec, or rarad and decrad.".to_string()),
+            },
+        };
+        let magnitude = column(&["mag", "vmag", "magnitude"]).ok_or("The star catalogue needs the column mag.")?;
+        let color_index = column(&["ci", "bv", "b_v", "b-v"]);
+
+        let mut stars = vec![];
+        for (i, line) in lines {
+            let fields = fields(i, line)?;
+            let number = |column: usize, name: &str| -> Result<f64, String> {
+                let field = fields.get(column).map_or("", String::as_str);
+                field.parse().map_err(|_| format!("Invalid {name} in line {} of the star catalogue: {field:?}", i + 1))
+            };
+
+            let b_v = match color_index {
+                Some(column) if fields.get(column).is_some_and(|field| !field.is_empty()) => number(column, "colour index")?,
+                _ => DEFAULT_COLOR_INDEX,
+            };
+            stars.push(Star {
+                direction: equatorial_direction(number(ra, "right ascension")? * scale, number(dec, "declination")? * scale),
+                magnitude: number(magnitude, "magnitude")?,
+                b_v,
+            });
+        }
+        Ok(Stars::new(stars))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+        Stars::parse(&source).map_err(|error| format!("{}: {error}", path.display()))
+    }
+
+    // the stars less than radius radians from the direction
+    pub fn around(&self, direction: DVec3, radius: f64) -> Vec<&Star> {
+        let min_dot = radius.min(std::f64::consts::PI).cos();
+        let (min, max) = (cell(direction - radius), cell(direction + radius));
+        let cell_count = (0..3).map(|i| (max[i] - min[i] + 1) as usize).product::<usize>();
+
+        if cell_count > self.stars.len() {
+            return self.stars.iter().filter(|star| star.direction.dot(direction) >= min_dot).collect();
+        }
+        let mut stars = vec![];
+        for x in min[0]..=max[0] {
+            for y in min[1]..=max[1] {
+                for z in min[2]..=max[2] {
+                    let indices = self.cells.get(&[x, y, z]).map_or(&[][..], Vec::as_slice);
+                    stars.extend(indices.iter().map(|&i| &self.stars[i]).filter(|star| star.direction.dot(direction) >= min_dot));
+                }
+            }
+        }
+        stars
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_columns() {
+        let stars = Stars::parse("# a comment\nid,proper,ra,dec,mag,ci\n1,Polaris,37.95,89.26,1.97,0.636\n2,,0,0,5,\n").unwrap();
+        assert_eq!(stars.stars.len(), 2);
+        assert!(stars.stars[0].direction.y > 0.999);
+        assert_eq!((stars.stars[0].magnitude, stars.stars[0].b_v), (1.97, 0.636));
+        assert!((stars.stars[1].direction - DVec3::NEG_Z).length() < 1e-12);
+        assert_eq!(stars.stars[1].b_v, DEFAULT_COLOR_INDEX);
+
+        let radians = Stars::parse("rarad,decrad,ra,dec,mag\n1.5707963267948966,0,6,0,1\n").unwrap();
+        assert!((radians.stars[0].direction - DVec3::NEG_X).length() < 1e-12);
+
+        let quoted = Stars::parse("\"proper\",\"ra\",\"dec\",\"mag\"\n\"Alpha, \"\"the first\"\"\",\"90\", 0 ,\"2\"\n").unwrap();
+        assert!((quoted.stars[0].direction - DVec3::NEG_X).length() < 1e-12);
+        assert_eq!(quoted.stars[0].magnitude, 2.);
+        for line in ["\"Alpha,90,0,2", "\"Alpha\" A,90,0,2", "Al\"pha,90,0,2"] {
+            assert!(Stars::parse(&format!("proper,ra,dec,mag\n{line}\n")).is_err(), "{line}");
+        }
+
+        assert!(Stars::parse("ra,mag\n1,2\n").is_err());
+        assert!(Stars::parse("ra,dec,mag\n1,north,2\n").is_err());
+    }
+
+    #[test]
+    fn reads_the_bright_stars() {
+        let stars = Stars::parse(include_str!("../assets/catalogues/bright-stars.csv")).unwrap();
+        assert_eq!(stars.stars.len(), 17);
+        let polaris = stars.stars.last().unwrap();
+        assert!(polaris.direction.y > 0.999 && polaris.color(1.).max_element() < 0.2);
+    }
+
+    #[test]
+    fn finds_the_stars_around() {
+        let mut random = crate::starfield::Random(3);
+        let stars: Vec<Star> = (0..2000)
+            .map(|_| {
+                let direction = equatorial_direction(random.next_f64() * 2. * std::f64::consts::PI, (random.next_f64() * 2. - 1.).asin());
+                Star { direction, magnitude: 0., b_v: 0. }
+            })
+            .collect();
+        let stars = Stars::new(stars);
+
+        for (direction, radius) in [(DVec3::X, 0.05), (DVec3::new(0.3, -0.5, 0.8).normalize(), 0.2), (DVec3::Y, 2.)] {
+            let expected = stars.stars.iter().filter(|star| star.direction.angle_between(direction) < radius).count();
+            assert_eq!(stars.around(direction, radius).len(), expected);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::f64::consts::PI;
+
+use bevy::render::{render_asset::RenderAssetUsages, render_resource::{Extent3d, TextureDimension, TextureFormat}, texture::Image};
+
+use crate::elliptic::NullGeodesic;
+
+// schwarzschild rays from a static camera at r only depend on the angle alpha between them and the radial direction,
+// so the orbits u(phi) are worked out once per camera radius instead of once per pixel and frame;
+// alpha is sampled at the centers of equal cells in [0, pi] and phi from 0 to TABLE_PHI_MAX
+const TABLE_ALPHA_CNT: usize = 512;
+const TABLE_PHI_CNT: usize = 256;
+const TABLE_PHI_MAX: f64 = 4. * PI; // rays still going around after two orbits are taken as captured
+const SUBSTEPS: usize = 10; // rk4 steps between the samples of the table
+
+// the table is made again once the camera has moved this far relative to its radius
+pub const TABLE_R_TOLERANCE: f64 = 1e-3;
+
+#[derive(Debug, Clone)]
+pub struct Orbit {
+    pub end_phi: f64, // where the ray escapes to infinity or falls through the horizon
+    pub captured: bool,
+    pub u: Vec<f64>, // 1 / r at the phi of the table, 0 after escaping and 1 / 2 after falling in
+}
+
+// u'' = u (3 u - 1)
+fn derivative(y: [f64; 2]) -> [f64; 2] {
+    [y[1], y[0] * (3. * y[0] - 1.)]
+}
+
+fn rk4(y: [f64; 2], h: f64) -> [f64; 2] {
+    let add = |y: [f64; 2], d: [f64; 2], h: f64| [y[0] + h * d[0], y[1] + h * d[1]];
+
+    let k1 = derivative(y);
+    let k2 = derivative(add(y, k1, h / 2.));
+    let k3 = derivative(add(y, k2, h / 2.));
+    let k4 = derivative(add(y, k3, h));
+    [0, 1].map(|i| y[i] + h / 6. * (k1[i] + 2. * k2[i] + 2. * k3[i] + k4[i]))
+}
+
+// the ray leaves the camera at r with the angle alpha to the outward radial direction, phi grows along it;
+// exact where it is on an orbit that comes in from infinity and integrated otherwise
+pub fn orbit(r: f64, alpha: f64) -> Orbit {
+    exact_orbit(r, alpha).unwrap_or_else(|| integrated_orbit(r, alpha))
+}
+
+// the camera is where the exact ray gets to 1 / r coming in, rays pointing outwards go back the way it came;
+// None inside of the photon sphere for the rays going out that turn around before they get to infinity
+fn exact_orbit(r: f64, alpha: f64) -> Option<Orbit> {
+    let (u0, v0) = (1. / r, -alpha.cos() / (r * alpha.sin()));
+    let geodesic = NullGeodesic::new(1. / (v0 * v0 + u0 * u0 * (1. - 2. * u0)).sqrt());
+    let camera_phi = geodesic.phi(u0).filter(|phi| phi.is_finite())?;
+
+    let outwards = v0 < 0.;
+    let (end_phi, captured) = if outwards { (camera_phi, false) } else { (geodesic.end_phi() - camera_phi, geodesic.captured()) };
+    let (end_phi, captured) = if end_phi > TABLE_PHI_MAX { (TABLE_PHI_MAX, true) } else { (end_phi, captured) };
+
+    let d_phi = TABLE_PHI_MAX / (TABLE_PHI_CNT - 1) as f64;
+    let edge = if captured { 0.5 } else { 0. };
+    let u = (0..TABLE_PHI_CNT)
+        .map(|j| j as f64 * d_phi)
+        .map(|phi| if phi >= end_phi { edge } else { geodesic.u(if outwards { camera_phi - phi } else { camera_phi + phi }) })
+        .collect();
+    Some(Orbit { end_phi, captured, u })
+}
+
+// rk4 on the orbit equation
+fn integrated_orbit(r: f64, alpha: f64) -> Orbit {
+    let d_phi = TABLE_PHI_MAX / (TABLE_PHI_CNT - 1) as f64;
+    let h = d_phi / SUBSTEPS as f64;
+
+    let mut y = [1. / r, -alpha.cos() / (r * alpha.sin())];
+    let mut u = vec![y[0]];
+    for i in 0..(TABLE_PHI_CNT - 1) * SUBSTEPS {
+        let prev = y;
+        y = rk4(y, h);
+
+        let escaped = y[0] <= 0.;
+        let captured = y[0] >= 0.5;
+        if escaped || captured {
+            // linear in between the steps
+            let edge = if escaped { 0. } else { 0.5 };
+            let end_phi = (i as f64 + (edge - prev[0]) / (y[0] - prev[0])) * h;
+            u.resize(TABLE_PHI_CNT, edge);
+            return Orbit { end_phi, captured, u };
+        }
+
+        if (i + 1) % SUBSTEPS == 0 {
+            u.push(y[0]);
+        }
+    }
+
+    Orbit { end_phi: TABLE_PHI_MAX, captured: true, u }
+}
+
+// rgba32 float texture with a column for each alpha, the first row holds the end phi and 1 for captured rays,
+// the rest u at the phi of the table
+pub fn deflection_table(r: f64) -> Image {
+    let orbits: Vec<Orbit> = (0..TABLE_ALPHA_CNT)
+        .map(|i| orbit(r, (i as f64 + 0.5) / TABLE_ALPHA_CNT as f64 * PI))
+        .collect();
+
+    let mut texels = Vec::with_capacity(TABLE_ALPHA_CNT * (TABLE_PHI_CNT + 1));
+    texels.extend(orbits.iter().map(|orbit| [orbit.end_phi, if orbit.captured { 1. } else { 0. }, 0., 0.]));
+    for j in 0..TABLE_PHI_CNT {
+        texels.extend(orbits.iter().map(|orbit| [orbit.u[j], 0., 0., 0.]));
+    }
+
+    let data = texels
+        .into_iter()
+        .flatten()
+        .flat_map(|value| (value as f32).to_le_bytes())
+        .collect();
+
+    Image::new(
+        Extent3d { width: TABLE_ALPHA_CNT as u32, height: TABLE_PHI_CNT as u32 + 1, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba32Float,
+        RenderAssetUsages::RENDER_WORLD,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the ray coming in with the impact parameter b, du/dphi = -u cot(alpha) at the camera like in the shader
+    fn incoming_alpha(r: f64, b: f64) -> f64 {
+        PI - (r * r / (b * b) - 1. + 2. / r).sqrt().recip().atan()
+    }
+
+    // far from the black hole the ray is a straight line that reaches infinity at phi = alpha, bent by about 4 / b
+    #[test]
+    fn weak_field_orbits() {
+        let r = 1e6;
+        for b in [1e3, 3e3] {
+            let alpha = incoming_alpha(r, b);
+            let orbit = orbit(r, alpha);
+            let deflection = 4. / b + 15. * PI / 4. / (b * b);
+            assert!(!orbit.captured, "b {b}");
+            assert!((orbit.end_phi - alpha - deflection).abs() < 100. / (b * b * b), "b {b}");
+        }
+    }
+
+    #[test]
+    fn capture_inside_of_the_photon_sphere() {
+        let r = 10.;
+        let alpha = incoming_alpha(r, 27_f64.sqrt());
+        assert!(!orbit(r, alpha - 1e-3).captured);
+        assert!(orbit(r, alpha + 1e-3).captured);
+
+        // the ones pointing outwards all escape
+        assert!((1..16).all(|i| !orbit(r, i as f64 / 32. * PI).captured));
+    }
+
+    // the shader goes linearly from one column to the next, which keeps the escape direction from stepping near the
+    // edge of the shadow where it changes fastest
+    #[test]
+    fn end_phi_between_columns() {
+        let r = 10.;
+        let column_alpha = |i: f64| (i + 0.5) / TABLE_ALPHA_CNT as f64 * PI;
+        let edge = incoming_alpha(r, 27_f64.sqrt());
+        let column = (edge / PI * TABLE_ALPHA_CNT as f64 - 0.5).floor() - 2.;
+
+        let (left, right) = (orbit(r, column_alpha(column)), orbit(r, column_alpha(column + 1.)));
+        let middle = orbit(r, column_alpha(column + 0.5));
+        assert!(!left.captured && !right.captured && !middle.captured);
+
+        let linear = (left.end_phi + right.end_phi) / 2.;
+        let nearest = left.end_phi;
+        assert!((linear - middle.end_phi).abs() < (nearest - middle.end_phi).abs() / 4., "{linear} {nearest} {}", middle.end_phi);
+    }
+
+    // the integrator against the exact orbits through a camera at r = 10
+    #[test]
+    fn integrated_orbits() {
+        let r = 10_f64;
+        for alpha in [0.3, 1., 2., 2.5, 2.6, 2.7, 3.] {
+            let (exact, integrated) = (exact_orbit(r, alpha).unwrap(), integrated_orbit(r, alpha));
+            assert_eq!(integrated.captured, exact.captured, "alpha {alpha}");
+            assert!((integrated.end_phi - exact.end_phi).abs() < 1e-4, "alpha {alpha}");
+            for j in 0..20 {
+                let phi = j as f64 * TABLE_PHI_MAX / (TABLE_PHI_CNT - 1) as f64;
+                if phi < exact.end_phi {
+                    assert!((integrated.u[j] - exact.u[j]).abs() < 1e-8, "alpha {alpha} phi {phi}");
+                }
+            }
+        }
+
+        // inside of the photon sphere the rays going out with a large impact parameter fall back in
+        assert!(exact_orbit(2.5, 1.5).is_none());
+        assert!(orbit(2.5, 1.5).captured);
+    }
+
+    #[test]
+    fn table_layout() {
+        let r = 10.;
+        let table = deflection_table(r);
+        assert_eq!((table.width(), table.height()), (TABLE_ALPHA_CNT as u32, TABLE_PHI_CNT as u32 + 1));
+
+        let texel = |x: usize, y: usize| {
+            let offset = 16 * (y * TABLE_ALPHA_CNT + x);
+            let value = |i: usize| f32::from_le_bytes(table.data[offset + 4 * i..offset + 4 * i + 4].try_into().unwrap());
+            [value(0), value(1)]
+        };
+
+        // the ray straight out escapes at once, the one straight in falls in
+        assert!(texel(0, 0)[0] < 0.1 && texel(0, 0)[1] == 0.);
+        assert_eq!(texel(TABLE_ALPHA_CNT - 1, 0)[1], 1.);
+
+        // every orbit starts at the camera
+        assert!((0..TABLE_ALPHA_CNT).all(|x| (texel(x, 1)[0] - 0.1).abs() < 1e-6));
+    }
+}
@@ -0,0 +1,162 @@
+use std::f64::consts::PI;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{LIGHT_SPEED, NEWTON_CONSTANT};
+
+const STEFAN_BOLTZMANN: f64 = 5.67e-8;
+
+// what the accretion disc looks like, the colours of the texture or the thermal glow of the gas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DiscEmission {
+    #[default]
+    Texture,
+    Blackbody,
+}
+
+impl DiscEmission {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DiscEmission::Texture => "Texture",
+            DiscEmission::Blackbody => "Blackbody",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            DiscEmission::Texture => DiscEmission::Blackbody,
+            DiscEmission::Blackbody => DiscEmission::Texture,
+        }
+    }
+
+    // accretion_disc_emission in shaders/common.wgsl
+    pub fn shader_value(&self) -> u32 {
+        match self {
+            DiscEmission::Texture => 0,
+            DiscEmission::Blackbody => 1,
+        }
+    }
+}
+
+// an infinitely thin disc in the equatorial plane, or a torus of gas around the same ring that the rays
+// are marched through, which can hide itself and the black hole behind it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DiscShape {
+    #[default]
+    Thin,
+    Torus,
+}
+
+impl DiscShape {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DiscShape::Thin => "Thin",
+            DiscShape::Torus => "Torus",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            DiscShape::Thin => DiscShape::Torus,
+            DiscShape::Torus => DiscShape::Thin,
+        }
+    }
+
+    // accretion_disc_shape in shaders/common.wgsl
+    pub fn shader_value(&self) -> u32 {
+        match self {
+            DiscShape::Thin => 0,
+            DiscShape::Torus => 1,
+        }
+    }
+}
+
+// temperature of a shakura-sunyaev disc that exerts no torque at its inner edge r_in is
+// T(r) = T_* ((1 - sqrt(r_in / r)) / r^3)^(1/4) with r in units of M; this is T_* in kelvin
+// for the mass in kg and the accretion rate in kg/s, heavier holes have cooler discs
+pub fn temperature_scale(mass: f64, accretion_rate: f64) -> f64 {
+    let c = LIGHT_SPEED as f64;
+    let g = NEWTON_CONSTANT as f64;
+    (3. * accretion_rate * c.powi(6) / (8. * PI * STEFAN_BOLTZMANN * g * g * mass * mass)).max(0.).powf(0.25)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reference::blackbody_color;
+
+    const SOLAR_MASS: f64 = 1.989e30;
+
+    // the profile of disc_temperature in shaders/common.wgsl
+    fn profile(inner_edge: f64, r: f64) -> f64 {
+        ((1. - (inner_edge / r).sqrt()).max(0.) / (r * r * r)).powf(0.25)
+    }
+
+    // zero at the inner edge and hottest at 49/36 of it, where the shader puts the brightness of the disc
+    #[test]
+    fn temperature_profile() {
+        let isco = 6.;
+        assert_eq!(profile(isco, isco), 0.);
+        assert_eq!(profile(isco, 5.), 0.);
+
+        let peak = 49. / 36. * isco;
+        for r in [6.01, 7., 8., 8.1, 8.3, 9., 12., 50.] {
+            assert!(profile(isco, r) < profile(isco, peak), "r {r}");
+        }
+        let h = 1e-4;
+        assert!((profile(isco, peak + h) - profile(isco, peak - h)).abs() < 1e-12);
+    }
+
+    // T^4 = 3 G M Mdot / (8 pi sigma R^3) (1 - sqrt(R_in / R)) with R in metres
+    #[test]
+    fn temperature_in_kelvin() {
+        let (mass, accretion_rate) = (10. * SOLAR_MASS, 1e15);
+        let (c, g) = (LIGHT_SPEED as f64, NEWTON_CONSTANT as f64);
+        let metres = |r: f64| r * g * mass / (c * c);
+
+        let r = 10.;
+        let expected = (3. * g * mass * accretion_rate / (8. * PI * STEFAN_BOLTZMANN * metres(r).powi(3)) * (1. - (6. / r).sqrt())).powf(0.25);
+        let temperature = temperature_scale(mass, accretion_rate) * profile(6., r);
+        assert!((temperature / expected - 1.).abs() < 1e-4, "{temperature} K instead of {expected} K");
+        // the x-rays of stellar mass black holes
+        assert!(temperature > 1e6 && temperature < 1e7);
+    }
+
+    // T goes as Mdot^(1/4) / M^(1/2) at the same r in units of M, so as M^(-1/4) when the accretion rate grows with
+    // the mass like the eddington rate does
+    #[test]
+    fn temperature_scaling() {
+        let base = temperature_scale(SOLAR_MASS, 1e14);
+        assert!((temperature_scale(SOLAR_MASS, 16e14) / base - 2.).abs() < 1e-6);
+        assert!((temperature_scale(16. * SOLAR_MASS, 1e14) / base - 0.25).abs() < 1e-6);
+        assert!((temperature_scale(16. * SOLAR_MASS, 16e14) / base - 0.5).abs() < 1e-6);
+        assert_eq!(temperature_scale(SOLAR_MASS, -1.), 0.);
+    }
+
+    // the brightest channel is 1, cool discs glow red, hot ones blue and around 6500 K they are close to white
+    #[test]
+    fn blackbody_colors() {
+        for temperature in [1000., 3000., 6500., 20000., 1e5] {
+            let color = blackbody_color(temperature);
+            assert!((color.max_element() - 1.).abs() < 1e-12 && color.min_element() >= 0., "{temperature} K: {color}");
+        }
+
+        let red = blackbody_color(2000.);
+        assert!(red.x == 1. && red.x > red.y && red.y > red.z, "{red}");
+        let blue = blackbody_color(30000.);
+        assert!(blue.z == 1. && blue.z > blue.y && blue.y > blue.x, "{blue}");
+        let white = blackbody_color(6500.);
+        assert!(white.min_element() > 0.85, "{white}");
+
+        // it hardly changes beyond the range, so the temperature is clamped to it
+        assert_eq!(blackbody_color(1e7), blackbody_color(1e6));
+        assert_eq!(blackbody_color(100.), blackbody_color(500.));
+    }
+
+    #[test]
+    fn shader_values() {
+        assert_eq!([DiscEmission::Texture, DiscEmission::Blackbody].map(|emission| emission.shader_value()), [0, 1]);
+        assert_eq!(DiscEmission::Texture.next().next(), DiscEmission::Texture);
+        assert_eq!([DiscShape::Thin, DiscShape::Torus].map(|shape| shape.shader_value()), [0, 1]);
+    }
+}
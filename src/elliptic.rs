@@ -0,0 +1,254 @@
+use std::f64::consts::PI;
+
+// exact null geodesics of the schwarzschild metric in units of M; u = 1 / r along a ray solves
+// (du/dphi)^2 = 2 u^3 - u^2 + 1 / b^2 = 2 (u - u1) (u - u2) (u - u3), so u(phi) is an elliptic function of phi
+// and phi(u) an elliptic integral, the ground truth for the integrators in the shaders
+
+// 3 sqrt(3), rays with a smaller impact parameter fall into the black hole
+pub const CRITICAL_IMPACT_PARAMETER: f64 = 5.196_152_422_706_632;
+
+/* #region elliptic functions */
+// carlson's symmetric integral R_F(x, y, z) = 1/2 int_0^inf dt / sqrt((t + x) (t + y) (t + z)) by duplication,
+// at most one of x, y, z may be 0; NaN for arguments it isn't defined for
+pub fn carlson_rf(x: f64, y: f64, z: f64) -> f64 {
+    const TOLERANCE: f64 = 0.0025; // the error goes as its sixth power
+    const MAX_DUPLICATIONS: usize = 64; // each one brings x, y and z 4 times closer together
+
+    if !(x.is_finite() && y.is_finite() && z.is_finite()) || x.min(y).min(z) < 0. {
+        return f64::NAN;
+    }
+
+    let (mut x, mut y, mut z) = (x, y, z);
+    for _ in 0..MAX_DUPLICATIONS {
+        let (sqrt_x, sqrt_y, sqrt_z) = (x.sqrt(), y.sqrt(), z.sqrt());
+        let lambda = sqrt_x * (sqrt_y + sqrt_z) + sqrt_y * sqrt_z;
+        x = 0.25 * (x + lambda);
+        y = 0.25 * (y + lambda);
+        z = 0.25 * (z + lambda);
+
+        let mean = (x + y + z) / 3.;
+        let (dx, dy, dz) = ((mean - x) / mean, (mean - y) / mean, (mean - z) / mean);
+        if dx.abs().max(dy.abs()).max(dz.abs()) <= TOLERANCE {
+            let e2 = dx * dy - dz * dz;
+            let e3 = dx * dy * dz;
+            return (1. + (e2 / 24. - 0.1 - 3. / 44. * e3) * e2 + e3 / 14.) / mean.sqrt();
+        }
+    }
+    f64::NAN
+}
+
+// complete elliptic integral of the first kind for the parameter m = k^2 < 1
+pub fn elliptic_k(m: f64) -> f64 {
+    carlson_rf(0., 1. - m, 1.)
+}
+
+// incomplete elliptic integral of the first kind F(phi | m) = int_0^phi dt / sqrt(1 - m sin^2 t) for any phi
+pub fn elliptic_f(phi: f64, m: f64) -> f64 {
+    let periods = (phi / PI).round();
+    let phi = phi - periods * PI;
+    let (sin, cos) = phi.sin_cos();
+    let f = sin * carlson_rf(cos * cos, 1. - m * sin * sin, 1.);
+    if periods == 0. { f } else { f + 2. * periods * elliptic_k(m) }
+}
+
+// jacobi elliptic functions sn, cn and dn of u for the parameter 0 <= m <= 1 by descending landen transformations
+pub fn jacobi(u: f64, m: f64) -> (f64, f64, f64) {
+    const TOLERANCE: f64 = 1e-8; // the error goes as its square
+    const MAX_TRANSFORMATIONS: usize = 13;
+
+    let mut m_complement = 1. - m;
+    if m_complement <= 0. {
+        let sech = 1. / u.cosh();
+        return (u.tanh(), sech, sech);
+    }
+
+    let mut a = 1.;
+    let mut c = 1.;
+    let mut dn = 1.;
+    let mut levels = [(0., 0.); MAX_TRANSFORMATIONS];
+    let mut level_cnt = 0;
+    for level in &mut levels {
+        m_complement = m_complement.sqrt();
+        *level = (a, m_complement);
+        level_cnt += 1;
+        c = 0.5 * (a + m_complement);
+        if (a - m_complement).abs() <= TOLERANCE * a {
+            break;
+        }
+        m_complement *= a;
+        a = c;
+    }
+
+    let u = u * c;
+    let (mut sn, mut cn) = u.sin_cos();
+    if sn != 0. {
+        let mut a = cn / sn;
+        c *= a;
+        for &(b, root) in levels[..level_cnt].iter().rev() {
+            a *= c;
+            c *= dn;
+            dn = (root + a) / (b + a);
+            a = c / b;
+        }
+        a = 1. / (c * c + 1.).sqrt();
+        sn = if sn >= 0. { a } else { -a };
+        cn = c * sn;
+    }
+    (sn, cn, dn)
+}
+/* #endregion */
+
+#[derive(Debug, Clone, Copy)]
+enum Shape {
+    // b > 3 sqrt(3), the roots are real with u1 < 0 <= u2 < u3 and the ray turns around at u2;
+    // u = u1 + (u2 - u1) sn^2(w, m)
+    Scattered { u1: f64, u2: f64 },
+    // b <= 3 sqrt(3), u1 < 0 is the only real root and the ray falls in;
+    // u = u1 + a (1 - cn(w, m)) / (1 + cn(w, m)) with a the distance from u1 to the complex roots
+    Plunging { u1: f64, a: f64 },
+}
+
+// a ray coming in from infinity with the impact parameter b, phi is 0 at infinity and grows along the ray;
+// w = w0 + scale phi is the argument of the elliptic functions
+#[derive(Debug, Clone, Copy)]
+pub struct NullGeodesic {
+    shape: Shape,
+    m: f64,
+    scale: f64,
+    w0: f64,
+}
+
+impl NullGeodesic {
+    // b > 0
+    pub fn new(impact_parameter: f64) -> Self {
+        let b = impact_parameter;
+
+        // u = t + 1/6 takes u^3 - u^2 / 2 + 1 / (2 b^2) to t^3 - t / 12 + q
+        let q = 1. / (2. * b * b) - 1. / 108.;
+        if b > CRITICAL_IMPACT_PARAMETER {
+            // three real roots by the trigonometric formula
+            let theta = (-108. * q).clamp(-1., 1.).acos() / 3.;
+            let root = |k: f64| 1. / 6. + (theta - 2. * PI / 3. * k).cos() / 3.;
+            let (u1, u2, u3) = (root(2.), root(1.), root(0.));
+
+            let m = (u2 - u1) / (u3 - u1);
+            let w0 = elliptic_f((-u1 / (u2 - u1)).sqrt().asin(), m);
+            NullGeodesic { shape: Shape::Scattered { u1, u2 }, m, scale: (0.5 * (u3 - u1)).sqrt(), w0 }
+        } else {
+            // cardano, the complex roots add up to 1/2 - u1 and make 1 / (2 b^2) with u1
+            let d = (q * q / 4. - 1. / 46656.).max(0.).sqrt();
+            let u1 = 1. / 6. + (-q / 2. + d).cbrt() + (-q / 2. - d).cbrt();
+            let re = 0.5 * (0.5 - u1);
+            let norm2 = -1. / (2. * b * b * u1);
+            let a = (norm2 - 2. * re * u1 + u1 * u1).sqrt();
+
+            let m = ((a + re - u1) / (2. * a)).min(1.);
+            let w0 = elliptic_f(((a + u1) / (a - u1)).clamp(-1., 1.).acos(), m);
+            NullGeodesic { shape: Shape::Plunging { u1, a }, m, scale: (2. * a).sqrt(), w0 }
+        }
+    }
+
+    pub fn captured(&self) -> bool {
+        matches!(self.shape, Shape::Plunging { .. })
+    }
+
+    // 1 / r at phi, negative past the escape of scattered rays
+    pub fn u(&self, phi: f64) -> f64 {
+        let w = self.w0 + self.scale * phi;
+        let (sn, cn, _) = jacobi(w, self.m);
+        match self.shape {
+            Shape::Scattered { u1, u2 } => u1 + (u2 - u1) * sn * sn,
+            Shape::Plunging { u1, a } => u1 + a * (1. - cn) / (1. + cn),
+        }
+    }
+
+    // phi where the ray first gets to u coming in, None for a scattered ray that turns around before
+    pub fn phi(&self, u: f64) -> Option<f64> {
+        let w = match self.shape {
+            Shape::Scattered { u1, u2 } => {
+                let sn2 = (u - u1) / (u2 - u1);
+                if sn2 > 1. {
+                    return None;
+                }
+                elliptic_f(sn2.max(0.).sqrt().asin(), self.m)
+            }
+            Shape::Plunging { u1, a } => elliptic_f(((a - u + u1) / (a + u - u1)).clamp(-1., 1.).acos(), self.m),
+        };
+        Some((w - self.w0) / self.scale)
+    }
+
+    // phi where the ray escapes to infinity again or falls through the horizon at u = 1/2
+    pub fn end_phi(&self) -> f64 {
+        match self.shape {
+            // u is symmetric about the turning point at w = K
+            Shape::Scattered { .. } => 2. * (elliptic_k(self.m) - self.w0) / self.scale,
+            Shape::Plunging { .. } => self.phi(0.5).unwrap_or(f64::INFINITY),
+        }
+    }
+
+    // how far the ray is bent from a straight line, None for the rays that fall in
+    pub fn deflection(&self) -> Option<f64> {
+        (!self.captured()).then(|| self.end_phi() - PI)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elliptic_functions() {
+        assert!((elliptic_k(0.) - PI / 2.).abs() < 1e-14);
+        assert!((elliptic_k(0.5) - 1.854_074_677_301_372).abs() < 1e-13);
+
+        // and no hanging on what a radial ray or a bad parameter feeds in
+        assert!(carlson_rf(f64::NAN, 1., 1.).is_nan());
+        assert!(carlson_rf(0., f64::INFINITY, 1.).is_nan());
+        assert!(carlson_rf(0., 0., 0.).is_nan());
+        assert!(elliptic_k(f64::NAN).is_nan());
+
+        for m in [0., 0.3, 0.9, 0.999] {
+            for phi in [0.1, 1., 2., 5.] {
+                let (sn, cn, dn) = jacobi(elliptic_f(phi, m), m);
+                assert!((sn - phi.sin()).abs() < 1e-12, "sn m {m} phi {phi}");
+                assert!((cn - phi.cos()).abs() < 1e-12, "cn m {m} phi {phi}");
+                assert!((dn * dn + m * sn * sn - 1.).abs() < 1e-12, "dn m {m} phi {phi}");
+            }
+        }
+    }
+
+    #[test]
+    fn orbits_solve_the_orbit_equation() {
+        for b in [3., 5., 5.3, 6., 20.] {
+            let geodesic = NullGeodesic::new(b);
+            let end = geodesic.end_phi().min(10.);
+            for i in 1..20 {
+                let phi = end * i as f64 / 20.;
+                let (u, h) = (geodesic.u(phi), 1e-5);
+                let du = (geodesic.u(phi + h) - geodesic.u(phi - h)) / (2. * h);
+                let residual = du * du - (2. * u * u * u - u * u + 1. / (b * b));
+                assert!(residual.abs() < 1e-8, "b {b} phi {phi} residual {residual}");
+            }
+            assert!(geodesic.u(0.).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn weak_field_deflection() {
+        for b in [1e3, 1e4] {
+            let deflection = NullGeodesic::new(b).deflection().unwrap();
+            let expansion = 4. / b + 15. * PI / 4. / (b * b);
+            assert!((deflection - expansion).abs() < 100. / (b * b * b), "b {b}");
+        }
+    }
+
+    #[test]
+    fn capture_at_the_critical_impact_parameter() {
+        assert!(!NullGeodesic::new(CRITICAL_IMPACT_PARAMETER * 1.001).captured());
+        assert!(NullGeodesic::new(CRITICAL_IMPACT_PARAMETER * 0.999).captured());
+
+        // close to it the ray winds around the photon sphere, logarithmically often
+        let deflection = NullGeodesic::new(CRITICAL_IMPACT_PARAMETER * (1. + 1e-6)).deflection().unwrap();
+        assert!(deflection > 4. * PI);
+    }
+}
@@ -0,0 +1,407 @@
+use std::{f64::consts::PI, iter::Peekable, str::Chars};
+
+// expressions in r and theta typed into the sidebar, e.g. -(1-2/r) or r^2*sin(θ)^2
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Var(Var),
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Var {
+    R,
+    Theta,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Exp,
+    Ln,
+    Sqrt,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sin" => Some(Function::Sin),
+            "cos" => Some(Function::Cos),
+            "tan" => Some(Function::Tan),
+            "exp" => Some(Function::Exp),
+            "ln" | "log" => Some(Function::Ln),
+            "sqrt" => Some(Function::Sqrt),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, x: f64) -> f64 {
+        match self {
+            Function::Sin => x.sin(),
+            Function::Cos => x.cos(),
+            Function::Tan => x.tan(),
+            Function::Exp => x.exp(),
+            Function::Ln => x.ln(),
+            Function::Sqrt => x.sqrt(),
+        }
+    }
+}
+
+/* #region parsing */
+// expr := term (('+' | '-') term)*
+// term := unary (('*' | '/') unary)*
+// unary := '-' unary | power
+// power := atom ('^' unary)?
+// atom := number | variable | constant | function '(' expr ')' | '(' expr ')'
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.peek().copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.chars.next();
+                Ok(())
+            }
+            Some(c) => Err(format!("Expected '{expected}', found '{c}'.")),
+            None => Err(format!("Expected '{expected}', found the end.")),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        loop {
+            match self.peek() {
+                Some('+') => {
+                    self.chars.next();
+                    lhs = Expr::Add(Box::new(lhs), Box::new(self.term()?));
+                }
+                Some('-') => {
+                    self.chars.next();
+                    lhs = Expr::Sub(Box::new(lhs), Box::new(self.term()?));
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            match self.peek() {
+                Some('*') => {
+                    self.chars.next();
+                    lhs = Expr::Mul(Box::new(lhs), Box::new(self.unary()?));
+                }
+                Some('/') => {
+                    self.chars.next();
+                    lhs = Expr::Div(Box::new(lhs), Box::new(self.unary()?));
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some('-') {
+            self.chars.next();
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.atom()?;
+        if self.peek() == Some('^') {
+            self.chars.next();
+            return Ok(Expr::Pow(Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.peek() {
+            Some('(') => {
+                self.chars.next();
+                let inner = self.expr()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let mut number = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                // exponent, as in 1e-3
+                if self.chars.next_if_eq(&'e').is_some() {
+                    number.push('e');
+                    if let Some(sign) = self.chars.next_if(|c| *c == '-' || *c == '+') {
+                        number.push(sign);
+                    }
+                    while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit()) {
+                        number.push(c);
+                    }
+                }
+                number.parse().map(Expr::Number).map_err(|_| format!("Invalid number '{number}'."))
+            }
+            Some(c) if c.is_alphabetic() => {
+                let mut name = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                match name.as_str() {
+                    "r" => Ok(Expr::Var(Var::R)),
+                    "θ" | "theta" => Ok(Expr::Var(Var::Theta)),
+                    "π" | "pi" => Ok(Expr::Number(PI)),
+                    _ => {
+                        let function = Function::from_name(&name).ok_or(format!("Unknown name '{name}'."))?;
+                        self.expect('(')?;
+                        let argument = self.expr()?;
+                        self.expect(')')?;
+                        Ok(Expr::Call(function, Box::new(argument)))
+                    }
+                }
+            }
+            Some(c) => Err(format!("Unexpected '{c}'.")),
+            None => Err("Unexpected end.".to_string()),
+        }
+    }
+}
+
+impl Expr {
+    pub fn parse(source: &str) -> Result<Expr, String> {
+        let mut parser = Parser { chars: source.chars().peekable() };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            None => Ok(expr),
+            Some(c) => Err(format!("Unexpected '{c}'.")),
+        }
+    }
+}
+/* #endregion */
+
+/* #region evaluation */
+impl Expr {
+    pub fn eval(&self, r: f64, theta: f64) -> f64 {
+        match self {
+            Expr::Number(x) => *x,
+            Expr::Var(Var::R) => r,
+            Expr::Var(Var::Theta) => theta,
+            Expr::Neg(a) => -a.eval(r, theta),
+            Expr::Add(a, b) => a.eval(r, theta) + b.eval(r, theta),
+            Expr::Sub(a, b) => a.eval(r, theta) - b.eval(r, theta),
+            Expr::Mul(a, b) => a.eval(r, theta) * b.eval(r, theta),
+            Expr::Div(a, b) => a.eval(r, theta) / b.eval(r, theta),
+            Expr::Pow(a, b) => a.eval(r, theta).powf(b.eval(r, theta)),
+            Expr::Call(function, a) => function.apply(a.eval(r, theta)),
+        }
+    }
+}
+/* #endregion */
+
+/* #region differentiation */
+// constructors that fold constants, so the derivatives don't grow out of hand
+fn neg(a: Expr) -> Expr {
+    match a {
+        Expr::Number(x) => Expr::Number(-x),
+        Expr::Neg(a) => *a,
+        a => Expr::Neg(Box::new(a)),
+    }
+}
+
+fn add(a: Expr, b: Expr) -> Expr {
+    match (a, b) {
+        (Expr::Number(x), Expr::Number(y)) => Expr::Number(x + y),
+        (Expr::Number(0.), b) => b,
+        (a, Expr::Number(0.)) => a,
+        (a, b) => Expr::Add(Box::new(a), Box::new(b)),
+    }
+}
+
+fn sub(a: Expr, b: Expr) -> Expr {
+    match (a, b) {
+        (Expr::Number(x), Expr::Number(y)) => Expr::Number(x - y),
+        (Expr::Number(0.), b) => neg(b),
+        (a, Expr::Number(0.)) => a,
+        (a, b) => Expr::Sub(Box::new(a), Box::new(b)),
+    }
+}
+
+fn mul(a: Expr, b: Expr) -> Expr {
+    match (a, b) {
+        (Expr::Number(x), Expr::Number(y)) => Expr::Number(x * y),
+        (Expr::Number(0.), _) | (_, Expr::Number(0.)) => Expr::Number(0.),
+        (Expr::Number(1.), b) => b,
+        (a, Expr::Number(1.)) => a,
+        (a, b) => Expr::Mul(Box::new(a), Box::new(b)),
+    }
+}
+
+fn div(a: Expr, b: Expr) -> Expr {
+    match (a, b) {
+        (Expr::Number(x), Expr::Number(y)) => Expr::Number(x / y),
+        (Expr::Number(0.), _) => Expr::Number(0.),
+        (a, Expr::Number(1.)) => a,
+        (a, b) => Expr::Div(Box::new(a), Box::new(b)),
+    }
+}
+
+fn pow(a: Expr, b: Expr) -> Expr {
+    match (a, b) {
+        (Expr::Number(x), Expr::Number(y)) => Expr::Number(x.powf(y)),
+        (_, Expr::Number(0.)) => Expr::Number(1.),
+        (a, Expr::Number(1.)) => a,
+        (a, b) => Expr::Pow(Box::new(a), Box::new(b)),
+    }
+}
+
+fn call(function: Function, a: Expr) -> Expr {
+    match a {
+        Expr::Number(x) => Expr::Number(function.apply(x)),
+        a => Expr::Call(function, Box::new(a)),
+    }
+}
+
+impl Expr {
+    pub fn derivative(&self, var: Var) -> Expr {
+        match self {
+            Expr::Number(_) => Expr::Number(0.),
+            Expr::Var(v) => Expr::Number(if *v == var { 1. } else { 0. }),
+            Expr::Neg(a) => neg(a.derivative(var)),
+            Expr::Add(a, b) => add(a.derivative(var), b.derivative(var)),
+            Expr::Sub(a, b) => sub(a.derivative(var), b.derivative(var)),
+            Expr::Mul(a, b) => add(
+                mul(a.derivative(var), (**b).clone()),
+                mul((**a).clone(), b.derivative(var)),
+            ),
+            Expr::Div(a, b) => div(
+                sub(
+                    mul(a.derivative(var), (**b).clone()),
+                    mul((**a).clone(), b.derivative(var)),
+                ),
+                pow((**b).clone(), Expr::Number(2.)),
+            ),
+            Expr::Pow(a, b) => match **b {
+                // keeps negative bases working for integer exponents
+                Expr::Number(n) => mul(
+                    mul(Expr::Number(n), pow((**a).clone(), Expr::Number(n - 1.))),
+                    a.derivative(var),
+                ),
+                _ => mul(
+                    self.clone(),
+                    add(
+                        mul(b.derivative(var), call(Function::Ln, (**a).clone())),
+                        div(mul((**b).clone(), a.derivative(var)), (**a).clone()),
+                    ),
+                ),
+            },
+            Expr::Call(function, a) => {
+                let outer = match function {
+                    Function::Sin => call(Function::Cos, (**a).clone()),
+                    Function::Cos => neg(call(Function::Sin, (**a).clone())),
+                    Function::Tan => div(Expr::Number(1.), pow(call(Function::Cos, (**a).clone()), Expr::Number(2.))),
+                    Function::Exp => self.clone(),
+                    Function::Ln => div(Expr::Number(1.), (**a).clone()),
+                    Function::Sqrt => div(Expr::Number(0.5), self.clone()),
+                };
+                mul(outer, a.derivative(var))
+            }
+        }
+    }
+}
+/* #endregion */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, r: f64, theta: f64) -> f64 {
+        Expr::parse(source).unwrap().eval(r, theta)
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("1+2*3", 0., 0.), 7.);
+        assert_eq!(eval("(1+2)*3", 0., 0.), 9.);
+        assert_eq!(eval("8/4/2", 0., 0.), 1.);
+        assert_eq!(eval("1-2-3", 0., 0.), -4.);
+        assert_eq!(eval("2*r^2", 3., 0.), 18.);
+        assert_eq!(eval("1e-3 * 2", 0., 0.), 2e-3);
+        assert!((eval("sin(θ)^2 + cos(theta)^2", 0., 0.7) - 1.).abs() < 1e-15);
+        assert!((eval("pi", 0., 0.) - PI).abs() < 1e-15);
+    }
+
+    // the minus binds less tightly than ^, and ^ groups to the right with a signed exponent
+    #[test]
+    fn unary_minus_and_powers() {
+        assert_eq!(eval("-2^2", 0., 0.), -4.);
+        assert_eq!(eval("(-2)^2", 0., 0.), 4.);
+        assert_eq!(eval("--r", 5., 0.), 5.);
+        assert_eq!(eval("2*-r", 5., 0.), -10.);
+        assert_eq!(eval("2^3^2", 0., 0.), 512.);
+        assert_eq!(eval("2^-1", 0., 0.), 0.5);
+        assert_eq!(eval("-(1-2/r)", 4., 0.), -0.5);
+    }
+
+    #[test]
+    fn parse_errors() {
+        for source in ["", "1+", "(1+2", "1+2)", "2**3", "foo(r)", "sin r", "1..2", "r r", "^2"] {
+            assert!(Expr::parse(source).is_err(), "{source}");
+        }
+        assert_eq!(Expr::parse("foo(r)"), Err("Unknown name 'foo'.".to_string()));
+        assert_eq!(Expr::parse("(1+2"), Err("Expected ')', found the end.".to_string()));
+    }
+
+    // against central differences, at points where every function is smooth
+    #[test]
+    fn derivatives() {
+        let sources = [
+            "-(1-2/r)",
+            "1/(1-2/r)",
+            "r^2*sin(θ)^2",
+            "exp(-r/3)*cos(2*θ)",
+            "ln(r)*tan(θ/2)",
+            "sqrt(r^2+θ)",
+            "r^θ",
+            "(r-1)^3/(θ+1)",
+        ];
+        let h = 1e-6;
+        for source in sources {
+            let expr = Expr::parse(source).unwrap();
+            let (dr, dtheta) = (expr.derivative(Var::R), expr.derivative(Var::Theta));
+            for (r, theta) in [(3., 0.4), (7.5, 1.2), (2.5, 2.8)] {
+                let numeric_dr = (expr.eval(r + h, theta) - expr.eval(r - h, theta)) / (2. * h);
+                let numeric_dtheta = (expr.eval(r, theta + h) - expr.eval(r, theta - h)) / (2. * h);
+                assert!((dr.eval(r, theta) - numeric_dr).abs() < 1e-6 * numeric_dr.abs().max(1.), "{source} d/dr at {r}, {theta}");
+                assert!((dtheta.eval(r, theta) - numeric_dtheta).abs() < 1e-6 * numeric_dtheta.abs().max(1.), "{source} d/dθ at {r}, {theta}");
+            }
+        }
+    }
+
+    // the constants are folded away, so the derivative of a constant is a number and x^1 is x
+    #[test]
+    fn constant_folding() {
+        assert_eq!(Expr::parse("3*2^2").unwrap().derivative(Var::R), Expr::Number(0.));
+        assert_eq!(Expr::parse("r").unwrap().derivative(Var::R), Expr::Number(1.));
+        assert_eq!(Expr::parse("r^2").unwrap().derivative(Var::Theta), Expr::Number(0.));
+        assert_eq!(Expr::parse("r^2").unwrap().derivative(Var::R), Expr::parse("2*r").unwrap());
+    }
+}
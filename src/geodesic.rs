@@ -0,0 +1,416 @@
+use bevy::math::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::metric::{Horizons, Metric};
+
+// timelike geodesic of a free-falling observer in the coordinates of the metric, parametrized by proper time;
+// the energy and angular momentum per unit mass are conserved, so only r and theta need second order equations
+#[derive(Debug, Clone, Copy)]
+pub struct Geodesic {
+    pub energy: f64,
+    pub angular_momentum: f64,
+    pub x: [f64; 5], // r, theta, phi, dr/dtau, dtheta/dtau
+    pub proper_time: f64,
+}
+
+/* #region metric helpers */
+// the components that stay finite at the horizons, g^rr in place of g_rr
+#[derive(Debug, Clone, Copy)]
+struct Smooth {
+    g_tt: f64,
+    g_tphi: f64,
+    g_phiphi: f64,
+    g_thetatheta: f64,
+    inverse_g_rr: f64,
+}
+
+fn smooth(metric: &dyn Metric, r: f64, theta: f64) -> Smooth {
+    let g = metric.components(r, theta);
+    Smooth {
+        g_tt: g.g_tt,
+        g_tphi: g.g_tphi,
+        g_phiphi: g.g_phiphi,
+        g_thetatheta: g.g_thetatheta,
+        inverse_g_rr: 1. / g.g_rr,
+    }
+}
+
+// central differences in r and theta
+fn partial_derivatives(f: impl Fn(f64, f64) -> f64, r: f64, theta: f64) -> (f64, f64) {
+    let h_r = 1e-6 * r.max(1.);
+    let h_theta = 1e-6;
+    (
+        (f(r + h_r, theta) - f(r - h_r, theta)) / (2. * h_r),
+        (f(r, theta + h_theta) - f(r, theta - h_theta)) / (2. * h_theta),
+    )
+}
+
+// g^{mu nu} alpha_mu beta_nu for covectors (t, r, theta, phi)
+fn dot(g: &Smooth, alpha: [f64; 4], beta: [f64; 4]) -> f64 {
+    let det = g.g_tt * g.g_phiphi - g.g_tphi * g.g_tphi;
+    g.g_phiphi / det * alpha[0] * beta[0]
+        - g.g_tphi / det * (alpha[0] * beta[3] + alpha[3] * beta[0])
+        + g.g_tt / det * alpha[3] * beta[3]
+        + g.inverse_g_rr * alpha[1] * beta[1]
+        + alpha[2] * beta[2] / g.g_thetatheta
+}
+
+fn raise(g: &Smooth, alpha: [f64; 4]) -> [f64; 4] {
+    let basis = |i: usize| {
+        let mut e = [0.; 4];
+        e[i] = 1.;
+        e
+    };
+    [0, 1, 2, 3].map(|i| dot(g, basis(i), alpha))
+}
+
+fn contract(alpha: [f64; 4], v: [f64; 4]) -> f64 {
+    alpha.iter().zip(v).map(|(a, b)| a * b).sum()
+}
+
+fn combine(a: [f64; 4], b: [f64; 4], factor: f64) -> [f64; 4] {
+    [0, 1, 2, 3].map(|i| a[i] + factor * b[i])
+}
+
+// falling in radially from rest at infinity, or with unit energy where that isn't possible; u^r = g^rr u_r < 0
+fn infalling(g: &Smooth) -> [f64; 4] {
+    let dt = [1., 0., 0., 0.];
+    let u_r = ((-1. - dot(g, dt, dt)) / g.inverse_g_rr).max(0.).sqrt();
+    [-1., -g.inverse_g_rr.signum() * u_r, 0., 0.]
+}
+
+// the observers the shaders set up the rays for as a covector, followed by an orthonormal frame of covectors
+// made by gram-schmidt from dr, dtheta and dphi, the same as in shaders/metrics/kerr.wgsl;
+// the zero angular momentum observer where there is one, the one falling in radially with unit energy elsewhere
+fn reference_frame(metric: &dyn Metric, r: f64, theta: f64) -> [[f64; 4]; 4] {
+    let g = smooth(metric, r, theta);
+
+    let u = if g.inverse_g_rr > 0. {
+        let dt = [1., 0., 0., 0.];
+        let alpha = (-1. / dot(&g, dt, dt)).sqrt();
+        [-alpha, 0., 0., 0.]
+    } else {
+        infalling(&g)
+    };
+
+    let mut frame = [u, [0., 1., 0., 0.], [0., 0., 1., 0.], [0., 0., 0., 1.]];
+    for i in 1..4 {
+        let mut e = combine(frame[i], u, dot(&g, frame[i], u));
+        for e_j in &frame[1..i] {
+            e = combine(e, *e_j, -dot(&g, e, *e_j));
+        }
+        let norm = dot(&g, e, e).sqrt();
+        frame[i] = e.map(|x| x / norm);
+    }
+    frame
+}
+/* #endregion */
+
+impl Geodesic {
+    // velocity in units of c along r, theta and phi of the reference frame, None for velocities of c and more
+    // or where the coordinates break down
+    pub fn new(metric: &dyn Metric, pos: Vec3, velocity: [f64; 3]) -> Option<Self> {
+        let (r, theta) = metric.to_coordinates(pos);
+        let phi = (-pos.z as f64).atan2(pos.x as f64);
+
+        let speed2: f64 = velocity.iter().map(|v| v * v).sum();
+        if speed2 >= 1. {
+            return None;
+        }
+        let gamma = 1. / (1. - speed2).sqrt();
+
+        let frame = reference_frame(metric, r, theta);
+        let mut u = frame[0].map(|x| gamma * x);
+        for (e, v) in frame[1..].iter().zip(velocity) {
+            u = combine(u, *e, gamma * v);
+        }
+        let u_vector = raise(&smooth(metric, r, theta), u);
+
+        let geodesic = Geodesic {
+            energy: -u[0],
+            angular_momentum: u[3],
+            x: [r, theta, phi, u_vector[1], u_vector[2]],
+            proper_time: 0.,
+        };
+        geodesic.x.iter().all(|x| x.is_finite()).then_some(geodesic)
+    }
+
+    fn r(&self) -> f64 {
+        self.x[0]
+    }
+
+    // dt/dtau and dphi/dtau from the conserved quantities
+    fn u_t_phi(&self, g: &Smooth) -> (f64, f64) {
+        let det = g.g_tt * g.g_phiphi - g.g_tphi * g.g_tphi;
+        let e = self.energy;
+        let l = self.angular_momentum;
+        (-(g.g_phiphi * e + g.g_tphi * l) / det, (g.g_tphi * e + g.g_tt * l) / det)
+    }
+
+    // g^ab p_a p_b over t and phi, it diverges at the horizons but g^rr times it doesn't
+    fn potential(&self, g: &Smooth) -> f64 {
+        let p = [-self.energy, 0., 0., self.angular_momentum];
+        dot(g, p, p)
+    }
+
+    // (dr/dtau)^2 from the normalization of the 4-velocity
+    fn radial_velocity2(&self, metric: &dyn Metric, r: f64, theta: f64, u_theta: f64) -> f64 {
+        let g = smooth(metric, r, theta);
+        -g.inverse_g_rr * (1. + g.g_thetatheta * u_theta * u_theta + self.potential(&g))
+    }
+
+    // the geodesic equation; in boyer-lindquist like coordinates the terms with dt/dtau and d(g_rr)/dr diverge
+    // at the horizons and cancel each other, so they are combined through the normalization before differentiating
+    fn derivative(&self, metric: &dyn Metric, x: [f64; 5]) -> [f64; 5] {
+        let [r, theta, _, u_r, u_theta] = x;
+        let g = smooth(metric, r, theta);
+        let (_, u_phi) = self.u_t_phi(&g);
+
+        let (d_r_inverse_g_rr, d_theta_inverse_g_rr) = partial_derivatives(|r, theta| smooth(metric, r, theta).inverse_g_rr, r, theta);
+        let (d_r_g_thetatheta, d_theta_g_thetatheta) = partial_derivatives(|r, theta| smooth(metric, r, theta).g_thetatheta, r, theta);
+        let (d_r_w, _) = partial_derivatives(|r, theta| {
+            let g = smooth(metric, r, theta);
+            g.inverse_g_rr * self.potential(&g)
+        }, r, theta);
+        let (_, d_theta_y) = partial_derivatives(|r, theta| {
+            let g = smooth(metric, r, theta);
+            self.potential(&g) / g.inverse_g_rr
+        }, r, theta);
+
+        let inverse_g_rr = g.inverse_g_rr;
+        let inverse_g_thetatheta = 1. / g.g_thetatheta;
+        let normalization = 1. + g.g_thetatheta * u_theta * u_theta;
+
+        let a_r = -0.5 * d_r_w
+            - 0.5 * d_r_inverse_g_rr * normalization
+            + d_theta_inverse_g_rr / inverse_g_rr * u_r * u_theta
+            + 0.5 * inverse_g_rr * d_r_g_thetatheta * u_theta * u_theta;
+        let a_theta = -0.5 * inverse_g_thetatheta * inverse_g_rr * d_theta_y
+            + 0.5 * inverse_g_thetatheta * d_theta_inverse_g_rr / inverse_g_rr * normalization
+            - inverse_g_thetatheta * d_r_g_thetatheta * u_r * u_theta
+            - 0.5 * inverse_g_thetatheta * d_theta_g_thetatheta * u_theta * u_theta;
+
+        [u_r, u_theta, u_phi + metric.phi_shift(r) * u_r, a_r, a_theta]
+    }
+
+    fn rk4(&mut self, metric: &dyn Metric, h: f64) {
+        let add = |x: [f64; 5], d: [f64; 5], h: f64| [0, 1, 2, 3, 4].map(|i| x[i] + h * d[i]);
+
+        let k1 = self.derivative(metric, self.x);
+        let k2 = self.derivative(metric, add(self.x, k1, h / 2.));
+        let k3 = self.derivative(metric, add(self.x, k2, h / 2.));
+        let k4 = self.derivative(metric, add(self.x, k3, h));
+
+        self.x = [0, 1, 2, 3, 4].map(|i| self.x[i] + h / 6. * (k1[i] + 2. * k2[i] + 2. * k3[i] + k4[i]));
+        self.proper_time += h;
+
+        // keeps the 4-velocity normalized, the steps over a horizon would throw it off otherwise
+        let [r, theta, _, u_r, u_theta] = self.x;
+        let u_r2 = self.radial_velocity2(metric, r, theta, u_theta);
+        if u_r2 > 0. {
+            self.x[3] = u_r.signum() * u_r2.sqrt();
+        }
+    }
+
+    // false once the observer can't be followed any further, at the singularity, an inner horizon,
+    // a wormhole throat or the cosmological horizon
+    pub fn advance(&mut self, metric: &dyn Metric, horizons: &Horizons, proper_time: f64) -> bool {
+        let r_min = horizons.inner.or(metric.throat()).unwrap_or(0.) + 1e-2;
+        let r_max = horizons.cosmological.map_or(f64::INFINITY, |r_c| r_c * 0.99);
+
+        // the steps shrink with the free fall time scale, which goes as r^(3/2)
+        let mut remaining = proper_time;
+        for _ in 0..1000 {
+            if remaining <= 0. {
+                break;
+            }
+            let h = remaining.min(0.01 * self.r().powf(1.5)).max(1e-6);
+            self.rk4(metric, h);
+            remaining -= h;
+
+            if !self.x.iter().all(|x| x.is_finite()) || self.r() <= r_min || self.r() >= r_max {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn position(&self, metric: &dyn Metric) -> Vec3 {
+        metric.chart_position(self.x[0], self.x[1], self.x[2])
+    }
+
+    // relative to the reference frame of the shaders, in the directions of the chart
+    pub fn velocity(&self, metric: &dyn Metric) -> Vec3 {
+        let [r, theta, phi, u_r, u_theta] = self.x;
+        let (u_t, u_phi) = self.u_t_phi(&smooth(metric, r, theta));
+        relative_velocity(metric, r, theta, phi, [u_t, u_r, u_theta, u_phi])
+    }
+}
+
+// velocity of the observer with the 4-velocity u relative to the reference frame, in the directions of the chart
+fn relative_velocity(metric: &dyn Metric, r: f64, theta: f64, phi: f64, u: [f64; 4]) -> Vec3 {
+    let frame = reference_frame(metric, r, theta);
+    let gamma = -contract(frame[0], u);
+    let v = [1, 2, 3].map(|i| contract(frame[i], u) / gamma);
+
+    // directions of r, theta and phi in the chart
+    let h = 1e-4;
+    let pos = metric.chart_position(r, theta, phi);
+    let directions = [
+        (metric.chart_position(r + h * r, theta, phi) - pos).normalize_or_zero(),
+        (metric.chart_position(r, theta + h, phi) - pos).normalize_or_zero(),
+        (metric.chart_position(r, theta, phi + h) - pos).normalize_or_zero(),
+    ];
+
+    directions.iter().zip(v).map(|(direction, v)| *direction * v as f32).sum()
+}
+
+/* #region observers */
+// the observers the camera can ride along with when it isn't falling freely
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Observer {
+    #[default]
+    Static,
+    Zamo,
+    CircularOrbit,
+    Infalling,
+}
+
+impl Observer {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Observer::Static => "Static",
+            Observer::Zamo => "Zero angular momentum",
+            Observer::CircularOrbit => "Circular orbit",
+            Observer::Infalling => "Falling in from rest",
+        }
+    }
+
+    // the one after this in the sidebar
+    pub fn next(&self) -> Self {
+        match self {
+            Observer::Static => Observer::Zamo,
+            Observer::Zamo => Observer::CircularOrbit,
+            Observer::CircularOrbit => Observer::Infalling,
+            Observer::Infalling => Observer::Static,
+        }
+    }
+
+    // 4-velocity at (r, theta), None where there is no such observer
+    fn four_velocity(&self, metric: &dyn Metric, r: f64, theta: f64) -> Option<[f64; 4]> {
+        let g = smooth(metric, r, theta);
+        let rotating = |omega: f64| {
+            let norm = -(g.g_tt + 2. * g.g_tphi * omega + g.g_phiphi * omega * omega);
+            (norm > 0.).then(|| [1. / norm.sqrt(), 0., 0., omega / norm.sqrt()])
+        };
+
+        match self {
+            Observer::Static => rotating(0.),
+            Observer::Zamo => rotating(-g.g_tphi / g.g_phiphi),
+            // circling the spin axis with the angular velocity of the circular orbit at that r in the equatorial plane
+            Observer::CircularOrbit => rotating(metric.orbital_angular_velocity(r)),
+            Observer::Infalling => Some(raise(&g, infalling(&g))),
+        }
+        .filter(|u| u.iter().all(|x| x.is_finite()))
+    }
+
+    // velocity relative to the reference frame of the shaders, 0 where there is no such observer
+    pub fn velocity(&self, metric: &dyn Metric, pos: Vec3) -> Vec3 {
+        let (r, theta) = metric.to_coordinates(pos);
+        let phi = (-pos.z as f64).atan2(pos.x as f64);
+
+        self.four_velocity(metric, r, theta).map_or(Vec3::ZERO, |u| relative_velocity(metric, r, theta, phi, u))
+    }
+}
+/* #endregion */
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use super::*;
+    use crate::metric::KerrNewmanDeSitter;
+
+    // g_ab u^a u^b, -1 for an observer
+    fn norm(metric: &dyn Metric, r: f64, theta: f64, u: [f64; 4]) -> f64 {
+        let g = metric.components(r, theta);
+        g.g_tt * u[0] * u[0] + 2. * g.g_tphi * u[0] * u[3] + g.g_phiphi * u[3] * u[3] + g.g_rr * u[1] * u[1] + g.g_thetatheta * u[2] * u[2]
+    }
+
+    fn four_velocity(metric: &dyn Metric, geodesic: &Geodesic) -> [f64; 4] {
+        let [r, theta, _, u_r, u_theta] = geodesic.x;
+        let (u_t, u_phi) = geodesic.u_t_phi(&smooth(metric, r, theta));
+        [u_t, u_r, u_theta, u_phi]
+    }
+
+    #[test]
+    fn observers_are_normalized() {
+        let metric = KerrNewmanDeSitter { a: 0.7, q: 0.2, lambda: 0. };
+        for (r, theta) in [(8., FRAC_PI_2), (4., 1.), (20., 0.3)] {
+            for observer in [Observer::Static, Observer::Zamo, Observer::CircularOrbit, Observer::Infalling] {
+                let u = observer.four_velocity(&metric, r, theta).unwrap();
+                assert!((norm(&metric, r, theta, u) + 1.).abs() < 1e-9, "{observer:?} at {r}, {theta}");
+            }
+        }
+        // nothing can stay put inside of the ergosphere
+        assert!(Observer::Static.four_velocity(&metric, 1.9, FRAC_PI_2).is_none());
+    }
+
+    #[test]
+    fn normalization_along_the_path() {
+        let metric = KerrNewmanDeSitter { a: 0.7, q: 0., lambda: 0. };
+        let horizons = metric.horizons().unwrap();
+        let pos = metric.chart_position(12., 1.2, 0.3);
+        let mut geodesic = Geodesic::new(&metric, pos, [-0.2, 0.1, 0.25]).unwrap();
+
+        for _ in 0..20 {
+            assert!(geodesic.advance(&metric, &horizons, 5.));
+            let [r, theta, ..] = geodesic.x;
+            let u = four_velocity(&metric, &geodesic);
+            assert!((norm(&metric, r, theta, u) + 1.).abs() < 1e-6, "at tau {}", geodesic.proper_time);
+        }
+    }
+
+    // r = r0 / 2 (1 + cos eta), tau = sqrt(r0^3 / 8) (eta + sin eta), which is pi sqrt(r0^3 / 8) at the singularity
+    #[test]
+    fn radial_infall() {
+        let metric = KerrNewmanDeSitter { a: 0., q: 0., lambda: 0. };
+        let horizons = metric.horizons().unwrap();
+        let r0: f64 = 10.;
+        let mut geodesic = Geodesic::new(&metric, metric.chart_position(r0, FRAC_PI_2, 0.), [0.; 3]).unwrap();
+
+        while geodesic.advance(&metric, &horizons, 0.1) {}
+
+        let eta = (2. * geodesic.r() / r0 - 1.).acos();
+        let expected = (r0 * r0 * r0 / 8.).sqrt() * (eta + eta.sin());
+        assert!((geodesic.proper_time - expected).abs() < 1e-3, "{} instead of {expected}", geodesic.proper_time);
+        assert!((geodesic.proper_time - PI * (r0 * r0 * r0 / 8.).sqrt()).abs() < 1e-2);
+    }
+
+    // started with the velocity of the circular orbit observer in the equatorial plane, it stays at its r
+    #[test]
+    fn circular_orbit() {
+        for metric in [KerrNewmanDeSitter { a: 0., q: 0., lambda: 0. }, KerrNewmanDeSitter { a: 0.5, q: 0., lambda: 0. }] {
+            let horizons = metric.horizons().unwrap();
+            let r = 10.;
+            let u = Observer::CircularOrbit.four_velocity(&metric, r, FRAC_PI_2).unwrap();
+            let g = smooth(&metric, r, FRAC_PI_2);
+            let energy = -(g.g_tt * u[0] + g.g_tphi * u[3]);
+            let angular_momentum = g.g_tphi * u[0] + g.g_phiphi * u[3];
+            let frame = reference_frame(&metric, r, FRAC_PI_2);
+            let gamma = -contract(frame[0], u);
+            let velocity = [1, 2, 3].map(|i| contract(frame[i], u) / gamma);
+            assert!(velocity[0].abs() < 1e-9 && velocity[1].abs() < 1e-9, "{velocity:?}");
+
+            let mut geodesic = Geodesic::new(&metric, metric.chart_position(r, FRAC_PI_2, 0.), velocity).unwrap();
+            assert!((geodesic.energy - energy).abs() < 1e-5 && (geodesic.angular_momentum - angular_momentum).abs() < 1e-4);
+
+            // about one orbit
+            for _ in 0..20 {
+                assert!(geodesic.advance(&metric, &horizons, 10.));
+                assert!((geodesic.r() - r).abs() < 1e-3, "a {} r {} at tau {}", metric.a, geodesic.r(), geodesic.proper_time);
+                assert!((geodesic.x[1] - FRAC_PI_2).abs() < 1e-6);
+            }
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::math::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::SpacetimeParams;
+
+// what the camera and the spacetime look like at a time of a camera path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    pub time: f64, // seconds from the start of the path
+    pub eye: Vec3,
+    pub target: Vec3,
+    pub fov: f32, // in degrees
+    pub spacetime: SpacetimeParams,
+}
+
+const CHANNEL_CNT: usize = 12;
+
+impl Keyframe {
+    // everything that is interpolated, the mass in its logarithm so it stays positive and changes by the same
+    // factor over equal times
+    fn channels(&self) -> [f64; CHANNEL_CNT] {
+        let spacetime = &self.spacetime;
+        [
+            self.eye.x as f64,
+            self.eye.y as f64,
+            self.eye.z as f64,
+            self.target.x as f64,
+            self.target.y as f64,
+            self.target.z as f64,
+            self.fov as f64,
+            spacetime.mass.ln(),
+            spacetime.a,
+            spacetime.q,
+            spacetime.lambda,
+            spacetime.throat_radius,
+        ]
+    }
+
+    // the metric and the user-defined components of self, which can't be interpolated
+    fn with_channels(&self, time: f64, channels: [f64; CHANNEL_CNT]) -> Keyframe {
+        let [eye_x, eye_y, eye_z, target_x, target_y, target_z, fov, ln_mass, a, q, lambda, throat_radius] = channels;
+        Keyframe {
+            time,
+            eye: Vec3::new(eye_x as f32, eye_y as f32, eye_z as f32),
+            target: Vec3::new(target_x as f32, target_y as f32, target_z as f32),
+            fov: fov as f32,
+            spacetime: SpacetimeParams {
+                mass: ln_mass.exp(),
+                a,
+                q,
+                lambda,
+                throat_radius,
+                ..self.spacetime.clone()
+            },
+        }
+    }
+}
+
+// keyframes sorted by time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CameraPath {
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl CameraPath {
+    // replaces the one at the same time
+    pub fn insert(&mut self, keyframe: Keyframe) {
+        self.keyframes.retain(|other| other.time != keyframe.time);
+        let index = self.keyframes.partition_point(|other| other.time < keyframe.time);
+        self.keyframes.insert(index, keyframe);
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.keyframes.last().map_or(0., |keyframe| keyframe.time)
+    }
+
+    // d/dt of the channels at a keyframe, central differences inside and one-sided ones at the ends
+    fn tangent(&self, index: usize) -> [f64; CHANNEL_CNT] {
+        let before = &self.keyframes[index.saturating_sub(1)];
+        let after = &self.keyframes[(index + 1).min(self.keyframes.len() - 1)];
+        let (p0, p1) = (before.channels(), after.channels());
+        let dt = after.time - before.time;
+        std::array::from_fn(|i| if dt > 0. { (p1[i] - p0[i]) / dt } else { 0. })
+    }
+
+    // cubic hermite spline through the keyframes with catmull-rom tangents, which follows straight lines exactly;
+    // held at the first and the last keyframe outside of the path
+    pub fn sample(&self, time: f64) -> Option<Keyframe> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        if time <= first.time {
+            return Some(first.with_channels(time, first.channels()));
+        }
+        if time >= last.time {
+            return Some(last.with_channels(time, last.channels()));
+        }
+
+        let index = self.keyframes.partition_point(|keyframe| keyframe.time <= time) - 1;
+        let (k0, k1) = (&self.keyframes[index], &self.keyframes[index + 1]);
+        let (p0, p1) = (k0.channels(), k1.channels());
+        let (m0, m1) = (self.tangent(index), self.tangent(index + 1));
+
+        let h = k1.time - k0.time;
+        let s = (time - k0.time) / h;
+        let (s2, s3) = (s * s, s * s * s);
+        let h00 = 2. * s3 - 3. * s2 + 1.;
+        let h10 = s3 - 2. * s2 + s;
+        let h01 = -2. * s3 + 3. * s2;
+        let h11 = s3 - s2;
+
+        let channels = std::array::from_fn(|i| h00 * p0[i] + h10 * h * m0[i] + h01 * p1[i] + h11 * h * m1[i]);
+        Some(k0.with_channels(time, channels))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(time: f64, eye: Vec3, mass: f64) -> Keyframe {
+        Keyframe {
+            time,
+            eye,
+            target: Vec3::ZERO,
+            fov: 90.,
+            spacetime: SpacetimeParams { mass, ..SpacetimeParams::default() },
+        }
+    }
+
+    #[test]
+    fn passes_through_the_keyframes() {
+        let mut path = CameraPath::default();
+        path.insert(keyframe(3., Vec3::new(0., 5., 20.), 1e35));
+        path.insert(keyframe(0., Vec3::new(0., 10., 40.), 1e34));
+        path.insert(keyframe(1., Vec3::new(30., 0., 0.), 2e34));
+        assert_eq!(path.keyframes.iter().map(|keyframe| keyframe.time).collect::<Vec<_>>(), [0., 1., 3.]);
+
+        for keyframe in &path.keyframes {
+            let sample = path.sample(keyframe.time).unwrap();
+            assert!((sample.eye - keyframe.eye).length() < 1e-4);
+            assert!((sample.spacetime.mass / keyframe.spacetime.mass - 1.).abs() < 1e-9);
+        }
+        assert!((path.sample(-1.).unwrap().eye - path.keyframes[0].eye).length() < 1e-6);
+        assert!((path.sample(10.).unwrap().eye - path.keyframes[2].eye).length() < 1e-6);
+    }
+
+    #[test]
+    fn follows_straight_lines() {
+        let mut path = CameraPath::default();
+        for (time, x) in [(0., 0.), (1., 10.), (4., 40.), (5., 50.)] {
+            path.insert(keyframe(time, Vec3::new(x, 0., 20.), 1e34));
+        }
+
+        for i in 0..50 {
+            let time = i as f64 / 10.;
+            let sample = path.sample(time).unwrap();
+            assert!((sample.eye.x as f64 - 10. * time).abs() < 1e-4, "time {time}");
+        }
+    }
+}
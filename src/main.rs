@@ -1,14 +1,68 @@
-use std::{f32::consts::PI, vec};
+use std::{f32::consts::PI, fs, path::{Path, PathBuf}, vec};
 
-use bevy::{math::vec3, prelude::*, render::{camera::ScalingMode, render_resource::{AsBindGroup, ShaderRef}}, sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle}};
+use bevy::{asset::LoadState, math::vec3, prelude::*, render::{camera::ScalingMode, mesh::MeshVertexBufferLayout, render_resource::{AsBindGroup, RenderPipelineDescriptor, ShaderRef, ShaderType, SpecializedMeshPipelineError}, view::screenshot::ScreenshotManager}, sprite::{Material2d, Material2dKey, Material2dPlugin, MaterialMesh2dBundle}, tasks::{block_on, poll_once, AsyncComputeTaskPool, Task}, window::PrimaryWindow};
 use bevy_simple_text_input::{TextInputBundle, TextInputInactive, TextInputPlugin, TextInputValue};
+use serde::{Deserialize, Serialize};
 use smooth_bevy_cameras::{controllers::unreal::{UnrealCameraBundle, UnrealCameraController, UnrealCameraPlugin}, LookTransform, LookTransformPlugin};
 
+use catalogue::StarCatalogue;
+use deflection::deflection_table;
+use disc::{DiscEmission, DiscShape};
+use geodesic::{Geodesic, Observer};
+use keyframes::{CameraPath, Keyframe};
+use metric::{EllisWormhole, Horizons, KerrNewmanDeSitter, Metric, MetricKind, UserDefined};
+use scene::SceneFile;
+use skybox::{assemble_cube, cube_from_faces, into_cube, placeholder_cube, SkyboxLoad, SkyboxProjection, SkyboxSource};
+use spacescape::SpacescapeProject;
+use starfield::Starfield;
+
+mod catalogue;
+mod deflection;
+mod disc;
+mod elliptic;
+mod expression;
+mod geodesic;
+mod keyframes;
+mod metric;
+mod offline;
+mod reference;
+mod scene;
+mod skybox;
+mod spacescape;
+mod starfield;
+
 fn main() {
+    // see offline::run
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).is_some_and(|command| command == "render") {
+        if let Err(error) = offline::run(&args[2..]) {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // the scene to start with, see scene::SceneFile
+    let scene_path = args.get(1).cloned();
+    let scene = match &scene_path {
+        Some(path) => SceneFile::load(Path::new(path)).unwrap_or_else(|error| {
+            eprintln!("{error}");
+            std::process::exit(1);
+        }),
+        None => SceneFile::default(),
+    };
+
     App::new()
         .insert_resource(WindowData::default())
-        .insert_resource(CamData::default())
-        .insert_resource(SpacetimeParams::default())
+        .insert_resource(CamData { observer: scene.camera.observer, ..default() })
+        .insert_resource(scene.spacetime.clone())
+        .insert_resource(FreeFall::default())
+        .insert_resource(scene.accretion_disc.params.clone())
+        .insert_resource(scene.jets.clone())
+        .insert_resource(scene.ray_tracing.clone())
+        .insert_resource(CameraPathPlayer { path: scene.keyframes.clone(), ..default() })
+        .insert_resource(Skyboxes::new(scene.skybox.sources(), scene.skybox.starfield.clone(), scene.skybox.catalogue.clone()))
+        .insert_resource(StartupScene { scene, path: scene_path.unwrap_or(DEFAULT_SCENE_PATH.to_string()) })
         .insert_resource(Msaa::default())
         .add_plugins((
             DefaultPlugins.set(WindowPlugin {
@@ -19,17 +73,28 @@ fn main() {
                 ..default()
             }),
         ))
-        .add_plugins(Material2dPlugin::<SchwarzschildMaterial>::default())
+        .add_plugins(Material2dPlugin::<SpacetimeMaterial>::default())
         .add_plugins(TextInputPlugin)
         .add_plugins((LookTransformPlugin, UnrealCameraPlugin::default()))
         .add_systems(Startup, setup)
-        .add_systems(Update, (focus, update_window_data, update_material, update_camera_data, update_position_text, update_spacetime_params))
+        .add_systems(Update, (focus, update_window_data, update_material.after(update_camera_data), update_camera_data, update_position_text, update_spacetime_params, switch_metric, update_metric_table, update_deflection_table.after(update_camera_data)))
+        .add_systems(Update, cross_wormhole_throat.before(update_camera_data))
+        .add_systems(Update, (update_free_fall.before(update_camera_data), update_free_fall_params, toggle_free_fall, drop_camera, update_free_fall_button))
+        .add_systems(Update, (update_observer.after(update_free_fall).after(update_camera_data), switch_observer))
+        .add_systems(Update, (update_accretion_disc, update_accretion_disc_params, switch_disc_emission, switch_disc_shape))
+        .add_systems(Update, (update_jets, update_jets_params, toggle_jets))
+        .add_systems(Update, (update_ray_tracing, update_ray_tracing_params, switch_integrator, toggle_deflection_table))
+        .add_systems(Update, (update_skyboxes, switch_skyboxes.after(update_starfield_params), update_starfield_params))
+        .add_systems(Update, (save_scene, load_scene))
+        .add_systems(Update, (update_camera_path_params, add_keyframe, clear_keyframes, toggle_playback, toggle_export, update_camera_path_buttons))
+        .add_systems(Update, (play_camera_path.after(update_spacetime_params).before(update_camera_data).before(update_material), export_camera_path.after(play_camera_path)))
         .run();
 }
 
 const INPUT_BORDER_COLOR_ACTIVE: Color = Color::rgb(0.4, 0.4, 0.4);
 const INPUT_BORDER_COLOR_INACTIVE: Color = Color::rgb(0.25, 0.25, 0.25);
 const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+const MESSAGE_COLOR: Color = Color::rgb(0.9, 0.6, 0.3);
 const INPUT_BG_COLOR: Color = Color::rgb(0.15, 0.15, 0.15);
 const SIDEBAR_BG_COLOR: Color = Color::rgba(0.1, 0.1, 0.1, 0.9);
 
@@ -44,77 +109,203 @@ fn length_to_si(val: f32, mass: f64) -> f64 {
     mass * ((val * NEWTON_CONSTANT / (LIGHT_SPEED * LIGHT_SPEED)) as f64)
 }
 
+fn time_to_si(val: f32, mass: f64) -> f64 {
+    mass * ((val * NEWTON_CONSTANT / LIGHT_SPEED.powf(3.)) as f64)
+}
+
 fn time_to_geo(val: f32, mass: f64) -> f64 {
     (val * LIGHT_SPEED.powf(3.) / NEWTON_CONSTANT) as f64 / mass
 }
 /* #endregion */
 
 /* #region shader */
-#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
-struct SchwarzschildMaterial {
-    #[texture(0)]
-    #[sampler(1)]
-    up_texture: Handle<Image>,
+#[derive(ShaderType, Debug, Clone, Default)]
+struct SpacetimeUniform {
+    a: f32,
+    q: f32,
+    lambda: f32,
 
-    #[texture(2)]
-    #[sampler(3)]
-    down_texture: Handle<Image>,
+    // derived on the cpu
+    horizon: f32,
+    cosmological_horizon: f32, // 0 when there is none
+
+    throat_radius: f32,
+    camera_side: f32, // 1 in the universe of the first skybox, -1 in the other one
+
+    // r range of the user-defined metric table
+    table_r_min: f32,
+    table_r_max: f32,
+}
 
-    #[texture(4)]
-    #[sampler(5)]
-    left_texture: Handle<Image>,
+impl TryFrom<&dyn Metric> for SpacetimeUniform {
+    type Error = String;
 
-    #[texture(6)]
-    #[sampler(7)]
-    right_texture: Handle<Image>,
+    fn try_from(metric: &dyn Metric) -> Result<Self, String> {
+        let horizons = metric.horizons().ok_or("Spacetime parameters without a valid spacetime.")?;
 
-    #[texture(8)]
-    #[sampler(9)]
-    forward_texture: Handle<Image>,
+        let mut uniform = SpacetimeUniform {
+            a: 0.,
+            q: 0.,
+            lambda: 0.,
+            horizon: horizons.outer.unwrap_or(0.) as f32,
+            cosmological_horizon: horizons.cosmological.unwrap_or(0.) as f32,
+            throat_radius: 0.,
+            camera_side: 1.,
+            table_r_min: 0.,
+            table_r_max: 0.,
+        };
+        metric.write_uniform(&mut uniform);
+        Ok(uniform)
+    }
+}
 
-    #[texture(10)]
-    #[sampler(11)]
-    backward_texture: Handle<Image>,
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+#[bind_group_data(SpacetimeMaterialKey)]
+struct SpacetimeMaterial {
+    // see skybox::SkyboxSource, the texture of the projection that isn't used is a placeholder
+    #[texture(0, dimension = "cube")]
+    #[sampler(1)]
+    skybox_cube: Handle<Image>,
+    #[texture(2)]
+    #[sampler(3)]
+    skybox_panorama: Handle<Image>,
+    #[uniform(4)]
+    skybox_projection: u32, // see SkyboxProjection
 
-    #[uniform(12)]
+    #[uniform(5)]
     skybox_intensity: f32,
 
-    #[uniform(13)]
+    #[uniform(6)]
     fov: f32,
 
-    #[uniform(14)]
+    #[uniform(7)]
     cam_pos: Vec3,
-    #[uniform(15)]
+    #[uniform(8)]
     cam_x: Vec3, // cam right
-    #[uniform(16)]
+    #[uniform(9)]
     cam_y: Vec3, // cam up
-    #[uniform(17)]
+    #[uniform(10)]
     cam_z: Vec3, // the way the camera is facing
 
-    #[texture(18)]
-    #[sampler(19)]
+    #[texture(11)]
+    #[sampler(12)]
     accretion_disc_texture: Handle<Image>,
-    #[uniform(20)]
+    #[uniform(13)]
     accretion_disc_r: f32,
-    #[uniform(21)]
+    #[uniform(14)]
     accretion_disc_width: f32,
-    #[uniform(22)]
+    #[uniform(15)]
     accretion_disc_intensity: f32,
-    #[uniform(23)]
-    accretion_disc_phi: f32,
+    #[uniform(16)]
+    accretion_disc_time: f32, // disc_time reduced to below DISC_TIME_PERIOD
+    disc_time: f64, // coordinate time the disc has been turning for
+
+    #[uniform(17)]
+    spacetime: SpacetimeUniform,
+
+    // skybox of the universe on the other side of the wormhole throat
+    #[texture(18, dimension = "cube")]
+    #[sampler(19)]
+    other_skybox_cube: Handle<Image>,
+    #[texture(20)]
+    #[sampler(21)]
+    other_skybox_panorama: Handle<Image>,
+    #[uniform(22)]
+    other_skybox_projection: u32,
+
+    // see UserDefined::metric_table
+    #[texture(23, sample_type = "float", filterable = false)]
+    metric_table: Handle<Image>,
+
+    #[uniform(24)]
+    cam_velocity: Vec3, // relative to the observer the rays are set up for
+
+    // see DiscEmission
+    #[uniform(25)]
+    accretion_disc_emission: u32,
+    #[uniform(26)]
+    accretion_disc_temperature: f32, // see disc::temperature_scale
+    #[uniform(27)]
+    accretion_disc_inner_edge: f32, // where the disc stops exerting torque, the isco
+
+    // see DiscShape
+    #[uniform(28)]
+    accretion_disc_shape: u32,
+    #[uniform(29)]
+    accretion_torus_thickness: f32,
+    #[uniform(30)]
+    accretion_torus_opacity: f32,
+    #[uniform(31)]
+    accretion_torus_noise: f32,
+
+    // see Jets
+    #[uniform(32)]
+    jet_opening_angle: f32, // half of it, in radians
+    #[uniform(33)]
+    jet_length: f32,
+    #[uniform(34)]
+    jet_lorentz_factor: f32,
+    #[uniform(35)]
+    jet_emissivity: f32, // 0 without jets
+
+    #[uniform(36)]
+    integrator: u32, // see Integrator
+
+    // see RayTracing
+    #[uniform(37)]
+    step_budget: u32,
+    #[uniform(38)]
+    max_orbits: f32,
+
+    // see deflection::deflection_table
+    #[texture(39, sample_type = "float", filterable = false)]
+    deflection_table: Handle<Image>,
+    #[uniform(40)]
+    deflection_table_r: f32, // 0 when the table can't be used
+
+    // see Metric::shader_def
+    shader_def: &'static str,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct SpacetimeMaterialKey {
+    shader_def: &'static str,
+}
+
+impl From<&SpacetimeMaterial> for SpacetimeMaterialKey {
+    fn from(material: &SpacetimeMaterial) -> Self {
+        SpacetimeMaterialKey { shader_def: material.shader_def }
+    }
 }
 
-impl Material2d for SchwarzschildMaterial {
+impl Material2d for SpacetimeMaterial {
     fn fragment_shader() -> ShaderRef {
-        "shaders/schwarzschild.wgsl".into()
+        "shaders/spacetime.wgsl".into()
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayout,
+        key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader_defs.push(key.bind_group_data.shader_def.into());
+        }
+        Ok(())
     }
 }
 
+// the rings don't share a period, so the disc jumps once per period; below it the time keeps a precision of
+// about 0.01 M in f32, where a second is 2e5 M for a black hole of one solar mass
+const DISC_TIME_PERIOD: f64 = 65536.;
+
 fn update_material(
     cam_data: ResMut<CamData>,
-    mut materials: ResMut<Assets<SchwarzschildMaterial>>,
+    mut materials: ResMut<Assets<SpacetimeMaterial>>,
     spacetime_params: Res<SpacetimeParams>,
+    player: Res<CameraPathPlayer>,
     time: Res<Time>,
+    mut drawn: Local<Option<SpacetimeParams>>,
 ) {
     let mat_id = materials.ids().next().expect("Failed to get material id.");
     let mat = materials.get_mut(mat_id).expect("Failed to get material.");
@@ -122,7 +313,88 @@ fn update_material(
     mat.cam_x = cam_data.cam_x;
     mat.cam_y = cam_data.cam_y;
     mat.cam_z = cam_data.cam_z;
-    mat.accretion_disc_phi = mat.accretion_disc_phi + (time_to_geo(time.delta_seconds(), spacetime_params.mass) as f32 / (mat.accretion_disc_r * mat.accretion_disc_r.sqrt())) % (2. * PI);
+    mat.cam_velocity = cam_data.cam_velocity;
+
+    // the horizons are slow to find for the user-defined metric, and the shader keeps the last spacetime it could draw
+    if drawn.as_ref() != Some(&*spacetime_params) {
+        match spacetime_params.metric().and_then(|metric| Ok((metric.shader_def(), SpacetimeUniform::try_from(&*metric)?))) {
+            Ok((shader_def, uniform)) => {
+                mat.shader_def = shader_def;
+                mat.spacetime = uniform;
+            }
+            Err(error) => error!("{error}"),
+        }
+        *drawn = Some(spacetime_params.clone());
+    }
+    mat.spacetime.camera_side = if cam_data.other_universe { -1. } else { 1. };
+
+    // every ring of the disc turns with its own angular velocity, so the shader gets the time and works out the angle
+    mat.disc_time = match &player.playback {
+        Some(playback) => playback.disc_time + time_to_geo(playback.time as f32, spacetime_params.mass),
+        None => mat.disc_time + time_to_geo(time.delta_seconds(), spacetime_params.mass),
+    };
+    mat.accretion_disc_time = (mat.disc_time % DISC_TIME_PERIOD) as f32;
+}
+
+// the user-defined metric is tabulated for the shader whenever its components change
+fn update_metric_table(
+    mut materials: ResMut<Assets<SpacetimeMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    spacetime_params: Res<SpacetimeParams>,
+    mut tabulated: Local<Option<[String; 4]>>,
+) {
+    if spacetime_params.metric != MetricKind::UserDefined || tabulated.as_ref() == Some(&spacetime_params.user_metric) {
+        return;
+    }
+
+    // the shader keeps the last table when the components don't parse
+    *tabulated = Some(spacetime_params.user_metric.clone());
+    let metric = match UserDefined::parse(&spacetime_params.user_metric) {
+        Ok(metric) => metric,
+        Err(error) => {
+            error!("Invalid user-defined metric: {error}");
+            return;
+        }
+    };
+    let mat_id = materials.ids().next().expect("Failed to get material id.");
+    let mat = materials.get_mut(mat_id).expect("Failed to get material.");
+    mat.metric_table = images.add(metric.metric_table());
+}
+
+// the schwarzschild orbits from a static camera are tabulated in the background whenever the camera moves to another
+// radius, if the table is switched on; the shader marches the rays until the table for the radius is there
+fn update_deflection_table(
+    mut materials: ResMut<Assets<SpacetimeMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    spacetime_params: Res<SpacetimeParams>,
+    ray_tracing: Res<RayTracing>,
+    cam_data: Res<CamData>,
+    mut tabulated: Local<Option<f64>>,
+    mut tabulating: Local<Option<(f64, Task<Image>)>>,
+) {
+    let mat_id = materials.ids().next().expect("Failed to get material id.");
+    let mat = materials.get_mut(mat_id).expect("Failed to get material.");
+
+    let r = cam_data.cam_pos.length() as f64;
+    if !ray_tracing.deflection_table || !spacetime_params.is_schwarzschild() || r <= 2. {
+        mat.deflection_table_r = 0.;
+        return;
+    }
+
+    let finished = tabulating.as_mut().and_then(|(r_task, task)| block_on(poll_once(task)).map(|table| (*r_task, table)));
+    if let Some((r_task, table)) = finished {
+        mat.deflection_table = images.add(table);
+        *tabulated = Some(r_task);
+        *tabulating = None;
+    }
+
+    let current = |r_table: f64| ((r - r_table) / r_table).abs() <= deflection::TABLE_R_TOLERANCE;
+    let up_to_date = tabulated.is_some_and(current);
+    // dropping the task for a radius the camera has left cancels it
+    if !up_to_date && !tabulating.as_ref().is_some_and(|(r_task, _)| current(*r_task)) {
+        *tabulating = Some((r, AsyncComputeTaskPool::get().spawn(async move { deflection_table(r) })));
+    }
+    mat.deflection_table_r = if up_to_date { tabulated.unwrap_or(0.) as f32 } else { 0. };
 }
 /* #endregion */
 
@@ -138,19 +410,26 @@ fn get_cam_axis(pos: Vec3, target: Vec3) -> (Vec3, Vec3, Vec3) {
     (cam_x, cam_y, cam_z)
 }
 
-// schwarzschild
+#[allow(clippy::too_many_arguments)]
 fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<SchwarzschildMaterial>>,
+    mut materials: ResMut<Assets<SpacetimeMaterial>>,
+    mut images: ResMut<Assets<Image>>,
     assets: Res<AssetServer>,
-    spacetime_params: Res<SpacetimeParams>
+    spacetime_params: Res<SpacetimeParams>,
+    free_fall: Res<FreeFall>,
+    accretion_disc: Res<AccretionDisc>,
+    jets: Res<Jets>,
+    ray_tracing: Res<RayTracing>,
+    startup_scene: Res<StartupScene>,
+    mut skyboxes: ResMut<Skyboxes>
 ) {
     let font: Handle<Font> = assets.load(FONT_PATH);
     let font_bold: Handle<Font> = assets.load(FONT_PATH_BOLD);
 
-    let cam_pos = vec3(0., 10., 40.);
-    let cam_target = Vec3::ZERO;
+    let cam_pos = startup_scene.scene.camera.eye;
+    let cam_target = startup_scene.scene.camera.target;
 
     let (cam_x, cam_y, cam_z) = get_cam_axis(cam_pos, cam_target);
 
@@ -168,6 +447,67 @@ fn setup(
     /* #endregion */
 
     /* #region ray tracing */
+    // the fov and the disc texture and size come from the scene, the skyboxes from update_skyboxes once they have loaded
+    let skybox_placeholder = images.add(placeholder_cube());
+    let mut material = SpacetimeMaterial {
+        skybox_cube: skybox_placeholder.clone(),
+        skybox_panorama: images.add(Image::default()),
+        skybox_projection: 0,
+
+        skybox_intensity: 0.,
+
+        fov: 0.,
+
+        cam_pos,
+        cam_x,
+        cam_y,
+        cam_z,
+
+        accretion_disc_texture: default(),
+        accretion_disc_r: 0.,
+        accretion_disc_width: 0.,
+        accretion_disc_intensity: 0.,
+        accretion_disc_time: 0.,
+        disc_time: 0.,
+
+        // update_material fills in the spacetime on the first frame
+        spacetime: default(),
+
+        other_skybox_cube: skybox_placeholder,
+        other_skybox_panorama: images.add(Image::default()),
+        other_skybox_projection: 0,
+
+        metric_table: images.add(Image::default()),
+
+        cam_velocity: Vec3::ZERO,
+
+        accretion_disc_emission: DiscEmission::default().shader_value(),
+        accretion_disc_temperature: 0.,
+        accretion_disc_inner_edge: 6.,
+        accretion_disc_shape: DiscShape::default().shader_value(),
+        accretion_torus_thickness: 0.,
+        accretion_torus_opacity: 0.,
+        accretion_torus_noise: 0.,
+
+        jet_opening_angle: 0.,
+        jet_length: 0.,
+        jet_lorentz_factor: 1.,
+        jet_emissivity: 0.,
+
+        integrator: Integrator::default().shader_value(),
+        step_budget: RayTracing::default().step_budget,
+        max_orbits: RayTracing::default().max_orbits as f32,
+
+        deflection_table: images.add(Image::default()),
+        deflection_table_r: 0.,
+
+        shader_def: spacetime_params.metric().map_or("METRIC_SPHERICAL", |metric| metric.shader_def()),
+    };
+    startup_scene.scene.apply_to_material(&mut material, &assets);
+    for (universe, source) in skyboxes.sources.clone().into_iter().enumerate() {
+        skyboxes.load(universe, source, &assets);
+    }
+
     commands
         .spawn(
             MaterialMesh2dBundle {
@@ -176,29 +516,7 @@ fn setup(
                     translation: Vec3::ZERO,
                     ..default()
                 },
-                material: materials.add(SchwarzschildMaterial {
-                    up_texture: assets.load("images/skybox/skybox1/up.png"),
-                    down_texture: assets.load("images/skybox/skybox1/down.png"),
-                    left_texture: assets.load("images/skybox/skybox1/left.png"),
-                    right_texture: assets.load("images/skybox/skybox1/right.png"),
-                    forward_texture: assets.load("images/skybox/skybox1/forward.png"),
-                    backward_texture: assets.load("images/skybox/skybox1/backward.png"),
-
-                    skybox_intensity: 0.7,
-
-                    fov: PI / 2.,
-
-                    cam_pos,
-                    cam_x,
-                    cam_y,
-                    cam_z,
-
-                    accretion_disc_texture: assets.load("images/accretion-disc/disc1.png"),
-                    accretion_disc_r: 6.,
-                    accretion_disc_width: 12.,
-                    accretion_disc_intensity: 0.8,
-                    accretion_disc_phi: 0.,
-                }),
+                material: materials.add(material),
                 ..default()
             }
         );
@@ -217,7 +535,7 @@ fn setup(
 
     /* #region ui */
     commands
-        .spawn(NodeBundle {
+        .spawn((NodeBundle {
             style: Style {
                 position_type: PositionType::Absolute,
                 right: Val::Px(0.),
@@ -233,7 +551,7 @@ fn setup(
             },
             background_color: SIDEBAR_BG_COLOR.into(),
             ..default()
-        })
+        }, Overlay))
         .with_children(|builder| {
             /* #region spacetime parameters */
             builder.spawn(TextBundle::from_section(
@@ -249,6 +567,40 @@ fn setup(
                 ..default()
             }));
 
+            builder.spawn(TextBundle::from_section(
+                "Metric: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    MetricButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        spacetime_params.metric.name(),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
             builder.spawn(TextBundle::from_section(
                 "M (kg): ",
                 TextStyle {
@@ -280,29 +632,41 @@ fn setup(
                     .with_inactive(true),
                 Name::new("SpacetimeParamsM")
             ));
-            /* #endregion */
 
-            /* #region cosmetic */
             builder.spawn(TextBundle::from_section(
-                "Cosmetics",
+                "a/M: ",
                 TextStyle {
                     font: font.clone(),
-                    font_size: 20.,
+                    font_size: 16.,
                     ..default()
                 }
-            ).with_style(Style {
-                margin: UiRect { 
-                    left: Val::Px(0.),
-                    right: Val::Px(0.),
-                    top: Val::Px(4.),
-                    bottom: Val::Px(8.)
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
                 },
-                grid_column: GridPlacement::span(2),
-                ..default()
-            }));
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", spacetime_params.a))
+                    .with_inactive(true),
+                Name::new("SpacetimeParamsA")
+            ));
 
             builder.spawn(TextBundle::from_section(
-                "Up skybox: ",
+                "Q/M: ",
                 TextStyle {
                     font: font.clone(),
                     font_size: 16.,
@@ -328,12 +692,13 @@ fn setup(
                         color: TEXT_COLOR,
                         ..default()
                     })
-                    .with_value("TODO")
+                    .with_value(format!("{}", spacetime_params.q))
                     .with_inactive(true),
+                Name::new("SpacetimeParamsQ")
             ));
 
             builder.spawn(TextBundle::from_section(
-                "Down skybox: ",
+                "Λ (M⁻²): ",
                 TextStyle {
                     font: font.clone(),
                     font_size: 16.,
@@ -359,12 +724,13 @@ fn setup(
                         color: TEXT_COLOR,
                         ..default()
                     })
-                    .with_value("TODO")
+                    .with_value(format!("{}", spacetime_params.lambda))
                     .with_inactive(true),
+                Name::new("SpacetimeParamsLambda")
             ));
 
             builder.spawn(TextBundle::from_section(
-                "Left skybox: ",
+                "Wormhole throat b/M: ",
                 TextStyle {
                     font: font.clone(),
                     font_size: 16.,
@@ -390,12 +756,13 @@ fn setup(
                         color: TEXT_COLOR,
                         ..default()
                     })
-                    .with_value("TODO")
+                    .with_value(format!("{}", spacetime_params.throat_radius))
                     .with_inactive(true),
+                Name::new("SpacetimeParamsThroat")
             ));
 
             builder.spawn(TextBundle::from_section(
-                "Right skybox: ",
+                "g_tt: ",
                 TextStyle {
                     font: font.clone(),
                     font_size: 16.,
@@ -421,12 +788,13 @@ fn setup(
                         color: TEXT_COLOR,
                         ..default()
                     })
-                    .with_value("TODO")
+                    .with_value(spacetime_params.user_metric[0].clone())
                     .with_inactive(true),
+                Name::new("SpacetimeParamsGtt")
             ));
 
             builder.spawn(TextBundle::from_section(
-                "Forward skybox: ",
+                "g_rr: ",
                 TextStyle {
                     font: font.clone(),
                     font_size: 16.,
@@ -452,12 +820,13 @@ fn setup(
                         color: TEXT_COLOR,
                         ..default()
                     })
-                    .with_value("TODO")
+                    .with_value(spacetime_params.user_metric[1].clone())
                     .with_inactive(true),
+                Name::new("SpacetimeParamsGrr")
             ));
 
             builder.spawn(TextBundle::from_section(
-                "Backward skybox: ",
+                "g_θθ: ",
                 TextStyle {
                     font: font.clone(),
                     font_size: 16.,
@@ -483,12 +852,13 @@ fn setup(
                         color: TEXT_COLOR,
                         ..default()
                     })
-                    .with_value("TODO")
+                    .with_value(spacetime_params.user_metric[2].clone())
                     .with_inactive(true),
+                Name::new("SpacetimeParamsGthetatheta")
             ));
 
             builder.spawn(TextBundle::from_section(
-                "Accretion disc: ",
+                "g_φφ: ",
                 TextStyle {
                     font: font.clone(),
                     font_size: 16.,
@@ -514,163 +884,3081 @@ fn setup(
                         color: TEXT_COLOR,
                         ..default()
                     })
-                    .with_value("TODO")
+                    .with_value(spacetime_params.user_metric[3].clone())
                     .with_inactive(true),
+                Name::new("SpacetimeParamsGphiphi")
+            ));
+
+            // why a parameter that was typed in isn't used
+            builder.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: MESSAGE_COLOR,
+                    }
+                ).with_style(Style {
+                    max_width: Val::Px(300.0),
+                    grid_column: GridPlacement::span(2),
+                    ..default()
+                }),
+                SpacetimeMessage
             ));
             /* #endregion */
-        });
 
-    /* #region position text */
-    commands
-        .spawn(NodeBundle {
-            style: Style {
-                position_type: PositionType::Absolute,
-                bottom: Val::Px(10.),
-                left: Val::Px(10.),
+            /* #region observer */
+            builder.spawn(TextBundle::from_section(
+                "Observer",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 20.,
+                    ..default()
+                }
+            ).with_style(Style {
+                margin: UiRect { 
+                    left: Val::Px(0.),
+                    right: Val::Px(0.),
+                    top: Val::Px(4.),
+                    bottom: Val::Px(8.)
+                },
+                grid_column: GridPlacement::span(2),
                 ..default()
-            },
-            ..default()
-        })
-        .with_children(|builder| {
-            builder.spawn((
-                TextBundle::from_sections(vec![
-                    TextSection::new(
-                        "Schwarzschild radius: ",
-                        TextStyle {
-                            font: font_bold.clone(),
-                            font_size: 16.,
-                            color: Color::WHITE,
+            }));
+
+            builder.spawn(TextBundle::from_section(
+                "Observer: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
                             ..default()
-                        }
-                    ),
-                    TextSection::new(
-                        "\n",
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    ObserverButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        startup_scene.scene.camera.observer.name(),
                         TextStyle {
                             font: font.clone(),
                             font_size: 16.,
-                            color: Color::WHITE,
-                            ..default()
+                            color: TEXT_COLOR,
                         }
-                    ),
-                    TextSection::new(
-                        "Difference in r from the event horizon: ",
-                        TextStyle {
-                            font: font_bold.clone(),
-                            font_size: 16.,
-                            color: Color::WHITE,
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "Free fall: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
                             ..default()
-                        }
-                    ),
-                    TextSection::new(
-                        "\n",
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    FreeFallButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        "off",
                         TextStyle {
                             font: font.clone(),
                             font_size: 16.,
-                            color: Color::WHITE,
-                            ..default()
-                        }
-                    ),
-                    TextSection::new(
-                        "Proper length from the event horizon: ",
-                        TextStyle {
-                            font: font_bold.clone(),
-                            font_size: 16.,
-                            color: Color::WHITE,
-                            ..default()
+                            color: TEXT_COLOR,
                         }
-                    ),
-                    TextSection::new(
-                        "",
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "v_r (c): ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", free_fall.velocity[0]))
+                    .with_inactive(true),
+                Name::new("FreeFallVr")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "v_θ (c): ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", free_fall.velocity[1]))
+                    .with_inactive(true),
+                Name::new("FreeFallVtheta")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "v_φ (c): ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", free_fall.velocity[2]))
+                    .with_inactive(true),
+                Name::new("FreeFallVphi")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Drop from rest at r/M: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", free_fall.drop_r))
+                    .with_inactive(true),
+                Name::new("FreeFallR")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    DropButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        "Drop",
                         TextStyle {
                             font: font.clone(),
                             font_size: 16.,
-                            color: Color::WHITE,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+            /* #endregion */
+
+            /* #region cosmetic */
+            builder.spawn(TextBundle::from_section(
+                "Cosmetics",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 20.,
+                    ..default()
+                }
+            ).with_style(Style {
+                margin: UiRect { 
+                    left: Val::Px(0.),
+                    right: Val::Px(0.),
+                    top: Val::Px(4.),
+                    bottom: Val::Px(8.)
+                },
+                grid_column: GridPlacement::span(2),
+                ..default()
+            }));
+
+            builder.spawn(TextBundle::from_section(
+                "Skybox: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(skyboxes.sources[0].path.clone())
+                    .with_inactive(true),
+                Name::new("SkyboxPath")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Skybox projection: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    SkyboxProjectionButton(0)
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        skyboxes.sources[0].projection.name(),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "Other skybox: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(skyboxes.sources[1].path.clone())
+                    .with_inactive(true),
+                Name::new("OtherSkyboxPath")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Other projection: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
                             ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    SkyboxProjectionButton(1)
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        skyboxes.sources[1].projection.name(),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
                         }
-                    ),
-                ]),
-                PositionText
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
             ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    LoadSkyboxesButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        "Load skyboxes",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "Starfield seed: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", skyboxes.starfield.seed))
+                    .with_inactive(true),
+                Name::new("StarfieldSeed")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Stars: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", skyboxes.starfield.stars))
+                    .with_inactive(true),
+                Name::new("StarfieldStars")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Faintest magnitude: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", skyboxes.starfield.faintest_magnitude))
+                    .with_inactive(true),
+                Name::new("StarfieldMagnitude")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Nebulae: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", skyboxes.starfield.nebulae.len()))
+                    .with_inactive(true),
+                Name::new("StarfieldNebulae")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Face size (px): ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", skyboxes.starfield.size))
+                    .with_inactive(true),
+                Name::new("StarfieldSize")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    GenerateStarfieldButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        "Generate starfield",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "Accretion disc: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value("TODO")
+                    .with_inactive(true),
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Disc emission: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    DiscEmissionButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        accretion_disc.emission.name(),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "Accretion rate (kg/s): ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{:.2e}", accretion_disc.accretion_rate))
+                    .with_inactive(true),
+                Name::new("AccretionDiscRate")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Disc shape: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    DiscShapeButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        accretion_disc.shape.name(),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "Torus thickness: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", accretion_disc.torus_thickness))
+                    .with_inactive(true),
+                Name::new("AccretionTorusThickness")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Torus opacity: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", accretion_disc.torus_opacity))
+                    .with_inactive(true),
+                Name::new("AccretionTorusOpacity")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Torus noise: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", accretion_disc.torus_noise))
+                    .with_inactive(true),
+                Name::new("AccretionTorusNoise")
+            ));
+            /* #endregion */
+
+            /* #region jets */
+            builder.spawn(TextBundle::from_section(
+                "Jets",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 20.,
+                    ..default()
+                }
+            ).with_style(Style {
+                margin: UiRect { 
+                    left: Val::Px(0.),
+                    right: Val::Px(0.),
+                    top: Val::Px(4.),
+                    bottom: Val::Px(8.)
+                },
+                grid_column: GridPlacement::span(2),
+                ..default()
+            }));
+
+            builder.spawn(TextBundle::from_section(
+                "Jets: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    JetsButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        if jets.enabled { "on" } else { "off" },
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "Opening angle (°): ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", jets.opening_angle))
+                    .with_inactive(true),
+                Name::new("JetsOpeningAngle")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Length/M: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", jets.length))
+                    .with_inactive(true),
+                Name::new("JetsLength")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Lorentz factor: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", jets.lorentz_factor))
+                    .with_inactive(true),
+                Name::new("JetsLorentzFactor")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Emissivity: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", jets.emissivity))
+                    .with_inactive(true),
+                Name::new("JetsEmissivity")
+            ));
+            /* #endregion */
+
+            /* #region ray tracing */
+            builder.spawn(TextBundle::from_section(
+                "Ray tracing",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 20.,
+                    ..default()
+                }
+            ).with_style(Style {
+                margin: UiRect { 
+                    left: Val::Px(0.),
+                    right: Val::Px(0.),
+                    top: Val::Px(4.),
+                    bottom: Val::Px(8.)
+                },
+                grid_column: GridPlacement::span(2),
+                ..default()
+            }));
+
+            builder.spawn(TextBundle::from_section(
+                "Integrator: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    IntegratorButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        ray_tracing.integrator.name(),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "Step budget: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", ray_tracing.step_budget))
+                    .with_inactive(true),
+                Name::new("RayTracingStepBudget")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Max orbits: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(format!("{}", ray_tracing.max_orbits))
+                    .with_inactive(true),
+                Name::new("RayTracingMaxOrbits")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Deflection table: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    DeflectionTableButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        if ray_tracing.deflection_table { "on" } else { "off" },
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+            /* #endregion */
+
+            /* #region camera path */
+            builder.spawn(TextBundle::from_section(
+                "Camera path",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 20.,
+                    ..default()
+                }
+            ).with_style(Style {
+                margin: UiRect { 
+                    left: Val::Px(0.),
+                    right: Val::Px(0.),
+                    top: Val::Px(4.),
+                    bottom: Val::Px(8.)
+                },
+                grid_column: GridPlacement::span(2),
+                ..default()
+            }));
+
+            builder.spawn(TextBundle::from_section(
+                "Keyframe at (s): ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value("0")
+                    .with_inactive(true),
+                Name::new("KeyframeTime")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    AddKeyframeButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        "Add keyframe",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    ClearKeyframesButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        format!("Clear {} keyframes", startup_scene.scene.keyframes.keyframes.len()),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    PlayPathButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        "Play",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "Export fps: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value("30")
+                    .with_inactive(true),
+                Name::new("ExportFps")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "Export folder: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value("frames")
+                    .with_inactive(true),
+                Name::new("ExportDir")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    ExportPathButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        "Export",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+            /* #endregion */
+
+            /* #region scene */
+            builder.spawn(TextBundle::from_section(
+                "Scene",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 20.,
+                    ..default()
+                }
+            ).with_style(Style {
+                margin: UiRect { 
+                    left: Val::Px(0.),
+                    right: Val::Px(0.),
+                    top: Val::Px(4.),
+                    bottom: Val::Px(8.)
+                },
+                grid_column: GridPlacement::span(2),
+                ..default()
+            }));
+
+            builder.spawn(TextBundle::from_section(
+                "File: ",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder.spawn((
+                NodeBundle {
+                    style: Style {
+                        width: Val::Px(200.0),
+                        border: UiRect::all(Val::Px(2.0)),
+                        padding: UiRect::all(Val::Px(2.0)),
+                        ..default()
+                    },
+                    border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                    background_color: INPUT_BG_COLOR.into(),
+                    ..default()
+                },
+                TextInputBundle::default()
+                    .with_text_style(TextStyle {
+                        font: font.clone(),
+                        font_size: 16.,
+                        color: TEXT_COLOR,
+                        ..default()
+                    })
+                    .with_value(startup_scene.path.clone())
+                    .with_inactive(true),
+                Name::new("ScenePath")
+            ));
+
+            builder.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    SaveSceneButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        "Save",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+
+            builder.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    ..default()
+                }
+            ));
+            builder
+                .spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(200.0),
+                            border: UiRect::all(Val::Px(2.0)),
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: INPUT_BORDER_COLOR_INACTIVE.into(),
+                        background_color: INPUT_BG_COLOR.into(),
+                        ..default()
+                    },
+                    LoadSceneButton
+                ))
+                .with_children(|builder| {
+                    builder.spawn(TextBundle::from_section(
+                        "Load",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: TEXT_COLOR,
+                        }
+                    ));
+                });
+            /* #endregion */
+        });
+
+    /* #region position text */
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.),
+                left: Val::Px(10.),
+                ..default()
+            },
+            ..default()
+        }, Overlay))
+        .with_children(|builder| {
+            builder.spawn((
+                TextBundle::from_sections(vec![
+                    TextSection::new(
+                        "Metric: ",
+                        TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "\n",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "Outer horizon radius (r+): ",
+                        TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "\n",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "Inner horizon radius (r-): ",
+                        TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "\n",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "Cosmological horizon radius: ",
+                        TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "\n",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "Photon orbit radius (prograde / retrograde): ",
+                        TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "\n",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "ISCO radius: ",
+                        TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "\n",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "Difference in r from the event horizon: ",
+                        TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "\n",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "Proper length from the event horizon: ",
+                        TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "\n",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "Proper time of the camera: ",
+                        TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                    TextSection::new(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 16.,
+                            color: Color::WHITE,
+                            ..default()
+                        }
+                    ),
+                ]),
+                PositionText
+            ));
+        });
+    /* #endregion */
+    /* #endregion */
+}
+
+fn focus(
+    query: Query<(Entity, &Interaction), Changed<Interaction>>,
+    mut text_input_query: Query<(Entity, &mut TextInputInactive, &mut BorderColor)>,
+) {
+    for (interaction_entity, interaction) in &query {
+        if *interaction == Interaction::Pressed {
+            for (entity, mut inactive, mut border_color) in &mut text_input_query {
+                if entity == interaction_entity {
+                    if !inactive.0 {
+                        inactive.0 = true;
+                        *border_color = INPUT_BORDER_COLOR_INACTIVE.into();
+                    }
+                    else {
+                        inactive.0 = false;
+                        *border_color = INPUT_BORDER_COLOR_ACTIVE.into();
+                    }
+                } else {
+                    inactive.0 = true;
+                    *border_color = INPUT_BORDER_COLOR_INACTIVE.into();
+                }
+            }
+        }
+    }
+}
+
+/* #region window data */
+#[derive(Resource, Default)]
+struct WindowData {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+fn update_window_data(
+    mut window_data: ResMut<WindowData>,
+    window: Query<&Window>
+) {
+    let window = window.single();
+
+    let width = window.width();
+    let height = window.height();
+
+    let (x, y) = match window.position {
+        WindowPosition::At(v) => (v.x as f32, v.y as f32),
+        _ => (0., 0.),
+    };
+
+    window_data.x = x as u32;
+    window_data.y = y as u32;
+    window_data.width = width as u32;
+    window_data.height = height as u32;
+}
+/* #endregion */
+
+/* #region camera data */
+#[derive(Resource, Default, Debug)]
+struct CamData {
+    cam_pos: Vec3,
+    cam_x: Vec3,
+    cam_y: Vec3,
+    cam_z: Vec3,
+    other_universe: bool, // behind the wormhole throat
+    cam_velocity: Vec3, // see SpacetimeMaterial::cam_velocity
+    observer: Observer, // whose 4-velocity the camera has when it isn't falling freely
+}
+
+fn update_camera_data(
+    mut cam: Query<&LookTransform>,
+    mut cam_data: ResMut<CamData>
+) {
+    if let Ok(transform) = cam.get_single_mut() {
+        let (cam_x, cam_y, cam_z) = get_cam_axis(transform.eye, transform.target);
+
+        cam_data.cam_pos = transform.eye;
+        cam_data.cam_x = cam_x;
+        cam_data.cam_y = cam_y;
+        cam_data.cam_z = cam_z;
+    }
+}
+
+// flying below the throat radius continues on the other side, which is mirrored in the shared chart,
+// so the eye is reflected through the throat sphere and the radial part of the view direction flips
+fn cross_wormhole_throat(
+    mut cam: Query<&mut LookTransform>,
+    mut cam_data: ResMut<CamData>,
+    spacetime_params: Res<SpacetimeParams>
+) {
+    let Some(throat_radius) = spacetime_params.metric().ok().and_then(|metric| metric.throat()) else {
+        cam_data.other_universe = false;
+        return;
+    };
+    let throat_radius = throat_radius as f32;
+
+    if let Ok(mut transform) = cam.get_single_mut() {
+        let r = transform.eye.length();
+        if r >= throat_radius {
+            return;
+        }
+
+        let normal = transform.eye.try_normalize().unwrap_or(Vec3::Z);
+        let look = transform.target - transform.eye;
+
+        transform.eye = normal * (2. * throat_radius - r);
+        transform.target = transform.eye + look - 2. * look.dot(normal) * normal;
+        cam_data.other_universe = !cam_data.other_universe;
+    }
+}
+/* #endregion */
+
+/* #region observer */
+#[derive(Resource)]
+struct FreeFall {
+    geodesic: Option<Geodesic>, // None while the camera is flown freely
+    velocity: [f64; 3], // initial velocity along r, theta and phi relative to the zero angular momentum observer in units of c
+    drop_r: f64, // of the "drop from rest at r" preset
+}
+
+impl Default for FreeFall {
+    fn default() -> Self {
+        FreeFall {
+            geodesic: None,
+            velocity: [0.; 3],
+            drop_r: 20.,
+        }
+    }
+}
+
+#[derive(Component)]
+struct FreeFallButton;
+
+#[derive(Component)]
+struct DropButton;
+
+// moves the eye along the geodesic in proper time, the camera controller still turns the view
+fn update_free_fall(
+    mut cam: Query<&mut LookTransform>,
+    mut cam_data: ResMut<CamData>,
+    mut free_fall: ResMut<FreeFall>,
+    spacetime_params: Res<SpacetimeParams>,
+    time: Res<Time>
+) {
+    let Ok(metric) = spacetime_params.metric() else {
+        return;
+    };
+    let (Some(geodesic), Some(horizons)) = (free_fall.geodesic.as_mut(), metric.horizons()) else {
+        return;
+    };
+
+    // the camera stays where it was when the geodesic can't be followed any further
+    if !geodesic.advance(&*metric, &horizons, time_to_geo(time.delta_seconds(), spacetime_params.mass)) {
+        free_fall.geodesic = None;
+        return;
+    }
+
+    if let Ok(mut transform) = cam.get_single_mut() {
+        let look = transform.target - transform.eye;
+        transform.eye = geodesic.position(&*metric);
+        transform.target = transform.eye + look;
+    }
+    cam_data.cam_velocity = geodesic.velocity(&*metric);
+}
+
+fn update_observer(
+    mut cam_data: ResMut<CamData>,
+    free_fall: Res<FreeFall>,
+    spacetime_params: Res<SpacetimeParams>
+) {
+    if let (None, Ok(metric)) = (free_fall.geodesic, spacetime_params.metric()) {
+        cam_data.cam_velocity = cam_data.observer.velocity(&*metric, cam_data.cam_pos);
+    }
+}
+
+#[derive(Component)]
+struct ObserverButton;
+
+// cycles through the observers on click
+#[allow(clippy::type_complexity)]
+fn switch_observer(
+    query: Query<(&Interaction, &Children), (Changed<Interaction>, With<ObserverButton>)>,
+    mut text_query: Query<&mut Text>,
+    mut cam_data: ResMut<CamData>
+) {
+    for (interaction, children) in &query {
+        if *interaction == Interaction::Pressed {
+            cam_data.observer = cam_data.observer.next();
+
+            if let Ok(mut text) = text_query.get_mut(children[0]) {
+                text.sections[0].value = cam_data.observer.name().to_string();
+            }
+        }
+    }
+}
+
+fn update_free_fall_params(
+    query: Query<(&TextInputValue, &Name)>,
+    mut free_fall: ResMut<FreeFall>
+) {
+    for (text_input, name) in &query {
+        match name.as_str() {
+            "FreeFallVr" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                free_fall.velocity[0] = value;
+            }
+            "FreeFallVtheta" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                free_fall.velocity[1] = value;
+            }
+            "FreeFallVphi" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                free_fall.velocity[2] = value;
+            }
+            "FreeFallR" => {
+                let value: f64 = text_input.0.parse().unwrap_or(20.);
+                free_fall.drop_r = value;
+            }
+            _ => {}
+        }
+    }
+}
+
+// starts falling from where the camera is with the velocity from the sidebar, or stops
+fn toggle_free_fall(
+    query: Query<&Interaction, (Changed<Interaction>, With<FreeFallButton>)>,
+    cam_data: Res<CamData>,
+    mut free_fall: ResMut<FreeFall>,
+    spacetime_params: Res<SpacetimeParams>
+) {
+    for interaction in &query {
+        if *interaction == Interaction::Pressed {
+            free_fall.geodesic = match free_fall.geodesic {
+                Some(_) => None,
+                None => spacetime_params.metric().ok().and_then(|metric| Geodesic::new(&*metric, cam_data.cam_pos, free_fall.velocity)),
+            };
+        }
+    }
+}
+
+// the "drop from rest at r" preset, the eye is moved to r in the direction it is in
+fn drop_camera(
+    query: Query<&Interaction, (Changed<Interaction>, With<DropButton>)>,
+    mut cam: Query<&mut LookTransform>,
+    mut free_fall: ResMut<FreeFall>,
+    spacetime_params: Res<SpacetimeParams>
+) {
+    for interaction in &query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let (Ok(metric), Ok(mut transform)) = (spacetime_params.metric(), cam.get_single_mut()) else {
+            continue;
+        };
+        let (_, theta) = metric.to_coordinates(transform.eye);
+        let phi = (-transform.eye.z).atan2(transform.eye.x) as f64;
+        let pos = metric.chart_position(free_fall.drop_r, theta, phi);
+
+        if let Some(geodesic) = Geodesic::new(&*metric, pos, [0.; 3]) {
+            let look = transform.target - transform.eye;
+            transform.eye = pos;
+            transform.target = pos + look;
+            free_fall.geodesic = Some(geodesic);
+        }
+    }
+}
+
+fn update_free_fall_button(
+    query: Query<&Children, With<FreeFallButton>>,
+    mut text_query: Query<&mut Text>,
+    free_fall: Res<FreeFall>
+) {
+    let state = if free_fall.geodesic.is_some() { "on" } else { "off" };
+    for children in &query {
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            if text.sections[0].value != state {
+                text.sections[0].value = state.to_string();
+            }
+        }
+    }
+}
+/* #endregion */
+
+/* #region accretion disc */
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct AccretionDisc {
+    emission: DiscEmission,
+    accretion_rate: f64, // in kg/s, sets the temperature of the blackbody disc together with the mass
+    shape: DiscShape,
+    torus_thickness: f64, // half height of the torus over half its width
+    torus_opacity: f64, // absorption per M at the densest point
+    torus_noise: f64, // between 0 for smooth gas and 1 for clumps with empty space between them
+}
+
+impl Default for AccretionDisc {
+    fn default() -> Self {
+        AccretionDisc {
+            emission: DiscEmission::default(),
+            accretion_rate: 5e9,
+            shape: DiscShape::default(),
+            torus_thickness: 0.3,
+            torus_opacity: 0.5,
+            torus_noise: 0.,
+        }
+    }
+}
+
+#[derive(Component)]
+struct DiscEmissionButton;
+
+#[derive(Component)]
+struct DiscShapeButton;
+
+// the inner edge is the isco, which is slow to find for the user-defined metric, so it is only found again
+// when the parameters change
+fn update_accretion_disc(
+    mut materials: ResMut<Assets<SpacetimeMaterial>>,
+    spacetime_params: Res<SpacetimeParams>,
+    accretion_disc: Res<AccretionDisc>,
+    mut cached: Local<Option<(SpacetimeParams, Option<f64>)>>
+) {
+    if cached.as_ref().map(|(params, _)| params) != Some(&*spacetime_params) {
+        *cached = Some((spacetime_params.clone(), spacetime_params.metric().ok().and_then(|metric| metric.isco())));
+    }
+    let Some((_, isco)) = *cached else {
+        return;
+    };
+
+    let mat_id = materials.ids().next().expect("Failed to get material id.");
+    let mat = materials.get_mut(mat_id).expect("Failed to get material.");
+    mat.accretion_disc_emission = accretion_disc.emission.shader_value();
+    mat.accretion_disc_temperature = disc::temperature_scale(spacetime_params.mass, accretion_disc.accretion_rate) as f32;
+    // without stable orbits, as around the wormhole, the disc is taken to end where it is drawn
+    mat.accretion_disc_inner_edge = isco.map_or(mat.accretion_disc_r, |r| r as f32);
+
+    mat.accretion_disc_shape = accretion_disc.shape.shader_value();
+    mat.accretion_torus_thickness = accretion_disc.torus_thickness as f32;
+    mat.accretion_torus_opacity = accretion_disc.torus_opacity as f32;
+    mat.accretion_torus_noise = accretion_disc.torus_noise.clamp(0., 1.) as f32;
+}
+
+fn update_accretion_disc_params(
+    query: Query<(&TextInputValue, &Name)>,
+    mut accretion_disc: ResMut<AccretionDisc>
+) {
+    for (text_input, name) in &query {
+        match name.as_str() {
+            "AccretionDiscRate" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                accretion_disc.accretion_rate = value;
+            }
+            "AccretionTorusThickness" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.3);
+                accretion_disc.torus_thickness = value;
+            }
+            "AccretionTorusOpacity" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                accretion_disc.torus_opacity = value;
+            }
+            "AccretionTorusNoise" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                accretion_disc.torus_noise = value;
+            }
+            _ => {}
+        }
+    }
+}
+
+// cycles through the kinds of emission on click
+#[allow(clippy::type_complexity)]
+fn switch_disc_emission(
+    query: Query<(&Interaction, &Children), (Changed<Interaction>, With<DiscEmissionButton>)>,
+    mut text_query: Query<&mut Text>,
+    mut accretion_disc: ResMut<AccretionDisc>
+) {
+    for (interaction, children) in &query {
+        if *interaction == Interaction::Pressed {
+            accretion_disc.emission = accretion_disc.emission.next();
+
+            if let Ok(mut text) = text_query.get_mut(children[0]) {
+                text.sections[0].value = accretion_disc.emission.name().to_string();
+            }
+        }
+    }
+}
+
+// switches between the thin disc and the torus on click
+#[allow(clippy::type_complexity)]
+fn switch_disc_shape(
+    query: Query<(&Interaction, &Children), (Changed<Interaction>, With<DiscShapeButton>)>,
+    mut text_query: Query<&mut Text>,
+    mut accretion_disc: ResMut<AccretionDisc>
+) {
+    for (interaction, children) in &query {
+        if *interaction == Interaction::Pressed {
+            accretion_disc.shape = accretion_disc.shape.next();
+
+            if let Ok(mut text) = text_query.get_mut(children[0]) {
+                text.sections[0].value = accretion_disc.shape.name().to_string();
+            }
+        }
+    }
+}
+/* #endregion */
+
+/* #region jets */
+// two cones of gas streaming out along the spin axis
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct Jets {
+    enabled: bool,
+    opening_angle: f64, // full angle of the cones in degrees
+    length: f64, // in units of M
+    lorentz_factor: f64, // of the bulk flow
+    emissivity: f64, // brightness per M along the ray at the base of the jets
+}
+
+impl Default for Jets {
+    fn default() -> Self {
+        Jets {
+            enabled: false,
+            opening_angle: 10.,
+            length: 40.,
+            lorentz_factor: 3.,
+            emissivity: 0.05,
+        }
+    }
+}
+
+#[derive(Component)]
+struct JetsButton;
+
+fn update_jets(
+    mut materials: ResMut<Assets<SpacetimeMaterial>>,
+    jets: Res<Jets>
+) {
+    let mat_id = materials.ids().next().expect("Failed to get material id.");
+    let mat = materials.get_mut(mat_id).expect("Failed to get material.");
+    mat.jet_opening_angle = (jets.opening_angle.clamp(0., 170.) / 2.).to_radians() as f32;
+    mat.jet_length = jets.length.max(0.) as f32;
+    mat.jet_lorentz_factor = jets.lorentz_factor.max(1.) as f32;
+    mat.jet_emissivity = if jets.enabled { jets.emissivity.max(0.) as f32 } else { 0. };
+}
+
+fn update_jets_params(
+    query: Query<(&TextInputValue, &Name)>,
+    mut jets: ResMut<Jets>
+) {
+    for (text_input, name) in &query {
+        match name.as_str() {
+            "JetsOpeningAngle" => {
+                let value: f64 = text_input.0.parse().unwrap_or(10.);
+                jets.opening_angle = value;
+            }
+            "JetsLength" => {
+                let value: f64 = text_input.0.parse().unwrap_or(40.);
+                jets.length = value;
+            }
+            "JetsLorentzFactor" => {
+                let value: f64 = text_input.0.parse().unwrap_or(1.);
+                jets.lorentz_factor = value;
+            }
+            "JetsEmissivity" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                jets.emissivity = value;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn toggle_jets(
+    query: Query<(&Interaction, &Children), (Changed<Interaction>, With<JetsButton>)>,
+    mut text_query: Query<&mut Text>,
+    mut jets: ResMut<Jets>
+) {
+    for (interaction, children) in &query {
+        if *interaction == Interaction::Pressed {
+            jets.enabled = !jets.enabled;
+
+            if let Ok(mut text) = text_query.get_mut(children[0]) {
+                text.sections[0].value = if jets.enabled { "on" } else { "off" }.to_string();
+            }
+        }
+    }
+}
+/* #endregion */
+
+/* #region ray tracing */
+// how the orbit equation of the spherically symmetric metrics is integrated, the kerr and user-defined
+// shaders always use rk4
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+enum Integrator {
+    Euler,
+    #[default]
+    Leapfrog,
+    Rk4,
+    DormandPrince, // adapts the step to the error estimate of its embedded 4th order solution
+}
+
+impl Integrator {
+    fn name(&self) -> &'static str {
+        match self {
+            Integrator::Euler => "Euler",
+            Integrator::Leapfrog => "Leapfrog",
+            Integrator::Rk4 => "RK4",
+            Integrator::DormandPrince => "Adaptive Dormand-Prince",
+        }
+    }
+
+    fn next(&self) -> Self {
+        match self {
+            Integrator::Euler => Integrator::Leapfrog,
+            Integrator::Leapfrog => Integrator::Rk4,
+            Integrator::Rk4 => Integrator::DormandPrince,
+            Integrator::DormandPrince => Integrator::Euler,
+        }
+    }
+
+    // integrator in shaders/common.wgsl
+    fn shader_value(&self) -> u32 {
+        match self {
+            Integrator::Euler => 0,
+            Integrator::Leapfrog => 1,
+            Integrator::Rk4 => 2,
+            Integrator::DormandPrince => 3,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct RayTracing {
+    integrator: Integrator,
+    step_budget: u32, // steps per ray, fewer are faster and coarser
+    max_orbits: f64, // how many times a ray stepping in phi may go around the black hole
+    // schwarzschild orbits are looked up in deflection::deflection_table instead, which is exact or integrated
+    // with rk4 for two orbits whatever the settings above
+    deflection_table: bool,
+}
+
+impl Default for RayTracing {
+    fn default() -> Self {
+        RayTracing {
+            integrator: Integrator::default(),
+            step_budget: 200,
+            max_orbits: 2.,
+            deflection_table: false,
+        }
+    }
+}
+
+#[derive(Component)]
+struct IntegratorButton;
+
+#[derive(Component)]
+struct DeflectionTableButton;
+
+fn update_ray_tracing(
+    mut materials: ResMut<Assets<SpacetimeMaterial>>,
+    ray_tracing: Res<RayTracing>
+) {
+    let mat_id = materials.ids().next().expect("Failed to get material id.");
+    let mat = materials.get_mut(mat_id).expect("Failed to get material.");
+    mat.integrator = ray_tracing.integrator.shader_value();
+    mat.step_budget = ray_tracing.step_budget.clamp(10, 5000);
+    mat.max_orbits = ray_tracing.max_orbits.max(0.1) as f32;
+}
+
+fn update_ray_tracing_params(
+    query: Query<(&TextInputValue, &Name)>,
+    mut ray_tracing: ResMut<RayTracing>
+) {
+    for (text_input, name) in &query {
+        match name.as_str() {
+            "RayTracingStepBudget" => {
+                let value: u32 = text_input.0.parse().unwrap_or(200);
+                ray_tracing.step_budget = value;
+            }
+            "RayTracingMaxOrbits" => {
+                let value: f64 = text_input.0.parse().unwrap_or(2.);
+                ray_tracing.max_orbits = value;
+            }
+            _ => {}
+        }
+    }
+}
+
+// cycles through the integrators on click
+#[allow(clippy::type_complexity)]
+fn switch_integrator(
+    query: Query<(&Interaction, &Children), (Changed<Interaction>, With<IntegratorButton>)>,
+    mut text_query: Query<&mut Text>,
+    mut ray_tracing: ResMut<RayTracing>
+) {
+    for (interaction, children) in &query {
+        if *interaction == Interaction::Pressed {
+            ray_tracing.integrator = ray_tracing.integrator.next();
+
+            if let Ok(mut text) = text_query.get_mut(children[0]) {
+                text.sections[0].value = ray_tracing.integrator.name().to_string();
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn toggle_deflection_table(
+    query: Query<(&Interaction, &Children), (Changed<Interaction>, With<DeflectionTableButton>)>,
+    mut text_query: Query<&mut Text>,
+    mut ray_tracing: ResMut<RayTracing>
+) {
+    for (interaction, children) in &query {
+        if *interaction == Interaction::Pressed {
+            ray_tracing.deflection_table = !ray_tracing.deflection_table;
+
+            if let Ok(mut text) = text_query.get_mut(children[0]) {
+                text.sections[0].value = if ray_tracing.deflection_table { "on" } else { "off" }.to_string();
+            }
+        }
+    }
+}
+/* #endregion */
+
+/* #region skybox */
+// the skyboxes of this universe and of the one on the other side of a wormhole throat, and the images they
+// are waiting for
+#[derive(Resource)]
+struct Skyboxes {
+    sources: [SkyboxSource; 2],
+    starfield: Starfield, // of the skyboxes with SkyboxProjection::Starfield, the other universe uses the next seed
+    catalogue: StarCatalogue, // only the render subcommand draws it, the window passes it on to the scene files it saves
+    loading: [Option<SkyboxLoad>; 2],
+}
+
+impl Skyboxes {
+    fn new(sources: [SkyboxSource; 2], starfield: Starfield, catalogue: StarCatalogue) -> Self {
+        Skyboxes { sources, starfield, catalogue, loading: [None, None] }
+    }
+
+    // update_skyboxes puts it on the material once it has loaded
+    fn load(&mut self, universe: usize, source: SkyboxSource, assets: &AssetServer) {
+        self.loading[universe] = Some(source.load(assets));
+        self.sources[universe] = source;
+    }
+}
+
+#[derive(Component)]
+struct SkyboxProjectionButton(usize); // of the universe
+
+#[derive(Component)]
+struct LoadSkyboxesButton;
+
+#[derive(Component)]
+struct GenerateStarfieldButton;
+
+fn update_skyboxes(
+    mut skyboxes: ResMut<Skyboxes>,
+    mut materials: ResMut<Assets<SpacetimeMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    assets: Res<AssetServer>
+) {
+    for universe in 0..2 {
+        let Some(load) = &skyboxes.loading[universe] else {
+            continue;
+        };
+        let states: Vec<LoadState> = load.handles().into_iter().map(|handle| assets.load_state(handle.id())).collect();
+        if states.contains(&LoadState::Failed) {
+            error!("Failed to load the skybox {}.", skyboxes.sources[universe].path);
+            skyboxes.loading[universe] = None;
+            continue;
+        }
+        if states.iter().any(|state| *state != LoadState::Loaded) {
+            continue;
+        }
+
+        let texture = match load {
+            SkyboxLoad::Faces(faces) => {
+                let faces = faces.each_ref().map(|face| images.get(face).expect("Loaded skybox face."));
+                assemble_cube(faces).map(|cube| images.add(cube))
+            }
+            SkyboxLoad::Cube(image) => into_cube(images.get(image).expect("Loaded skybox.").clone()).map(|cube| images.add(cube)),
+            SkyboxLoad::Panorama(image) => Ok(image.clone()),
+            SkyboxLoad::Starfield => {
+                let starfield = Starfield { seed: skyboxes.starfield.seed + universe as u64, ..skyboxes.starfield.clone() };
+                Ok(images.add(cube_from_faces(&starfield.generate())))
+            }
+            SkyboxLoad::Spacescape => SpacescapeProject::load(&Path::new(offline::ASSETS_DIR).join(&skyboxes.sources[universe].path))
+                .map(|project| images.add(cube_from_faces(&project.generate(skyboxes.starfield.size)))),
+        };
+        skyboxes.loading[universe] = None;
+        let texture = match texture {
+            Ok(texture) => texture,
+            Err(error) => {
+                error!("Failed to load the skybox {}: {error}", skyboxes.sources[universe].path);
+                continue;
+            }
+        };
+
+        let mat_id = materials.ids().next().expect("Failed to get material id.");
+        let mat = materials.get_mut(mat_id).expect("Failed to get material.");
+        let (cube, panorama, projection) = if universe == 0 {
+            (&mut mat.skybox_cube, &mut mat.skybox_panorama, &mut mat.skybox_projection)
+        } else {
+            (&mut mat.other_skybox_cube, &mut mat.other_skybox_panorama, &mut mat.other_skybox_projection)
+        };
+        let source = &skyboxes.sources[universe];
+        match source.projection {
+            SkyboxProjection::Cube | SkyboxProjection::Starfield => *cube = texture,
+            SkyboxProjection::Equirectangular => *panorama = texture,
+        }
+        *projection = source.projection.shader_value();
+    }
+}
+
+// the projection buttons cycle through the projections and load their skybox again, the load button loads both
+// skyboxes from the text inputs; generating a starfield shows it in this universe and in the other one if it has one
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+fn switch_skyboxes(
+    load_query: Query<&Interaction, (Changed<Interaction>, With<LoadSkyboxesButton>)>,
+    generate_query: Query<&Interaction, (Changed<Interaction>, With<GenerateStarfieldButton>)>,
+    projection_query: Query<(&Interaction, &SkyboxProjectionButton), Changed<Interaction>>,
+    labels: Query<(&Children, &SkyboxProjectionButton)>,
+    inputs: Query<(&TextInputValue, &Name)>,
+    mut text_query: Query<&mut Text>,
+    mut skyboxes: ResMut<Skyboxes>,
+    assets: Res<AssetServer>
+) {
+    let mut reload = [load_query.iter().any(|interaction| *interaction == Interaction::Pressed); 2];
+    if generate_query.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        skyboxes.sources[0].projection = SkyboxProjection::Starfield;
+        reload[0] = true;
+        reload[1] |= skyboxes.sources[1].projection == SkyboxProjection::Starfield;
+    }
+
+    for (interaction, button) in &projection_query {
+        if *interaction == Interaction::Pressed && !reload[button.0] {
+            let projection = skyboxes.sources[button.0].projection.next();
+            skyboxes.sources[button.0].projection = projection;
+            reload[button.0] = true;
+        }
+    }
+
+    for (children, button) in &labels {
+        let name = skyboxes.sources[button.0].projection.name();
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            if text.sections[0].value != name {
+                text.sections[0].value = name.to_string();
+            }
+        }
+    }
+
+    for (path, name) in &inputs {
+        let universe = match name.as_str() {
+            "SkyboxPath" => 0,
+            "OtherSkyboxPath" => 1,
+            _ => continue,
+        };
+        if reload[universe] {
+            let source = SkyboxSource { path: path.0.clone(), projection: skyboxes.sources[universe].projection };
+            skyboxes.load(universe, source, &assets);
+        }
+    }
+}
+
+fn update_starfield_params(
+    query: Query<(&TextInputValue, &Name)>,
+    mut skyboxes: ResMut<Skyboxes>
+) {
+    // skyboxes only counts as changed when one of the parameters does
+    let mut starfield = skyboxes.starfield.clone();
+    for (text_input, name) in &query {
+        match name.as_str() {
+            "StarfieldSeed" => {
+                let value: u64 = text_input.0.parse().unwrap_or(0);
+                starfield.seed = value;
+            }
+            "StarfieldStars" => {
+                let value: u32 = text_input.0.parse().unwrap_or(5000);
+                starfield.stars = value;
+            }
+            "StarfieldMagnitude" => {
+                let value: f64 = text_input.0.parse().unwrap_or(6.);
+                starfield.faintest_magnitude = value;
+            }
+            "StarfieldNebulae" => {
+                let value: usize = text_input.0.parse().unwrap_or(2);
+                let value = value.min(8);
+                if value != starfield.nebulae.len() {
+                    starfield.set_nebula_count(value);
+                }
+            }
+            "StarfieldSize" => {
+                let value: u32 = text_input.0.parse().unwrap_or(1024);
+                starfield.size = value.clamp(16, 4096);
+            }
+            _ => {}
+        }
+    }
+    if starfield != skyboxes.starfield {
+        skyboxes.starfield = starfield;
+    }
+}
+/* #endregion */
+
+/* #region scene */
+const DEFAULT_SCENE_PATH: &str = "scene.toml";
+
+// the scene from the command line, or the defaults, and the path the sidebar starts with
+#[derive(Resource)]
+struct StartupScene {
+    scene: SceneFile,
+    path: String,
+}
+
+#[derive(Component)]
+struct SaveSceneButton;
+
+#[derive(Component)]
+struct LoadSceneButton;
+
+fn scene_path<'a>(mut inputs: impl Iterator<Item = (&'a TextInputValue, &'a Name)>) -> Option<String> {
+    inputs.find(|(_, name)| name.as_str() == "ScenePath").map(|(path, _)| path.0.clone())
+}
+
+type SidebarButtons<'w, 's> = Query<'w, 's, (&'static Children, AnyOf<(&'static MetricButton, &'static ObserverButton, &'static DiscEmissionButton, &'static DiscShapeButton, &'static JetsButton, &'static IntegratorButton, &'static DeflectionTableButton, &'static SkyboxProjectionButton)>)>;
+
+// shows the resources in the text inputs and on the buttons when something else than the sidebar changed them,
+// the systems that read the inputs every frame would undo it otherwise
+#[allow(clippy::too_many_arguments)]
+fn refresh_sidebar(
+    inputs: &mut Query<(&mut TextInputValue, &Name)>,
+    buttons: &SidebarButtons,
+    text_query: &mut Query<&mut Text>,
+    spacetime_params: &SpacetimeParams,
+    observer: Observer,
+    accretion_disc: &AccretionDisc,
+    jets: &Jets,
+    ray_tracing: &RayTracing,
+    skyboxes: &Skyboxes
+) {
+    for (mut value, name) in inputs {
+        let new_value = match name.as_str() {
+            "SpacetimeParamsM" => format!("{:.2e}", spacetime_params.mass),
+            "SpacetimeParamsA" => format!("{}", spacetime_params.a),
+            "SpacetimeParamsQ" => format!("{}", spacetime_params.q),
+            "SpacetimeParamsLambda" => format!("{}", spacetime_params.lambda),
+            "SpacetimeParamsThroat" => format!("{}", spacetime_params.throat_radius),
+            "SpacetimeParamsGtt" => spacetime_params.user_metric[0].clone(),
+            "SpacetimeParamsGrr" => spacetime_params.user_metric[1].clone(),
+            "SpacetimeParamsGthetatheta" => spacetime_params.user_metric[2].clone(),
+            "SpacetimeParamsGphiphi" => spacetime_params.user_metric[3].clone(),
+            "AccretionDiscRate" => format!("{:.2e}", accretion_disc.accretion_rate),
+            "AccretionTorusThickness" => format!("{}", accretion_disc.torus_thickness),
+            "AccretionTorusOpacity" => format!("{}", accretion_disc.torus_opacity),
+            "AccretionTorusNoise" => format!("{}", accretion_disc.torus_noise),
+            "JetsOpeningAngle" => format!("{}", jets.opening_angle),
+            "JetsLength" => format!("{}", jets.length),
+            "JetsLorentzFactor" => format!("{}", jets.lorentz_factor),
+            "JetsEmissivity" => format!("{}", jets.emissivity),
+            "RayTracingStepBudget" => format!("{}", ray_tracing.step_budget),
+            "RayTracingMaxOrbits" => format!("{}", ray_tracing.max_orbits),
+            "SkyboxPath" => skyboxes.sources[0].path.clone(),
+            "OtherSkyboxPath" => skyboxes.sources[1].path.clone(),
+            "StarfieldSeed" => format!("{}", skyboxes.starfield.seed),
+            "StarfieldStars" => format!("{}", skyboxes.starfield.stars),
+            "StarfieldMagnitude" => format!("{}", skyboxes.starfield.faintest_magnitude),
+            "StarfieldNebulae" => format!("{}", skyboxes.starfield.nebulae.len()),
+            "StarfieldSize" => format!("{}", skyboxes.starfield.size),
+            _ => continue,
+        };
+        if value.0 != new_value {
+            value.0 = new_value;
+        }
+    }
+
+    for (children, buttons) in buttons {
+        let label = match buttons {
+            (Some(_), ..) => spacetime_params.metric.name(),
+            (_, Some(_), ..) => observer.name(),
+            (_, _, Some(_), ..) => accretion_disc.emission.name(),
+            (_, _, _, Some(_), ..) => accretion_disc.shape.name(),
+            (_, _, _, _, Some(_), ..) => if jets.enabled { "on" } else { "off" },
+            (_, _, _, _, _, Some(_), ..) => ray_tracing.integrator.name(),
+            (_, _, _, _, _, _, Some(_), _) => if ray_tracing.deflection_table { "on" } else { "off" },
+            (.., Some(SkyboxProjectionButton(universe))) => skyboxes.sources[*universe].projection.name(),
+            _ => continue,
+        };
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            if text.sections[0].value != label {
+                text.sections[0].value = label.to_string();
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn save_scene(
+    query: Query<&Interaction, (Changed<Interaction>, With<SaveSceneButton>)>,
+    inputs: Query<(&TextInputValue, &Name)>,
+    cam: Query<&LookTransform>,
+    cam_data: Res<CamData>,
+    materials: Res<Assets<SpacetimeMaterial>>,
+    spacetime_params: Res<SpacetimeParams>,
+    accretion_disc: Res<AccretionDisc>,
+    jets: Res<Jets>,
+    ray_tracing: Res<RayTracing>,
+    player: Res<CameraPathPlayer>,
+    skyboxes: Res<Skyboxes>
+) {
+    for interaction in &query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let (Some(path), Ok(transform)) = (scene_path(inputs.iter()), cam.get_single()) else {
+            continue;
+        };
+        let mat_id = materials.ids().next().expect("Failed to get material id.");
+        let mat = materials.get(mat_id).expect("Failed to get material.");
+
+        let scene = SceneFile::capture(transform, &cam_data, mat, &spacetime_params, &accretion_disc, &jets, &ray_tracing, &skyboxes.sources, &skyboxes.starfield, &skyboxes.catalogue, &player.path);
+        match scene.save(Path::new(&path)) {
+            Ok(()) => info!("Saved the scene to {path}."),
+            Err(error) => error!("{error}"),
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn load_scene(
+    query: Query<&Interaction, (Changed<Interaction>, With<LoadSceneButton>)>,
+    mut inputs: Query<(&mut TextInputValue, &Name)>,
+    buttons: SidebarButtons,
+    mut text_query: Query<&mut Text>,
+    mut cam: Query<&mut LookTransform>,
+    mut cam_data: ResMut<CamData>,
+    mut materials: ResMut<Assets<SpacetimeMaterial>>,
+    assets: Res<AssetServer>,
+    mut spacetime_params: ResMut<SpacetimeParams>,
+    mut free_fall: ResMut<FreeFall>,
+    mut accretion_disc: ResMut<AccretionDisc>,
+    mut jets: ResMut<Jets>,
+    mut ray_tracing: ResMut<RayTracing>,
+    mut player: ResMut<CameraPathPlayer>,
+    mut skyboxes: ResMut<Skyboxes>
+) {
+    for interaction in &query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let Some(path) = scene_path(inputs.iter()) else {
+            continue;
+        };
+        let scene = match SceneFile::load(Path::new(&path)) {
+            Ok(scene) => scene,
+            Err(error) => {
+                error!("{error}");
+                continue;
+            }
+        };
+
+        if let Ok(mut transform) = cam.get_single_mut() {
+            transform.eye = scene.camera.eye;
+            transform.target = scene.camera.target;
+        }
+        cam_data.other_universe = false;
+        cam_data.observer = scene.camera.observer;
+        free_fall.geodesic = None;
+
+        let mat_id = materials.ids().next().expect("Failed to get material id.");
+        let mat = materials.get_mut(mat_id).expect("Failed to get material.");
+        scene.apply_to_material(mat, &assets);
+
+        *spacetime_params = scene.spacetime;
+        *accretion_disc = scene.accretion_disc.params;
+        *jets = scene.jets;
+        *ray_tracing = scene.ray_tracing;
+        player.path = scene.keyframes;
+        player.playback = None;
+        skyboxes.starfield = scene.skybox.starfield.clone();
+        skyboxes.catalogue = scene.skybox.catalogue.clone();
+        for (universe, source) in scene.skybox.sources().into_iter().enumerate() {
+            skyboxes.load(universe, source, &assets);
+        }
+
+        refresh_sidebar(&mut inputs, &buttons, &mut text_query, &spacetime_params, cam_data.observer, &accretion_disc, &jets, &ray_tracing, &skyboxes);
+        info!("Loaded the scene from {path}.");
+    }
+}
+/* #endregion */
+
+/* #region camera path */
+#[derive(Resource)]
+struct CameraPathPlayer {
+    path: CameraPath,
+    playback: Option<Playback>,
+    next_time: f64, // of the keyframe the sidebar adds, in seconds
+    fps: f64, // of the export
+    export_dir: String,
+}
+
+impl Default for CameraPathPlayer {
+    fn default() -> Self {
+        CameraPathPlayer {
+            path: CameraPath::default(),
+            playback: None,
+            next_time: 0.,
+            fps: 30.,
+            export_dir: "frames".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Playback {
+    time: f64, // seconds along the path
+    disc_time: f64, // SpacetimeMaterial::disc_time when it started, the disc turns with the time of the path
+    export: Option<Export>,
+}
+
+// the frames are taken at a fixed step in the time of the path however long they take to render
+#[derive(Debug, Clone)]
+struct Export {
+    dir: PathBuf,
+    fps: f64,
+    frame: u32,
+}
+
+impl Playback {
+    fn new(path: &CameraPath, disc_time: f64, export: Option<Export>) -> Self {
+        Playback {
+            time: path.keyframes.first().map_or(0., |keyframe| keyframe.time),
+            disc_time,
+            export,
+        }
+    }
+}
+
+// the sidebar and the position text, hidden while frames are exported
+#[derive(Component)]
+struct Overlay;
+
+#[derive(Component)]
+struct AddKeyframeButton;
+
+#[derive(Component)]
+struct ClearKeyframesButton;
+
+#[derive(Component)]
+struct PlayPathButton;
+
+#[derive(Component)]
+struct ExportPathButton;
+
+fn update_camera_path_params(
+    query: Query<(&TextInputValue, &Name)>,
+    mut player: ResMut<CameraPathPlayer>
+) {
+    for (text_input, name) in &query {
+        match name.as_str() {
+            "KeyframeTime" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                player.next_time = value;
+            }
+            "ExportFps" => {
+                let value: f64 = text_input.0.parse().unwrap_or(30.);
+                player.fps = value;
+            }
+            "ExportDir" if player.export_dir != text_input.0 => {
+                player.export_dir = text_input.0.clone();
+            }
+            _ => {}
+        }
+    }
+}
+
+// the view and the spacetime as they are at the time in the sidebar, which then moves on by two seconds
+fn add_keyframe(
+    query: Query<&Interaction, (Changed<Interaction>, With<AddKeyframeButton>)>,
+    mut inputs: Query<(&mut TextInputValue, &Name)>,
+    cam: Query<&LookTransform>,
+    materials: Res<Assets<SpacetimeMaterial>>,
+    spacetime_params: Res<SpacetimeParams>,
+    mut player: ResMut<CameraPathPlayer>
+) {
+    for interaction in &query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let Ok(transform) = cam.get_single() else {
+            continue;
+        };
+        let mat_id = materials.ids().next().expect("Failed to get material id.");
+        let mat = materials.get(mat_id).expect("Failed to get material.");
+
+        let time = player.next_time.max(0.);
+        player.path.insert(Keyframe {
+            time,
+            eye: transform.eye,
+            target: transform.target,
+            fov: mat.fov.to_degrees(),
+            spacetime: spacetime_params.clone(),
         });
-    /* #endregion */
-    /* #endregion */
+
+        for (mut value, name) in &mut inputs {
+            if name.as_str() == "KeyframeTime" {
+                value.0 = format!("{}", time + 2.);
+            }
+        }
+    }
 }
 
-fn focus(
-    query: Query<(Entity, &Interaction), Changed<Interaction>>,
-    mut text_input_query: Query<(Entity, &mut TextInputInactive, &mut BorderColor)>,
+fn clear_keyframes(
+    query: Query<&Interaction, (Changed<Interaction>, With<ClearKeyframesButton>)>,
+    mut inputs: Query<(&mut TextInputValue, &Name)>,
+    mut player: ResMut<CameraPathPlayer>
 ) {
-    for (interaction_entity, interaction) in &query {
+    for interaction in &query {
         if *interaction == Interaction::Pressed {
-            for (entity, mut inactive, mut border_color) in &mut text_input_query {
-                if entity == interaction_entity {
-                    if !inactive.0 {
-                        inactive.0 = true;
-                        *border_color = INPUT_BORDER_COLOR_INACTIVE.into();
-                    }
-                    else {
-                        inactive.0 = false;
-                        *border_color = INPUT_BORDER_COLOR_ACTIVE.into();
-                    }
-                } else {
-                    inactive.0 = true;
-                    *border_color = INPUT_BORDER_COLOR_INACTIVE.into();
+            player.path = CameraPath::default();
+            player.playback = None;
+
+            for (mut value, name) in &mut inputs {
+                if name.as_str() == "KeyframeTime" {
+                    value.0 = "0".to_string();
                 }
             }
         }
     }
 }
 
-/* #region window data */
-#[derive(Resource, Default)]
-struct WindowData {
-    x: u32,
-    y: u32,
-    width: u32,
-    height: u32,
+fn toggle_playback(
+    query: Query<&Interaction, (Changed<Interaction>, With<PlayPathButton>)>,
+    materials: Res<Assets<SpacetimeMaterial>>,
+    mut player: ResMut<CameraPathPlayer>
+) {
+    for interaction in &query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let mat_id = materials.ids().next().expect("Failed to get material id.");
+        let mat = materials.get(mat_id).expect("Failed to get material.");
+        player.playback = match player.playback {
+            Some(_) => None,
+            None if !player.path.keyframes.is_empty() => Some(Playback::new(&player.path, mat.disc_time, None)),
+            None => None,
+        };
+    }
 }
 
-fn update_window_data(
-    mut window_data: ResMut<WindowData>,
-    window: Query<&Window>
+fn toggle_export(
+    query: Query<&Interaction, (Changed<Interaction>, With<ExportPathButton>)>,
+    materials: Res<Assets<SpacetimeMaterial>>,
+    mut player: ResMut<CameraPathPlayer>
 ) {
-    let window = window.single();
+    for interaction in &query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
 
-    let width = window.width();
-    let height = window.height();
+        if player.playback.as_ref().is_some_and(|playback| playback.export.is_some()) {
+            player.playback = None;
+            continue;
+        }
+        if player.path.keyframes.is_empty() || player.fps <= 0. {
+            continue;
+        }
 
-    let (x, y) = match window.position {
-        WindowPosition::At(v) => (v.x as f32, v.y as f32),
-        _ => (0., 0.),
+        let dir = PathBuf::from(&player.export_dir);
+        if let Err(error) = fs::create_dir_all(&dir) {
+            error!("Failed to create {}: {error}", dir.display());
+            continue;
+        }
+        let mat_id = materials.ids().next().expect("Failed to get material id.");
+        let mat = materials.get(mat_id).expect("Failed to get material.");
+        player.playback = Some(Playback::new(&player.path, mat.disc_time, Some(Export { dir, fps: player.fps, frame: 0 })));
+    }
+}
+
+// moves the camera and changes the spacetime along the path, in real time unless the frames are exported
+#[allow(clippy::too_many_arguments)]
+fn play_camera_path(
+    mut inputs: Query<(&mut TextInputValue, &Name)>,
+    buttons: SidebarButtons,
+    mut text_query: Query<&mut Text>,
+    mut cam: Query<&mut LookTransform>,
+    cam_data: Res<CamData>,
+    mut free_fall: ResMut<FreeFall>,
+    mut materials: ResMut<Assets<SpacetimeMaterial>>,
+    mut spacetime_params: ResMut<SpacetimeParams>,
+    accretion_disc: Res<AccretionDisc>,
+    jets: Res<Jets>,
+    ray_tracing: Res<RayTracing>,
+    mut player: ResMut<CameraPathPlayer>,
+    skyboxes: Res<Skyboxes>,
+    time: Res<Time>
+) {
+    let player = &mut *player;
+    let Some(playback) = player.playback.as_mut() else {
+        return;
+    };
+    if playback.export.is_none() {
+        playback.time += time.delta_seconds_f64();
+    }
+    if playback.time > player.path.duration() {
+        player.playback = None;
+        return;
+    }
+    let Some(keyframe) = player.path.sample(playback.time) else {
+        player.playback = None;
+        return;
     };
 
-    window_data.x = x as u32;
-    window_data.y = y as u32;
-    window_data.width = width as u32;
-    window_data.height = height as u32;
+    if let Ok(mut transform) = cam.get_single_mut() {
+        transform.eye = keyframe.eye;
+        transform.target = keyframe.target;
+    }
+    free_fall.geodesic = None;
+
+    let mat_id = materials.ids().next().expect("Failed to get material id.");
+    let mat = materials.get_mut(mat_id).expect("Failed to get material.");
+    mat.fov = keyframe.fov.to_radians();
+
+    // the spline can overshoot the bounds of the sidebar in between the keyframes
+    let mut params = keyframe.spacetime;
+    params.a = params.a.clamp(-1., 1.);
+    let q_max = (1. - params.a * params.a).sqrt();
+    params.q = params.q.clamp(-q_max, q_max);
+    params.lambda = params.lambda.max(0.);
+    params.throat_radius = params.throat_radius.max(0.);
+    if params != *spacetime_params && params.metric().is_ok_and(|metric| metric.horizons().is_some()) {
+        *spacetime_params = params;
+    }
+
+    refresh_sidebar(&mut inputs, &buttons, &mut text_query, &spacetime_params, cam_data.observer, &accretion_disc, &jets, &ray_tracing, &skyboxes);
 }
-/* #endregion */
 
-/* #region camera data */
-#[derive(Resource, Default, Debug)]
-struct CamData {
-    cam_pos: Vec3,
-    cam_x: Vec3,
-    cam_y: Vec3,
-    cam_z: Vec3,
+// asks for a screenshot of the frame that play_camera_path set up and moves on to the next one once it has it
+fn export_camera_path(
+    mut player: ResMut<CameraPathPlayer>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    mut overlays: Query<&mut Visibility, With<Overlay>>
+) {
+    let exporting = player.playback.as_ref().is_some_and(|playback| playback.export.is_some());
+    let visibility = if exporting { Visibility::Hidden } else { Visibility::Inherited };
+    for mut overlay in &mut overlays {
+        if *overlay != visibility {
+            *overlay = visibility;
+        }
+    }
+
+    let player = &mut *player;
+    let (Some(playback), Ok(window)) = (player.playback.as_mut(), window.get_single()) else {
+        return;
+    };
+    let Some(export) = playback.export.as_mut() else {
+        return;
+    };
+
+    let path = export.dir.join(format!("frame_{:05}.png", export.frame));
+    // the last one hasn't been taken yet
+    if screenshots.save_screenshot_to_disk(window, path).is_err() {
+        return;
+    }
+
+    export.frame += 1;
+    let start = player.path.keyframes.first().map_or(0., |keyframe| keyframe.time);
+    playback.time = start + export.frame as f64 / export.fps;
+    if playback.time > player.path.duration() {
+        info!("Exported {} frames to {}.", export.frame, export.dir.display());
+        player.playback = None;
+    }
 }
 
-fn update_camera_data(
-    mut cam: Query<&LookTransform>,
-    mut cam_data: ResMut<CamData>
+fn update_camera_path_buttons(
+    play_query: Query<&Children, With<PlayPathButton>>,
+    export_query: Query<&Children, With<ExportPathButton>>,
+    clear_query: Query<&Children, With<ClearKeyframesButton>>,
+    mut text_query: Query<&mut Text>,
+    player: Res<CameraPathPlayer>
 ) {
-    if let Ok(transform) = cam.get_single_mut() {
-        let (cam_x, cam_y, cam_z) = get_cam_axis(transform.eye, transform.target);
+    let exporting = player.playback.as_ref().is_some_and(|playback| playback.export.is_some());
+    let mut set_label = |children: &Children, label: &str| {
+        if let Ok(mut text) = text_query.get_mut(children[0]) {
+            if text.sections[0].value != label {
+                text.sections[0].value = label.to_string();
+            }
+        }
+    };
 
-        cam_data.cam_pos = transform.eye;
-        cam_data.cam_x = cam_x;
-        cam_data.cam_y = cam_y;
-        cam_data.cam_z = cam_z;
+    for children in &play_query {
+        set_label(children, if player.playback.is_some() && !exporting { "Stop" } else { "Play" });
+    }
+    for children in &export_query {
+        set_label(children, if exporting { "Stop" } else { "Export" });
+    }
+    for children in &clear_query {
+        set_label(children, &format!("Clear {} keyframes", player.path.keyframes.len()));
     }
 }
 /* #endregion */
@@ -679,49 +3967,227 @@ fn update_camera_data(
 #[derive(Component)]
 struct PositionText;
 
+#[allow(clippy::type_complexity)]
 fn update_position_text(
     mut query: Query<&mut Text, With<PositionText>>,
     cam_data: Res<CamData>,
-    spacetime_params: Res<SpacetimeParams>
+    spacetime_params: Res<SpacetimeParams>,
+    free_fall: Res<FreeFall>,
+    mut cached: Local<Option<(SpacetimeParams, Horizons, Option<(f64, f64)>, Option<f64>)>>
 ) {
     let mut text = query.get_single_mut().expect("Failed to get text with PositionText.");
+    let Ok(metric) = spacetime_params.metric() else {
+        return;
+    };
+
+    // the horizons and orbits only change with the parameters and are slow to find for the user-defined metric
+    if cached.as_ref().map(|(params, ..)| params) != Some(&*spacetime_params) {
+        let Some(horizons) = metric.horizons() else {
+            return;
+        };
+        *cached = Some((spacetime_params.clone(), horizons, metric.photon_orbits(), metric.isco()));
+    }
+    let Some((_, horizons, photon_orbits, isco)) = *cached else {
+        return;
+    };
+
+    let mass = spacetime_params.mass;
+    let radius_text = |r: Option<f64>| match r {
+        Some(r) => format!("{:.2e} m", length_to_si(r as f32, mass)),
+        None => "none".to_string(),
+    };
 
-    let rs = length_to_si(2., spacetime_params.mass);
+    let (r, theta) = metric.to_coordinates(cam_data.cam_pos);
 
-    let r = cam_data.cam_pos.length();
-    let delta_r = length_to_si(r - 2., spacetime_params.mass);
+    // distances are measured from the event horizon, or the throat of a wormhole
+    let (surface, surface_name) = match (horizons.outer, metric.throat()) {
+        (Some(r_plus), _) => (r_plus, "event horizon"),
+        (None, Some(b)) => (b, "throat"),
+        (None, None) => (0., "center"),
+    };
+    let delta_r = length_to_si((r - surface) as f32, mass);
 
-    let proper_length = length_to_si(r.sqrt() * (r - 2.).sqrt() + f32::ln(r + r.sqrt() * (r - 2.).sqrt() - 1.), spacetime_params.mass);
+    // between the horizons r is a time coordinate, the camera reaches the singularity or the inner horizon at the latest
+    // after the proper time of free fall from rest at the event horizon
+    let inside = horizons.outer.is_some_and(|r_plus| r < r_plus && r > horizons.inner.unwrap_or(0.));
+    let (interval_name, interval) = if inside {
+        let (end, end_name) = match horizons.inner {
+            Some(r_minus) => (r_minus, "inner horizon"),
+            None => (0., "singularity"),
+        };
+        let proper_time = time_to_si(metric.radial_interval(end, r, theta) as f32, mass);
+        (format!("Maximal proper time until the {end_name}: "), format!("{proper_time:.2e} s\n"))
+    } else {
+        // beyond the cosmological horizon r is a time coordinate as well, so the length is only measured up to it
+        let r_max = horizons.cosmological.map_or(r, |r_c| r.min(r_c));
+        let proper_length = length_to_si(metric.radial_interval(surface, r_max, theta) as f32, mass);
+        (format!("Proper length from the {surface_name}: "), format!("{proper_length:.2e} m\n"))
+    };
 
-    text.sections[1].value = format!("{rs:.2e} m\n");
-    text.sections[3].value = format!("{delta_r:.2e} m\n");
-    text.sections[5].value = format!("{proper_length:.2e} m");
+    text.sections[1].value = format!("{}\n", spacetime_params.metric.name());
+    text.sections[3].value = format!("{}\n", radius_text(horizons.outer));
+    text.sections[5].value = format!("{}\n", radius_text(horizons.inner));
+    text.sections[7].value = format!("{}\n", radius_text(horizons.cosmological));
+    text.sections[9].value = match photon_orbits {
+        Some((r_pro, r_retro)) => format!("{} / {}\n", radius_text(Some(r_pro)), radius_text(Some(r_retro))),
+        None => "none\n".to_string(),
+    };
+    text.sections[11].value = format!("{}\n", radius_text(isco));
+    text.sections[12].value = format!("Difference in r from the {surface_name}: ");
+    text.sections[13].value = format!("{delta_r:.2e} m\n");
+    text.sections[14].value = interval_name;
+    text.sections[15].value = interval;
+    text.sections[17].value = match free_fall.geodesic {
+        Some(geodesic) => format!("{:.2e} s", time_to_si(geodesic.proper_time as f32, mass)),
+        None => "not falling".to_string(),
+    };
 }
 /* #endregion */
 
 /* #region spacetime parameters */
-#[derive(Resource)]
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 struct SpacetimeParams {
-    mass: f64
+    metric: MetricKind,
+    mass: f64,
+    a: f64, // angular momentum per unit mass in units of M
+    q: f64, // charge in geometrized units of M
+    lambda: f64, // cosmological constant in units of M^-2
+    throat_radius: f64, // of the ellis wormhole, M only sets the units then
+    user_metric: [String; 4], // g_tt, g_rr, g_thetatheta, g_phiphi in r and theta
 }
 
 impl Default for SpacetimeParams {
     fn default() -> Self {
         SpacetimeParams {
-            mass: 1e34 as f64
+            metric: MetricKind::default(),
+            mass: 1e34 as f64,
+            a: 0.,
+            q: 0.,
+            lambda: 0.,
+            throat_radius: 1.,
+            user_metric: [
+                "-(1-2/r)".to_string(),
+                "1/(1-2/r)".to_string(),
+                "r^2".to_string(),
+                "r^2*sin(θ)^2".to_string(),
+            ],
+        }
+    }
+}
+
+impl SpacetimeParams {
+    // only the user-defined metric can fail, when its components don't parse
+    fn metric(&self) -> Result<Box<dyn Metric>, String> {
+        Ok(match self.metric {
+            MetricKind::KerrNewmanDeSitter => Box::new(KerrNewmanDeSitter { a: self.a, q: self.q, lambda: self.lambda }),
+            MetricKind::EllisWormhole => Box::new(EllisWormhole { throat_radius: self.throat_radius }),
+            MetricKind::UserDefined => Box::new(UserDefined::parse(&self.user_metric)?),
+        })
+    }
+
+    fn is_schwarzschild(&self) -> bool {
+        self.metric == MetricKind::KerrNewmanDeSitter && self.a == 0. && self.q == 0. && self.lambda == 0.
+    }
+}
+
+#[derive(Component)]
+struct MetricButton;
+
+// cycles through the metrics on click
+#[allow(clippy::type_complexity)]
+fn switch_metric(
+    query: Query<(&Interaction, &Children), (Changed<Interaction>, With<MetricButton>)>,
+    mut text_query: Query<&mut Text>,
+    mut spacetime_params: ResMut<SpacetimeParams>
+) {
+    for (interaction, children) in &query {
+        if *interaction == Interaction::Pressed {
+            spacetime_params.metric = spacetime_params.metric.next();
+
+            if let Ok(mut text) = text_query.get_mut(children[0]) {
+                text.sections[0].value = spacetime_params.metric.name().to_string();
+            }
         }
     }
 }
 
+#[derive(Component)]
+struct SpacetimeMessage;
+
 fn update_spacetime_params(
     query: Query<(&TextInputValue, &Name)>,
-    mut spacetime_params: ResMut<SpacetimeParams>
+    mut message_query: Query<&mut Text, With<SpacetimeMessage>>,
+    mut spacetime_params: ResMut<SpacetimeParams>,
+    mut read: Local<Option<SpacetimeParams>>
 ) {
+    // the parameters as typed, the horizons are only looked for when these or the metric change
+    let mut params = spacetime_params.clone();
     for (text_input, name) in &query {
-        if name.contains("SpacetimeParamsM") {
-            let value: f64 = text_input.0.parse().unwrap_or(0.);
-            spacetime_params.mass = value;
+        match name.as_str() {
+            "SpacetimeParamsM" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                params.mass = value;
+            }
+            "SpacetimeParamsA" => {
+                // |a| > M would be a naked singularity
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                params.a = value.clamp(-1., 1.);
+            }
+            "SpacetimeParamsQ" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                params.q = value;
+            }
+            "SpacetimeParamsLambda" => {
+                let value: f64 = text_input.0.parse().unwrap_or(0.);
+                params.lambda = value.max(0.);
+            }
+            "SpacetimeParamsThroat" => {
+                let value: f64 = text_input.0.parse().unwrap_or(1.);
+                params.throat_radius = value.max(0.);
+            }
+            "SpacetimeParamsGtt" => params.user_metric[0].clone_from(&text_input.0),
+            "SpacetimeParamsGrr" => params.user_metric[1].clone_from(&text_input.0),
+            "SpacetimeParamsGthetatheta" => params.user_metric[2].clone_from(&text_input.0),
+            "SpacetimeParamsGphiphi" => params.user_metric[3].clone_from(&text_input.0),
+            _ => {}
+        }
+    }
+    if read.as_ref() == Some(&params) {
+        return;
+    }
+    *read = Some(params.clone());
+
+    // a^2 + q^2 > M^2 would be a naked singularity as well
+    let q_max = (1. - params.a * params.a).sqrt();
+    params.q = params.q.clamp(-q_max, q_max);
+
+    let mut messages = Vec::new();
+
+    // the user-defined metric keeps its last components while the new ones don't parse or leave no usable spacetime
+    if params.user_metric != spacetime_params.user_metric {
+        match UserDefined::parse(&params.user_metric) {
+            Ok(metric) if metric.horizons().is_some() => {}
+            Ok(_) => messages.push("The user-defined metric doesn't describe a usable spacetime, the last one is kept.".to_string()),
+            Err(error) => messages.push(format!("The user-defined metric doesn't parse: {error} The last one is kept.")),
+        }
+        if !messages.is_empty() {
+            params.user_metric.clone_from(&spacetime_params.user_metric);
         }
     }
+
+    // the same goes for a cosmological horizon that swallows the black hole, the last Λ or else Λ = 0 always leaves one
+    let usable = |params: &SpacetimeParams| params.metric().is_ok_and(|metric| metric.horizons().is_some());
+    if !usable(&params) {
+        let last = SpacetimeParams { lambda: spacetime_params.lambda, ..params.clone() };
+        let lambda = if usable(&last) { spacetime_params.lambda } else { 0. };
+        messages.push(format!("A cosmological horizon for Λ = {} would swallow the black hole, Λ stays at {lambda}.", params.lambda));
+        params.lambda = lambda;
+    }
+
+    if let Ok(mut text) = message_query.get_single_mut() {
+        text.sections[0].value = messages.join("\n");
+    }
+    spacetime_params.set_if_neq(params);
 }
 /* #endregion */
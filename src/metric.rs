@@ -0,0 +1,696 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use bevy::{math::Vec3, render::{render_asset::RenderAssetUsages, render_resource::{Extent3d, TextureDimension, TextureFormat}, texture::Image}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{expression::{Expr, Var}, SpacetimeUniform};
+
+// stationary, axisymmetric metric in coordinates (t, r, theta, phi) with the symmetry axis along y;
+// everything is in geometrized units of M
+pub trait Metric {
+    // picks the module in shaders/metrics that traces the rays
+    fn shader_def(&self) -> &'static str;
+
+    // parameters of the metric for the shader, the rest of the uniform is filled in by the caller
+    fn write_uniform(&self, uniform: &mut SpacetimeUniform);
+
+    // (r, theta) of a point in the chart the camera moves in
+    fn to_coordinates(&self, pos: Vec3) -> (f64, f64);
+
+    // the point of the chart at (r, theta, phi), phi is measured right-handedly about y from x
+    fn chart_position(&self, r: f64, theta: f64, phi: f64) -> Vec3;
+
+    // d(phi)/dr of the phi of the chart along lines of constant t, theta and phi of the metric,
+    // the ingoing phi of the kerr shader stays finite across the horizons
+    fn phi_shift(&self, _r: f64) -> f64 {
+        0.
+    }
+
+    fn components(&self, r: f64, theta: f64) -> MetricComponents;
+
+    // None when the parameters don't describe a usable spacetime
+    fn horizons(&self) -> Option<Horizons>;
+
+    // radius of a wormhole throat, distances are measured from it when there is no event horizon
+    fn throat(&self) -> Option<f64> {
+        None
+    }
+
+    // circular photon orbits in the equatorial plane, returns prograde, retrograde;
+    // for each root omega of the null condition the orbit is where the radial derivative vanishes too
+    fn photon_orbits(&self) -> Option<(f64, f64)> {
+        let (r_min, r_max) = radial_range(self)?;
+        let condition = |r: f64, sign: f64| {
+            let g = self.components(r, FRAC_PI_2);
+            let dg = components_derivative(self, r);
+            let discriminant = g.g_tphi * g.g_tphi - g.g_tt * g.g_phiphi;
+            let omega = (-g.g_tphi + sign * discriminant.max(0.).sqrt()) / g.g_phiphi;
+            dg.g_tt + 2. * dg.g_tphi * omega + dg.g_phiphi * omega * omega
+        };
+
+        let prograde = first_root(|r| condition(r, 1.), r_min, r_max)?;
+        let retrograde = first_root(|r| condition(r, -1.), r_min, r_max)?;
+        Some((prograde, retrograde))
+    }
+
+    // prograde circular orbit in the equatorial plane, 0 where there is none
+    fn orbital_angular_velocity(&self, r: f64) -> f64 {
+        let dg = components_derivative(self, r);
+        let discriminant = dg.g_tphi * dg.g_tphi - dg.g_tt * dg.g_phiphi;
+        if discriminant < 0. || dg.g_phiphi == 0. {
+            return 0.;
+        }
+
+        (-dg.g_tphi + discriminant.sqrt()) / dg.g_phiphi
+    }
+
+    // innermost stable circular orbit, the first minimum of the specific energy of prograde circular orbits
+    fn isco(&self) -> Option<f64> {
+        let (r_min, r_max) = radial_range(self)?;
+        let r_start = self.photon_orbits().map_or(r_min, |(r_pro, _)| r_pro * (1. + 1e-6));
+        let energy = |r: f64| {
+            let g = self.components(r, FRAC_PI_2);
+            let omega = self.orbital_angular_velocity(r);
+            let norm = -(g.g_tt + 2. * g.g_tphi * omega + g.g_phiphi * omega * omega);
+            if omega <= 0. || norm <= 0. {
+                return f64::INFINITY;
+            }
+            -(g.g_tt + g.g_tphi * omega) / norm.sqrt()
+        };
+
+        const STEPS: usize = 4000;
+        let ratio = (r_max / r_start).powf(1. / STEPS as f64);
+        let rs: Vec<f64> = (0..=STEPS).map(|i| r_start * ratio.powi(i as i32)).collect();
+        let energies: Vec<f64> = rs.iter().map(|&r| energy(r)).collect();
+
+        let i = (1..STEPS).find(|&i| energies[i].is_finite() && energies[i] < energies[i - 1] && energies[i] <= energies[i + 1])?;
+
+        // golden section search around the minimum of the scan
+        let (mut lo, mut hi) = (rs[i - 1], rs[i + 1]);
+        let ratio = (5f64.sqrt() - 1.) / 2.;
+        for _ in 0..60 {
+            let a = hi - ratio * (hi - lo);
+            let b = lo + ratio * (hi - lo);
+            if energy(a) < energy(b) {
+                hi = b;
+            } else {
+                lo = a;
+            }
+        }
+        Some((lo + hi) / 2.)
+    }
+
+    // proper length along a line of constant t, theta and phi outside of the horizon, inside, where r is a time
+    // coordinate, the proper time along it, which is the longest one can take between the two radii;
+    // substituting r = r_from + s^2 keeps the integrand finite where g_rr diverges at a horizon
+    fn radial_interval(&self, r_from: f64, r_to: f64, theta: f64) -> f64 {
+        const STEPS: usize = 256;
+
+        let s_max = (r_to - r_from).max(0.).sqrt();
+        let ds = s_max / STEPS as f64;
+
+        (0..STEPS)
+            .map(|i| {
+                let s = (i as f64 + 0.5) * ds;
+                2. * s * self.components(r_from + s * s, theta).g_rr.abs().sqrt() * ds
+            })
+            .sum()
+    }
+}
+
+// covariant components, the ones not listed vanish
+#[derive(Debug, Clone, Copy)]
+pub struct MetricComponents {
+    pub g_tt: f64,
+    pub g_tphi: f64,
+    pub g_rr: f64,
+    pub g_thetatheta: f64,
+    pub g_phiphi: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Horizons {
+    pub inner: Option<f64>,
+    pub outer: Option<f64>,
+    pub cosmological: Option<f64>,
+}
+
+// d/dr of the components in the equatorial plane
+fn components_derivative<M: Metric + ?Sized>(metric: &M, r: f64) -> MetricComponents {
+    let h = 1e-4 * r;
+    let plus = metric.components(r + h, FRAC_PI_2);
+    let minus = metric.components(r - h, FRAC_PI_2);
+
+    MetricComponents {
+        g_tt: (plus.g_tt - minus.g_tt) / (2. * h),
+        g_tphi: (plus.g_tphi - minus.g_tphi) / (2. * h),
+        g_rr: (plus.g_rr - minus.g_rr) / (2. * h),
+        g_thetatheta: (plus.g_thetatheta - minus.g_thetatheta) / (2. * h),
+        g_phiphi: (plus.g_phiphi - minus.g_phiphi) / (2. * h),
+    }
+}
+
+// the static region between the event horizon or throat and the cosmological horizon
+fn radial_range<M: Metric + ?Sized>(metric: &M) -> Option<(f64, f64)> {
+    let horizons = metric.horizons()?;
+    let r_min = horizons.outer.or(metric.throat()).unwrap_or(1e-3);
+    let r_max = horizons.cosmological.unwrap_or(1e4);
+
+    Some((r_min * (1. + 1e-6), r_max * (1. - 1e-6)))
+}
+
+// f must change sign between lo and hi
+fn bisect(f: impl Fn(f64) -> f64, mut lo: f64, mut hi: f64) -> f64 {
+    let lo_sign = f(lo).signum();
+    for _ in 0..60 {
+        let mid = (lo + hi) / 2.;
+        if f(mid).signum() == lo_sign {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.
+}
+
+// zeros of f between r_min and r_max in ascending order, scanned logarithmically
+fn roots(f: impl Fn(f64) -> f64, r_min: f64, r_max: f64) -> Vec<f64> {
+    const STEPS: usize = 4000;
+
+    let ratio = (r_max / r_min).powf(1. / STEPS as f64);
+    let mut roots = vec![];
+    let mut r0 = r_min;
+    for _ in 0..STEPS {
+        let r1 = r0 * ratio;
+        let (f0, f1) = (f(r0), f(r1));
+        if f0.is_finite() && f1.is_finite() && f0.signum() != f1.signum() {
+            roots.push(bisect(&f, r0, r1));
+        }
+        r0 = r1;
+    }
+    roots
+}
+
+fn spherical_to_cartesian(r: f64, theta: f64, phi: f64) -> Vec3 {
+    let (sin_theta, cos_theta) = theta.sin_cos();
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    Vec3::new((r * sin_theta * cos_phi) as f32, (r * cos_theta) as f32, (-r * sin_theta * sin_phi) as f32)
+}
+
+fn first_root(f: impl Fn(f64) -> f64, r_min: f64, r_max: f64) -> Option<f64> {
+    roots(f, r_min, r_max).first().copied()
+}
+
+/* #region kerr-newman-de sitter */
+// reduces to schwarzschild, reissner-nordstrom, kerr and schwarzschild-de sitter
+pub struct KerrNewmanDeSitter {
+    pub a: f64, // angular momentum per unit mass
+    pub q: f64, // charge
+    pub lambda: f64, // cosmological constant
+}
+
+impl KerrNewmanDeSitter {
+    pub fn delta_r(&self, r: f64) -> f64 {
+        (r * r + self.a * self.a) * (1. - self.lambda * r * r / 3.) - 2. * r + self.q * self.q
+    }
+
+    fn delta_r_derivative(&self, r: f64) -> f64 {
+        2. * r * (1. - self.lambda * r * r / 3.) - 2. * self.lambda * r * (r * r + self.a * self.a) / 3. - 2.
+    }
+}
+
+impl Metric for KerrNewmanDeSitter {
+    // the spherically symmetric cases only need the planar orbit equation
+    fn shader_def(&self) -> &'static str {
+        if self.a == 0. {
+            "METRIC_SPHERICAL"
+        } else {
+            "METRIC_KERR"
+        }
+    }
+
+    fn write_uniform(&self, uniform: &mut SpacetimeUniform) {
+        uniform.a = self.a as f32;
+        uniform.q = self.q as f32;
+        uniform.lambda = self.lambda as f32;
+    }
+
+    // boyer-lindquist r, the spin axis is y, same as in the shader
+    fn to_coordinates(&self, pos: Vec3) -> (f64, f64) {
+        let a2 = self.a * self.a;
+        let y = pos.y as f64;
+        let w = pos.length_squared() as f64 - a2;
+        let r = ((w + (w * w + 4. * a2 * y * y).sqrt()) / 2.).sqrt();
+
+        (r, (y / r).clamp(-1., 1.).acos())
+    }
+
+    fn chart_position(&self, r: f64, theta: f64, phi: f64) -> Vec3 {
+        let big_r = (r * r + self.a * self.a).sqrt();
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let (sin_phi, cos_phi) = phi.sin_cos();
+        Vec3::new((big_r * sin_theta * cos_phi) as f32, (r * cos_theta) as f32, (-big_r * sin_theta * sin_phi) as f32)
+    }
+
+    fn phi_shift(&self, r: f64) -> f64 {
+        let xi = 1. + self.lambda * self.a * self.a / 3.;
+        xi * self.a / self.delta_r(r)
+    }
+
+    fn components(&self, r: f64, theta: f64) -> MetricComponents {
+        let a = self.a;
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        let sin2 = sin_theta * sin_theta;
+
+        let sigma = r * r + a * a * cos_theta * cos_theta;
+        let delta_r = self.delta_r(r);
+        let delta_theta = 1. + self.lambda * a * a * cos_theta * cos_theta / 3.;
+        let xi = 1. + self.lambda * a * a / 3.;
+        let xi2_sigma = xi * xi * sigma;
+
+        MetricComponents {
+            g_tt: -(delta_r - delta_theta * a * a * sin2) / xi2_sigma,
+            g_tphi: a * sin2 * (delta_r - delta_theta * (r * r + a * a)) / xi2_sigma,
+            g_rr: sigma / delta_r,
+            g_thetatheta: sigma / delta_theta,
+            g_phiphi: sin2 * (delta_theta * (r * r + a * a).powi(2) - delta_r * a * a * sin2) / xi2_sigma,
+        }
+    }
+
+    // None when lambda is too large for a black hole to fit inside the cosmological horizon
+    fn horizons(&self) -> Option<Horizons> {
+        if self.lambda == 0. {
+            let d = (1. - self.a * self.a - self.q * self.q).max(0.).sqrt();
+            let inner = (d < 1.).then_some(1. - d);
+            return Some(Horizons { inner, outer: Some(1. + d), cosmological: None });
+        }
+
+        match roots(|r| self.delta_r(r), 1e-3, 1e8)[..] {
+            [outer, cosmological] if self.a == 0. && self.q == 0. => Some(Horizons { inner: None, outer: Some(outer), cosmological: Some(cosmological) }),
+            [inner, outer, cosmological] => Some(Horizons { inner: Some(inner), outer: Some(outer), cosmological: Some(cosmological) }),
+            _ => None,
+        }
+    }
+
+    // the roots of 4 delta_r - r delta_r' +- 4a sqrt(delta_r) outside the event horizon
+    fn photon_orbits(&self) -> Option<(f64, f64)> {
+        let horizons = self.horizons()?;
+        let outer = horizons.outer?;
+        let root = |sign: f64| {
+            let f = |r: f64| 4. * self.delta_r(r) - r * self.delta_r_derivative(r) + sign * 4. * self.a.abs() * self.delta_r(r).max(0.).sqrt();
+            bisect(f, outer, horizons.cosmological.unwrap_or(4.))
+        };
+
+        Some((root(1.), root(-1.)))
+    }
+
+    fn orbital_angular_velocity(&self, r: f64) -> f64 {
+        let k = ((r - self.q * self.q) / r.powi(4) - self.lambda / 3.).max(0.).sqrt();
+        k / (1. + self.a * k)
+    }
+}
+/* #endregion */
+
+/* #region ellis wormhole */
+// ds^2 = -dt^2 + dl^2 + (l^2 + b^2) dOmega^2, in the areal radius r^2 = l^2 + b^2;
+// both universes share one chart where the distance from the origin is |l| + b
+pub struct EllisWormhole {
+    pub throat_radius: f64,
+}
+
+impl Metric for EllisWormhole {
+    fn shader_def(&self) -> &'static str {
+        "METRIC_WORMHOLE"
+    }
+
+    fn write_uniform(&self, uniform: &mut SpacetimeUniform) {
+        uniform.throat_radius = self.throat_radius as f32;
+    }
+
+    fn to_coordinates(&self, pos: Vec3) -> (f64, f64) {
+        let b = self.throat_radius;
+        let length = pos.length() as f64;
+        let l = (length - b).max(0.);
+
+        ((l * l + b * b).sqrt(), (pos.y as f64 / length).clamp(-1., 1.).acos())
+    }
+
+    // on the side of the camera, which is the one the chart is drawn for
+    fn chart_position(&self, r: f64, theta: f64, phi: f64) -> Vec3 {
+        let b = self.throat_radius;
+        spherical_to_cartesian((r * r - b * b).max(0.).sqrt() + b, theta, phi)
+    }
+
+    fn components(&self, r: f64, theta: f64) -> MetricComponents {
+        let b = self.throat_radius;
+
+        MetricComponents {
+            g_tt: -1.,
+            g_tphi: 0.,
+            g_rr: r * r / (r * r - b * b),
+            g_thetatheta: r * r,
+            g_phiphi: r * r * theta.sin().powi(2),
+        }
+    }
+
+    fn horizons(&self) -> Option<Horizons> {
+        Some(Horizons { inner: None, outer: None, cosmological: None })
+    }
+
+    fn throat(&self) -> Option<f64> {
+        Some(self.throat_radius)
+    }
+
+    // the throat is the only photon orbit, and without gravity there are no other circular orbits
+    fn photon_orbits(&self) -> Option<(f64, f64)> {
+        Some((self.throat_radius, self.throat_radius))
+    }
+}
+/* #endregion */
+
+/* #region user-defined */
+// diagonal metric typed into the sidebar as g_tt, g_rr, g_thetatheta and g_phiphi in r and theta;
+// the derivatives are found symbolically and the shader integrates the geodesic equation
+// with the christoffel symbols tabulated over (r, theta)
+pub struct UserDefined {
+    g: [Expr; 4],
+    dg_dr: [Expr; 4],
+    dg_dtheta: [Expr; 4],
+}
+
+const TABLE_R_CNT: usize = 256;
+const TABLE_THETA_CNT: usize = 64;
+const TABLE_R_MIN: f64 = 0.1; // without an event horizon
+const TABLE_R_MAX: f64 = 1e3;
+
+impl UserDefined {
+    pub fn parse(sources: &[String; 4]) -> Result<Self, String> {
+        let mut g = vec![];
+        for source in sources {
+            g.push(Expr::parse(source)?);
+        }
+        let g: [Expr; 4] = g.try_into().expect("Four metric components.");
+
+        Ok(UserDefined {
+            dg_dr: g.clone().map(|g| g.derivative(Var::R)),
+            dg_dtheta: g.clone().map(|g| g.derivative(Var::Theta)),
+            g,
+        })
+    }
+
+    // the non-vanishing christoffel symbols of a diagonal metric, in the order
+    // t_tr, t_ttheta, r_tt, r_rr, r_rtheta, r_thetatheta, r_phiphi,
+    // theta_tt, theta_rr, theta_rtheta, theta_thetatheta, theta_phiphi, phi_rphi, phi_thetaphi
+    pub fn christoffel_symbols(&self, r: f64, theta: f64) -> [f64; 14] {
+        let [g_tt, g_rr, g_thth, g_phph] = self.g.each_ref().map(|g| g.eval(r, theta));
+        let [dr_tt, dr_rr, dr_thth, dr_phph] = self.dg_dr.each_ref().map(|g| g.eval(r, theta));
+        let [dth_tt, dth_rr, dth_thth, dth_phph] = self.dg_dtheta.each_ref().map(|g| g.eval(r, theta));
+
+        [
+            dr_tt / (2. * g_tt),
+            dth_tt / (2. * g_tt),
+            -dr_tt / (2. * g_rr),
+            dr_rr / (2. * g_rr),
+            dth_rr / (2. * g_rr),
+            -dr_thth / (2. * g_rr),
+            -dr_phph / (2. * g_rr),
+            -dth_tt / (2. * g_thth),
+            -dth_rr / (2. * g_thth),
+            dr_thth / (2. * g_thth),
+            dth_thth / (2. * g_thth),
+            -dth_phph / (2. * g_thth),
+            dr_phph / (2. * g_phph),
+            dth_phph / (2. * g_phph),
+        ]
+    }
+
+    // r is sampled logarithmically between these, theta at the centers of equal cells to avoid the poles
+    fn table_range(&self) -> (f64, f64) {
+        let horizons = self.horizons();
+        let r_min = horizons.and_then(|h| h.outer).map_or(TABLE_R_MIN, |r| r * 1.001);
+        let r_max = horizons.and_then(|h| h.cosmological).unwrap_or(TABLE_R_MAX);
+        (r_min, r_max)
+    }
+
+    // rgba32 float texture with five tiles of TABLE_THETA_CNT rows stacked vertically,
+    // the first holds the metric components and the rest the christoffel symbols
+    pub fn metric_table(&self) -> Image {
+        let (r_min, r_max) = self.table_range();
+        let ratio = (r_max / r_min).powf(1. / (TABLE_R_CNT - 1) as f64);
+
+        let mut tiles = vec![vec![]; 5];
+        for j in 0..TABLE_THETA_CNT {
+            let theta = (j as f64 + 0.5) / TABLE_THETA_CNT as f64 * PI;
+            for i in 0..TABLE_R_CNT {
+                let r = r_min * ratio.powi(i as i32);
+                let g = self.g.each_ref().map(|g| g.eval(r, theta));
+                let christoffel = self.christoffel_symbols(r, theta);
+
+                tiles[0].extend(g);
+                for (k, tile) in tiles[1..].iter_mut().enumerate() {
+                    tile.extend((0..4).map(|c| christoffel.get(4 * k + c).copied().unwrap_or(0.)));
+                }
+            }
+        }
+
+        let data = tiles
+            .concat()
+            .into_iter()
+            .flat_map(|value| (value as f32).to_le_bytes())
+            .collect();
+
+        Image::new(
+            Extent3d { width: TABLE_R_CNT as u32, height: 5 * TABLE_THETA_CNT as u32, depth_or_array_layers: 1 },
+            TextureDimension::D2,
+            data,
+            TextureFormat::Rgba32Float,
+            RenderAssetUsages::RENDER_WORLD,
+        )
+    }
+}
+
+impl Metric for UserDefined {
+    fn shader_def(&self) -> &'static str {
+        "METRIC_USER"
+    }
+
+    fn write_uniform(&self, uniform: &mut SpacetimeUniform) {
+        let (r_min, r_max) = self.table_range();
+        uniform.table_r_min = r_min as f32;
+        uniform.table_r_max = r_max as f32;
+    }
+
+    fn to_coordinates(&self, pos: Vec3) -> (f64, f64) {
+        let r = pos.length() as f64;
+        (r, (pos.y as f64 / r).clamp(-1., 1.).acos())
+    }
+
+    fn chart_position(&self, r: f64, theta: f64, phi: f64) -> Vec3 {
+        spherical_to_cartesian(r, theta, phi)
+    }
+
+    fn components(&self, r: f64, theta: f64) -> MetricComponents {
+        let [g_tt, g_rr, g_thetatheta, g_phiphi] = self.g.each_ref().map(|g| g.eval(r, theta));
+        MetricComponents { g_tt, g_tphi: 0., g_rr, g_thetatheta, g_phiphi }
+    }
+
+    // killing horizons of d/dt in the equatorial plane, the outermost one is cosmological
+    // when the metric isn't static far away
+    fn horizons(&self) -> Option<Horizons> {
+        let g_tt = |r: f64| self.g[0].eval(r, FRAC_PI_2);
+        let static_far_away = g_tt(1e4) < 0.;
+
+        match (static_far_away, &roots(g_tt, 1e-3, 1e4)[..]) {
+            (true, []) => Some(Horizons { inner: None, outer: None, cosmological: None }),
+            (true, [outer]) => Some(Horizons { inner: None, outer: Some(*outer), cosmological: None }),
+            (true, [.., inner, outer]) => Some(Horizons { inner: Some(*inner), outer: Some(*outer), cosmological: None }),
+            (false, [cosmological]) => Some(Horizons { inner: None, outer: None, cosmological: Some(*cosmological) }),
+            (false, [outer, cosmological]) => Some(Horizons { inner: None, outer: Some(*outer), cosmological: Some(*cosmological) }),
+            (false, [.., inner, outer, cosmological]) => Some(Horizons { inner: Some(*inner), outer: Some(*outer), cosmological: Some(*cosmological) }),
+            (false, []) => None,
+        }
+    }
+}
+/* #endregion */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MetricKind {
+    #[default]
+    KerrNewmanDeSitter,
+    EllisWormhole,
+    UserDefined,
+}
+
+impl MetricKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MetricKind::KerrNewmanDeSitter => "Kerr-Newman-de Sitter",
+            MetricKind::EllisWormhole => "Ellis wormhole",
+            MetricKind::UserDefined => "User-defined",
+        }
+    }
+
+    // the one after this in the sidebar
+    pub fn next(&self) -> Self {
+        match self {
+            MetricKind::KerrNewmanDeSitter => MetricKind::EllisWormhole,
+            MetricKind::EllisWormhole => MetricKind::UserDefined,
+            MetricKind::UserDefined => MetricKind::KerrNewmanDeSitter,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(value: f64, expected: f64, tolerance: f64) -> bool {
+        (value - expected).abs() < tolerance
+    }
+
+    // bardeen, press and teukolsky, prograde for positive a and retrograde for negative a
+    fn kerr_isco(a: f64) -> f64 {
+        let z1 = 1. + (1. - a * a).cbrt() * ((1. + a).cbrt() + (1. - a).cbrt());
+        let z2 = (3. * a * a + z1 * z1).sqrt();
+        3. + z2 - a.signum() * ((3. - z1) * (3. + z1 + 2. * z2)).sqrt()
+    }
+
+    #[test]
+    fn roots_in_order() {
+        let found = roots(|r| (r - 1.) * (r - 3.) * (r - 10.), 0.5, 100.);
+        assert_eq!(found.len(), 3);
+        assert!(found.iter().zip([1., 3., 10.]).all(|(&r, expected)| close(r, expected, 1e-12)), "{found:?}");
+        assert!(roots(|r| r * r + 1., 1e-3, 1e4).is_empty());
+    }
+
+    #[test]
+    fn schwarzschild() {
+        let metric = KerrNewmanDeSitter { a: 0., q: 0., lambda: 0. };
+        let horizons = metric.horizons().unwrap();
+        assert_eq!((horizons.inner, horizons.outer, horizons.cosmological), (None, Some(2.), None));
+
+        let (prograde, retrograde) = metric.photon_orbits().unwrap();
+        assert!(close(prograde, 3., 1e-9) && close(retrograde, 3., 1e-9), "{prograde} {retrograde}");
+        let isco = metric.isco().unwrap();
+        assert!(close(isco, 6., 1e-6), "{isco}");
+    }
+
+    // r = 1 +- sqrt(1 - q^2), the same when they are found as roots of delta_r with a tiny lambda
+    #[test]
+    fn reissner_nordstrom() {
+        for q in [0.3_f64, 0.8, 0.99] {
+            let d = (1. - q * q).sqrt();
+            for lambda in [0., 1e-9] {
+                let horizons = KerrNewmanDeSitter { a: 0., q, lambda }.horizons().unwrap();
+                assert!(close(horizons.inner.unwrap(), 1. - d, 1e-6), "q {q} lambda {lambda}");
+                assert!(close(horizons.outer.unwrap(), 1. + d, 1e-6), "q {q} lambda {lambda}");
+            }
+        }
+    }
+
+    #[test]
+    fn kerr() {
+        for a in [0.5, 0.9, -0.5, -0.9] {
+            let metric = KerrNewmanDeSitter { a, q: 0., lambda: 0. };
+            let isco = metric.isco().unwrap();
+            assert!(close(isco, kerr_isco(a), 1e-5), "a {a}: {isco} instead of {}", kerr_isco(a));
+        }
+
+        // the prograde orbits come down to the horizon at r = 1 as the spin becomes extremal, the retrograde ones
+        // stay at 4 and 9
+        let extremal = KerrNewmanDeSitter { a: 0.9999, q: 0., lambda: 0. };
+        let (prograde, retrograde) = extremal.photon_orbits().unwrap();
+        assert!(close(prograde, 1., 0.05) && close(retrograde, 4., 1e-3), "{prograde} {retrograde}");
+        let isco = extremal.isco().unwrap();
+        assert!(close(isco, kerr_isco(0.9999), 1e-3) && close(isco, 1., 0.1), "{isco}");
+        let retrograde_isco = KerrNewmanDeSitter { a: -1., q: 0., lambda: 0. }.isco().unwrap();
+        assert!(close(retrograde_isco, 9., 1e-5), "{retrograde_isco}");
+    }
+
+    // the horizons are the positive roots of r^3 - 3 r / lambda + 6 / lambda, in the trigonometric form
+    #[test]
+    fn schwarzschild_de_sitter() {
+        for lambda in [1e-6_f64, 1e-3, 0.1] {
+            let root = |k: f64| 2. / lambda.sqrt() * ((-3. * lambda.sqrt()).acos() / 3. - 2. * PI * k / 3.).cos();
+            let horizons = KerrNewmanDeSitter { a: 0., q: 0., lambda }.horizons().unwrap();
+            assert!(close(horizons.cosmological.unwrap() / root(0.), 1., 1e-9), "lambda {lambda}");
+            assert!(close(horizons.outer.unwrap() / root(1.), 1., 1e-9), "lambda {lambda}");
+        }
+
+        // the cosmological horizon would swallow the black hole above lambda = 1 / 9
+        assert!(KerrNewmanDeSitter { a: 0., q: 0., lambda: 0.12 }.horizons().is_none());
+    }
+
+    fn schwarzschild_components() -> [String; 4] {
+        ["-(1-2/r)", "1/(1-2/r)", "r^2", "r^2*sin(θ)^2"].map(str::to_string)
+    }
+
+    // in the order of UserDefined::christoffel_symbols
+    fn schwarzschild_christoffel_symbols(r: f64, theta: f64) -> [f64; 14] {
+        let f = 1. - 2. / r;
+        let (sin_theta, cos_theta) = theta.sin_cos();
+        [
+            1. / (r * r * f),
+            0.,
+            f / (r * r),
+            -1. / (r * r * f),
+            0.,
+            -r * f,
+            -r * f * sin_theta * sin_theta,
+            0.,
+            0.,
+            1. / r,
+            0.,
+            -sin_theta * cos_theta,
+            1. / r,
+            cos_theta / sin_theta,
+        ]
+    }
+
+    #[test]
+    fn user_defined_schwarzschild() {
+        let metric = UserDefined::parse(&schwarzschild_components()).unwrap();
+        for (r, theta) in [(3., 0.3), (10., FRAC_PI_2), (2.5, 2.), (100., 1.)] {
+            let symbols = metric.christoffel_symbols(r, theta);
+            let expected = schwarzschild_christoffel_symbols(r, theta);
+            for (i, (symbol, expected)) in symbols.iter().zip(expected).enumerate() {
+                assert!((symbol - expected).abs() < 1e-12 * expected.abs().max(1.), "{i} at {r}, {theta}: {symbol} instead of {expected}");
+            }
+        }
+
+        let horizons = metric.horizons().unwrap();
+        assert!((horizons.outer.unwrap() - 2.).abs() < 1e-9);
+        assert!(horizons.inner.is_none() && horizons.cosmological.is_none());
+    }
+
+    // the rows of the first tile hold the components and the next ones the christoffel symbols, four to a texel
+    #[test]
+    fn user_defined_table() {
+        let metric = UserDefined::parse(&schwarzschild_components()).unwrap();
+        let table = metric.metric_table();
+        assert_eq!((table.width(), table.height()), (TABLE_R_CNT as u32, 5 * TABLE_THETA_CNT as u32));
+
+        let texel = |x: usize, y: usize| {
+            let offset = 16 * (y * TABLE_R_CNT + x);
+            [0, 1, 2, 3].map(|i| f32::from_le_bytes(table.data[offset + 4 * i..offset + 4 * i + 4].try_into().unwrap()))
+        };
+        let (r_min, _) = metric.table_range();
+        let theta = 0.5 / TABLE_THETA_CNT as f64 * PI;
+        let g = metric.components(r_min, theta);
+        let symbols = metric.christoffel_symbols(r_min, theta);
+
+        assert_eq!(texel(0, 0), [g.g_tt, g.g_rr, g.g_thetatheta, g.g_phiphi].map(|g| g as f32));
+        assert_eq!(texel(0, TABLE_THETA_CNT), [0, 1, 2, 3].map(|i| symbols[i] as f32));
+        assert_eq!(texel(0, 4 * TABLE_THETA_CNT), [symbols[12] as f32, symbols[13] as f32, 0., 0.]);
+        // just outside of the horizon
+        assert!((r_min / 2. - 1.001).abs() < 1e-6);
+    }
+
+    #[test]
+    fn user_defined_parse_errors() {
+        let mut components = schwarzschild_components();
+        components[2] = "r^".to_string();
+        assert!(UserDefined::parse(&components).is_err());
+    }
+}
@@ -0,0 +1,272 @@
+use std::{f64::consts::PI, path::{Path, PathBuf}};
+
+use bevy::math::{DVec2, DVec3, DVec4, Vec3};
+use image::{Rgba, Rgba32FImage, RgbaImage};
+
+use crate::{catalogue::Stars, disc::{DiscEmission, DiscShape}, geodesic::Observer, get_cam_axis, metric::MetricKind, reference::{self, to_cubemap, to_equirectangular, Camera, Frame, Shading}, scene::SceneFile, skybox::{SkyboxProjection, SkyboxSource, CUBE_FACES}, spacescape::SpacescapeProject, starfield::Starfield};
+
+// `grplayground render <scene.toml> <output.png>` traces the scene with the cpu tracer in f64 and writes
+// the image without opening a window, so it isn't limited by the size of the screen or the precision of the shader
+
+pub const ASSETS_DIR: &str = "assets"; // where bevy looks for them when run with cargo
+
+const USAGE: &str = "Usage: grplayground render <scene.toml> <output.png> [--width <px>] [--height <px>] [--samples <n>] [--exact]
+
+  --width, --height  size of the image, 1920x1080 by default
+  --samples          rays per pixel along each axis, 1 by default
+  --exact            schwarzschild orbits from elliptic functions instead of the integrator";
+
+#[derive(Debug, Clone)]
+struct RenderArgs {
+    scene: PathBuf,
+    output: PathBuf,
+    width: usize,
+    height: usize,
+    samples: usize,
+    exact: bool,
+}
+
+impl RenderArgs {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut paths = vec![];
+        let mut render_args = RenderArgs {
+            scene: PathBuf::new(),
+            output: PathBuf::new(),
+            width: 1920,
+            height: 1080,
+            samples: 1,
+            exact: false,
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| -> Result<usize, String> {
+                let value = args.next().ok_or(format!("Missing value of {name}.\n\n{USAGE}"))?;
+                value.parse().ok().filter(|&value| value > 0).ok_or(format!("Invalid value of {name}: {value}.\n\n{USAGE}"))
+            };
+
+            match arg.as_str() {
+                "--width" => render_args.width = value(arg)?,
+                "--height" => render_args.height = value(arg)?,
+                "--samples" => render_args.samples = value(arg)?,
+                "--exact" => render_args.exact = true,
+                _ if arg.starts_with("--") => return Err(format!("Unknown option {arg}.\n\n{USAGE}")),
+                _ => paths.push(PathBuf::from(arg)),
+            }
+        }
+
+        let [scene, output] = <[PathBuf; 2]>::try_from(paths).map_err(|_| USAGE.to_string())?;
+        render_args.scene = scene;
+        render_args.output = output;
+        Ok(render_args)
+    }
+}
+
+/* #region textures */
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(value: f64) -> f64 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1. / 2.4) - 0.055
+    }
+}
+
+// linear colours like the gpu samples the srgb textures, hdr images are linear already
+fn load_texture(path: &str) -> Result<Rgba32FImage, String> {
+    let path = Path::new(ASSETS_DIR).join(path);
+    let mut texture = image::open(&path).map_err(|error| format!("Failed to load {}: {error}", path.display()))?.into_rgba32f();
+    if path.extension().is_some_and(|extension| extension == "hdr") {
+        return Ok(texture);
+    }
+    for pixel in texture.pixels_mut() {
+        for channel in &mut pixel.0[..3] {
+            *channel = srgb_to_linear(*channel);
+        }
+    }
+    Ok(texture)
+}
+
+// bilinear and clamped to the edges like bevy's default sampler
+fn sample(texture: &Rgba32FImage, coords: DVec2) -> DVec4 {
+    let (width, height) = texture.dimensions();
+    let pixel = coords * DVec2::new(width as f64, height as f64) - 0.5;
+    let corner = pixel.floor();
+    let t = pixel - corner;
+
+    let texel = |x: f64, y: f64| {
+        let x = x.clamp(0., width as f64 - 1.) as u32;
+        let y = y.clamp(0., height as f64 - 1.) as u32;
+        DVec4::from_array(texture.get_pixel(x, y).0.map(f64::from))
+    };
+    let top = texel(corner.x, corner.y).lerp(texel(corner.x + 1., corner.y), t.x);
+    let bottom = texel(corner.x, corner.y + 1.).lerp(texel(corner.x + 1., corner.y + 1.), t.x);
+    top.lerp(bottom, t.y)
+}
+
+enum Sky {
+    Cube([Rgba32FImage; 6]), // in the order of CUBE_FACES
+    Panorama(Rgba32FImage),
+}
+
+impl Sky {
+    fn load(source: &SkyboxSource, starfield: &Starfield) -> Result<Self, String> {
+        match source.projection {
+            SkyboxProjection::Equirectangular => return Ok(Sky::Panorama(load_texture(&source.path)?)),
+            SkyboxProjection::Starfield => return Ok(Sky::Cube(starfield.generate())),
+            SkyboxProjection::Cube => {}
+        }
+        if source.is_spacescape() {
+            let project = SpacescapeProject::load(&Path::new(ASSETS_DIR).join(&source.path))?;
+            return Ok(Sky::Cube(project.generate(starfield.size)));
+        }
+        if source.is_directory() {
+            let mut faces = vec![];
+            for face in CUBE_FACES {
+                faces.push(load_texture(&format!("{}/{face}.png", source.path))?);
+            }
+            return Ok(Sky::Cube(faces.try_into().expect("Six skybox faces.")));
+        }
+        if source.path.ends_with(".ktx2") || source.path.ends_with(".dds") {
+            return Err(format!("The cpu tracer can't read the cube map {}, only the faces or a single image with them stacked.", source.path));
+        }
+
+        let stacked = load_texture(&source.path)?;
+        let (width, height) = stacked.dimensions();
+        if height != width * 6 {
+            return Err(format!("{} has to be six square faces stacked on each other, not {width}x{height}.", source.path));
+        }
+        let faces = std::array::from_fn(|i| image::imageops::crop_imm(&stacked, 0, i as u32 * width, width, width).to_image());
+        Ok(Sky::Cube(faces))
+    }
+}
+
+// sample_skybox and sample_accretion_disc of shaders/common.wgsl
+struct TextureShading {
+    sky: Sky,
+    skybox_intensity: f64,
+    disc: Rgba32FImage,
+    disc_r: f64,
+    disc_width: f64,
+    disc_intensity: f64,
+}
+
+impl TextureShading {
+    fn load(scene: &SceneFile) -> Result<Self, String> {
+        let [sky, _] = scene.skybox.sources();
+        Ok(TextureShading {
+            sky: Sky::load(&sky, &scene.skybox.starfield)?,
+            skybox_intensity: scene.skybox.intensity as f64,
+            disc: load_texture(&scene.accretion_disc.texture)?,
+            disc_r: scene.accretion_disc.r as f64,
+            disc_width: scene.accretion_disc.width as f64,
+            disc_intensity: scene.accretion_disc.intensity as f64,
+        })
+    }
+}
+
+impl Shading for TextureShading {
+    fn sky(&self, direction: DVec3) -> DVec4 {
+        let color = match &self.sky {
+            Sky::Cube(faces) => {
+                let (coords, face) = to_cubemap(direction);
+                sample(&faces[face], coords)
+            }
+            Sky::Panorama(panorama) => sample(panorama, to_equirectangular(direction)),
+        };
+        color * self.skybox_intensity
+    }
+
+    fn disc(&self, point: DVec3, omega: f64, time: f64) -> DVec4 {
+        let max_r = self.disc_r + self.disc_width;
+        let r = point.length();
+        if r <= self.disc_r || r >= max_r {
+            return DVec4::ZERO;
+        }
+
+        let phi = (point.z.atan2(point.x) + (omega * time) % (2. * PI) + PI).rem_euclid(2. * PI);
+        let radial = (max_r - r) / self.disc_width;
+        sample(&self.disc, DVec2::new(phi / (2. * PI), radial)) * radial * self.disc_intensity
+    }
+}
+/* #endregion */
+
+// the scenes the cpu tracer draws like the window does, the rest are refused rather than drawn without what it lacks
+fn reference_scene(scene: &SceneFile, exact: bool) -> Result<reference::Scene, String> {
+    let spacetime = &scene.spacetime;
+    if spacetime.metric != MetricKind::KerrNewmanDeSitter || spacetime.a != 0. {
+        return Err(format!("The cpu tracer only follows the spherically symmetric metrics, not {} with a = {}.", spacetime.metric.name(), spacetime.a));
+    }
+    let disc = &scene.accretion_disc.params;
+    if disc.emission != DiscEmission::Texture {
+        return Err(format!("The cpu tracer only draws the texture of the accretion disc, not the {} emission.", disc.emission.name().to_lowercase()));
+    }
+    if disc.shape != DiscShape::Thin {
+        return Err(format!("The cpu tracer only draws the thin accretion disc, not the {} shape.", disc.shape.name().to_lowercase()));
+    }
+    if scene.jets.enabled {
+        return Err("The cpu tracer doesn't draw jets, turn them off in the scene.".to_string());
+    }
+    if scene.camera.observer != Observer::Static {
+        return Err(format!("The cpu tracer only renders the static observer, not the {} one.", scene.camera.observer.name().to_lowercase()));
+    }
+    if scene.camera.velocity != Vec3::ZERO {
+        return Err("The cpu tracer only renders a static camera, not one falling freely.".to_string());
+    }
+
+    let (x, y, z) = get_cam_axis(scene.camera.eye, scene.camera.target);
+    Ok(reference::Scene {
+        q: spacetime.q,
+        lambda: spacetime.lambda,
+        camera: Camera {
+            pos: scene.camera.eye.as_dvec3(),
+            x: x.as_dvec3(),
+            y: y.as_dvec3(),
+            z: z.as_dvec3(),
+            fov: (scene.camera.fov as f64).to_radians(),
+            velocity: DVec3::ZERO,
+        },
+        accretion_disc_r: scene.accretion_disc.r as f64,
+        accretion_disc_width: scene.accretion_disc.width as f64,
+        accretion_disc_time: scene.accretion_disc.time,
+        integrator: scene.ray_tracing.integrator,
+        step_budget: scene.ray_tracing.step_budget.clamp(10, 5000),
+        max_orbits: scene.ray_tracing.max_orbits.max(0.1),
+        exact,
+    })
+}
+
+// srgb with the colours clamped, like the window shows them
+fn to_image(frame: &Frame) -> RgbaImage {
+    RgbaImage::from_fn(frame.width as u32, frame.height as u32, |x, y| {
+        let color = frame.pixels[y as usize * frame.width + x as usize];
+        let channel = |value: f64| (linear_to_srgb(value.clamp(0., 1.)) * 255.).round() as u8;
+        Rgba([channel(color.x), channel(color.y), channel(color.z), 255])
+    })
+}
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let args = RenderArgs::parse(args)?;
+    let scene = SceneFile::load(&args.scene)?;
+    let tracer_scene = reference_scene(&scene, args.exact)?;
+    let shading = TextureShading::load(&scene)?;
+
+    let catalogue = &scene.skybox.catalogue;
+    let stars = if catalogue.path.is_empty() { None } else { Some(Stars::load(&Path::new(ASSETS_DIR).join(&catalogue.path))?) };
+
+    let mut frame = reference::render(&tracer_scene, &shading, args.width, args.height, args.samples);
+    if let Some(stars) = &stars {
+        let star_frame = reference::render_stars(&tracer_scene, stars, catalogue.brightness, args.width, args.height);
+        for (pixel, star) in frame.pixels.iter_mut().zip(star_frame.pixels) {
+            *pixel += star;
+        }
+    }
+    to_image(&frame).save(&args.output).map_err(|error| format!("Failed to write {}: {error}", args.output.display()))
+}
@@ -0,0 +1,709 @@
+use std::f64::consts::PI;
+
+use bevy::math::{DVec2, DVec3, DVec4, Vec3Swizzles};
+use rayon::prelude::*;
+
+use crate::{catalogue::Stars, elliptic::NullGeodesic, metric::{KerrNewmanDeSitter, Metric}, Integrator};
+
+// assets/shaders/metrics/spherical.wgsl in f64 on the cpu with the thin disc, to test the physics without a gpu
+// and to render frames offline; the functions have the names of the ones in the shaders they follow
+
+const INTEGRATOR_TOLERANCE: f64 = 1e-5;
+const BENDING_GAIN: f64 = 4.;
+const CHANNEL_WAVELENGTHS: [f64; 3] = [610., 550., 465.];
+const MAX_FREQUENCY_SHIFT: f64 = 10.;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub pos: DVec3,
+    pub x: DVec3, // right
+    pub y: DVec3, // up
+    pub z: DVec3, // the way the camera is facing
+    pub fov: f64,
+    pub velocity: DVec3, // relative to the static observer, see SpacetimeMaterial::cam_velocity
+}
+
+// the uniforms of the shader that the spherical metrics use
+#[derive(Debug, Clone, Copy)]
+pub struct Scene {
+    pub q: f64,
+    pub lambda: f64,
+    pub camera: Camera,
+    pub accretion_disc_r: f64,
+    pub accretion_disc_width: f64,
+    pub accretion_disc_time: f64,
+    pub integrator: Integrator,
+    pub step_budget: u32,
+    pub max_orbits: f64,
+    pub exact: bool, // schwarzschild orbits from elliptic::NullGeodesic instead of the integrator where it applies
+}
+
+// what the rays see; the colours are the ones at rest, the tracer shifts them
+pub trait Shading: Sync {
+    fn sky(&self, direction: DVec3) -> DVec4;
+    // transparent black outside of the disc, see sample_accretion_disc
+    fn disc(&self, point: DVec3, omega: f64, time: f64) -> DVec4;
+}
+
+// rows from the top, linear colours like the shader writes them
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<DVec4>,
+}
+
+/* #region shader functions */
+// the layer of skybox::CUBE_FACES and the texture coordinates on it that the gpu samples for a direction,
+// see to_cube_direction in the shader
+pub fn to_cubemap(v: DVec3) -> (DVec2, usize) {
+    let v = DVec3::new(v.x, v.y, -v.z);
+    let a = v.abs();
+    // the major axis and the coordinates on its face, with the signs of the cube map table of vulkan
+    let (face, ma, sc, tc) = if a.x >= a.y && a.x >= a.z {
+        if v.x > 0. { (0, a.x, -v.z, -v.y) } else { (1, a.x, v.z, -v.y) }
+    } else if a.y >= a.z {
+        if v.y > 0. { (2, a.y, v.x, v.z) } else { (3, a.y, v.x, -v.z) }
+    } else if v.z > 0. {
+        (4, a.z, v.x, -v.y)
+    } else {
+        (5, a.z, -v.x, -v.y)
+    };
+
+    (DVec2::new(sc, tc) / ma / 2. + 0.5, face)
+}
+
+// the direction a point of a face of to_cubemap is seen in
+pub fn from_cubemap(coords: DVec2, face: usize) -> DVec3 {
+    let DVec2 { x: sc, y: tc } = coords * 2. - 1.;
+    let v = match face {
+        0 => DVec3::new(1., -tc, -sc),
+        1 => DVec3::new(-1., -tc, sc),
+        2 => DVec3::new(sc, 1., tc),
+        3 => DVec3::new(sc, -1., -tc),
+        4 => DVec3::new(sc, -tc, 1.),
+        _ => DVec3::new(-sc, -tc, -1.),
+    };
+    DVec3::new(v.x, v.y, -v.z).normalize()
+}
+
+pub fn to_equirectangular(v: DVec3) -> DVec2 {
+    let d = v.normalize();
+    DVec2::new(0.5 + d.x.atan2(-d.z) / (2. * PI), 0.5 - d.y.clamp(-1., 1.).asin() / PI)
+}
+
+fn lobe(wavelength: f64, center: f64, width_below: f64, width_above: f64) -> f64 {
+    let t = (wavelength - center) / if wavelength < center { width_below } else { width_above };
+    (-0.5 * t * t).exp()
+}
+
+fn color_matching(w: f64) -> DVec3 {
+    DVec3::new(
+        1.056 * lobe(w, 599.8, 37.9, 31.0) + 0.362 * lobe(w, 442.0, 16.0, 26.7) - 0.065 * lobe(w, 501.1, 20.4, 26.2),
+        0.821 * lobe(w, 568.8, 46.9, 40.5) + 0.286 * lobe(w, 530.9, 16.3, 31.1),
+        1.217 * lobe(w, 437.0, 11.8, 36.0) + 0.681 * lobe(w, 459.0, 26.0, 13.8),
+    )
+}
+
+pub fn blackbody_color(temperature: f64) -> DVec3 {
+    let temperature = temperature.clamp(500., 1e6);
+
+    let mut xyz = DVec3::ZERO;
+    for i in 0..=40 {
+        let wavelength = 380. + 10. * i as f64;
+        let um = wavelength / 1000.;
+        let radiance = 1. / (um.powi(5) * ((14388. / (um * temperature)).exp() - 1.));
+        xyz += radiance * color_matching(wavelength);
+    }
+
+    let rgb = DVec3::new(
+        3.2406 * xyz.x - 1.5372 * xyz.y - 0.4986 * xyz.z,
+        -0.9689 * xyz.x + 1.8758 * xyz.y + 0.0415 * xyz.z,
+        0.0557 * xyz.x - 0.2040 * xyz.y + 1.0570 * xyz.z,
+    ).max(DVec3::ZERO);
+    rgb / rgb.max_element()
+}
+
+fn spectrum(color: DVec3, wavelength: f64) -> f64 {
+    let [x, y, z] = CHANNEL_WAVELENGTHS;
+    let mix = |a: f64, b: f64, t: f64| a + (b - a) * t;
+    if wavelength >= x {
+        return color.x * (1. - (wavelength - x) / 100.).max(0.);
+    }
+    if wavelength >= y {
+        return mix(color.y, color.x, (wavelength - y) / (x - y));
+    }
+    if wavelength >= z {
+        return mix(color.z, color.y, (wavelength - z) / (y - z));
+    }
+    color.z * (1. - (z - wavelength) / 100.).max(0.)
+}
+
+pub fn frequency_shift(color: DVec4, g: f64) -> DVec4 {
+    let g = g.clamp(0., MAX_FREQUENCY_SHIFT);
+    let rgb = color.truncate();
+    let shifted = DVec3::from_array(CHANNEL_WAVELENGTHS.map(|w| spectrum(rgb, w * g)));
+    (shifted * g * g * g).extend(color.w)
+}
+
+fn camera_frequency_shift(camera: &Camera, ray: DVec3) -> f64 {
+    let gamma = 1. / (1. - camera.velocity.length_squared()).sqrt();
+    gamma * (1. + camera.velocity.dot(ray))
+}
+
+fn aberrate(ray: DVec3, velocity: DVec3) -> DVec3 {
+    let v2 = velocity.length_squared();
+    if v2 == 0. {
+        return ray;
+    }
+
+    let gamma = 1. / (1. - v2).sqrt();
+    let n = -ray;
+    let n_parallel = n.dot(velocity) / v2 * velocity;
+    -(n + (gamma - 1.) * n_parallel + gamma * velocity).normalize()
+}
+
+// uv from -1 to 1 across the screen, y up
+pub fn construct_ray(camera: &Camera, uv: DVec2) -> DVec3 {
+    let fov_mult = 1. / (camera.fov / 2.).tan();
+    aberrate((uv.x * camera.x + uv.y * camera.y + fov_mult * camera.z).normalize(), camera.velocity)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct OrbitStep {
+    u: f64,
+    v: f64,
+    delta: f64,
+    next_delta: f64,
+}
+/* #endregion */
+
+// a scene with the horizons it has
+struct Tracer<'a> {
+    scene: &'a Scene,
+    shading: &'a dyn Shading,
+    horizon_u: f64,
+    escape_u: f64,
+}
+
+// where a ray ended up besides its colour, captured and end_phi only the tests look at
+#[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
+struct Marched {
+    color: DVec4,
+    captured: bool,
+    end_phi: f64, // where u went through 0, or the last phi the ray got to
+    sky: Option<(DVec3, f64)>, // the direction it sampled the sky in and the frequency shift of the sky
+}
+
+impl Tracer<'_> {
+    fn new<'a>(scene: &'a Scene, shading: &'a dyn Shading) -> Tracer<'a> {
+        let horizons = KerrNewmanDeSitter { a: 0., q: scene.q, lambda: scene.lambda }.horizons();
+        let horizon = horizons.and_then(|horizons| horizons.outer).unwrap_or(0.);
+        let cosmological_horizon = horizons.and_then(|horizons| horizons.cosmological);
+        Tracer {
+            scene,
+            shading,
+            horizon_u: if horizon > 0. { 1. / horizon } else { f64::INFINITY },
+            escape_u: cosmological_horizon.map_or(0., |r| 1. / r),
+        }
+    }
+
+    fn second_derivative(&self, u: f64) -> f64 {
+        u * (3. * u - 1.) - 2. * self.scene.q * self.scene.q * u * u * u
+    }
+
+    fn f(&self, u: f64) -> f64 {
+        1. - 2. * u + self.scene.q * self.scene.q * u * u - self.scene.lambda / (3. * u * u)
+    }
+
+    fn keplerian_angular_velocity(&self, u: f64) -> f64 {
+        let q2 = self.scene.q * self.scene.q;
+        (u * u * u - q2 * u * u * u * u - self.scene.lambda / 3.).max(0.).sqrt()
+    }
+
+    fn emitter_shift(&self, point: DVec3, omega: f64, l: f64) -> f64 {
+        let norm = self.f(1. / point.length()) - omega * omega * point.xz().length_squared();
+        if norm <= 0. {
+            return 0.;
+        }
+        norm.sqrt() / (1. - omega * l)
+    }
+
+    fn default_step_size(&self) -> f64 {
+        self.scene.max_orbits * 2. * PI / self.scene.step_budget as f64
+    }
+
+    fn bending_step_size(&self, u: f64) -> f64 {
+        let bending = (self.second_derivative(u) + u).abs() / u.max(1e-6);
+        (2. * self.default_step_size() / (1. + BENDING_GAIN * bending)).max(self.default_step_size() / 16.)
+    }
+
+    /* #region integrators */
+    fn orbit_derivative(&self, y: DVec2) -> DVec2 {
+        DVec2::new(y.y, self.second_derivative(y.x))
+    }
+
+    fn euler(&self, u: f64, v: f64, delta: f64) -> DVec2 {
+        DVec2::new(u + v * delta, v + self.second_derivative(u) * delta)
+    }
+
+    fn leapfrog(&self, u: f64, v: f64, delta: f64) -> DVec2 {
+        let v_intermediate = v + self.second_derivative(u) * delta / 2.;
+        let new_u = u + v_intermediate * delta;
+        DVec2::new(new_u, v_intermediate + self.second_derivative(new_u) * delta / 2.)
+    }
+
+    fn rk4(&self, u: f64, v: f64, delta: f64) -> DVec2 {
+        let y = DVec2::new(u, v);
+        let k1 = self.orbit_derivative(y);
+        let k2 = self.orbit_derivative(y + k1 * delta / 2.);
+        let k3 = self.orbit_derivative(y + k2 * delta / 2.);
+        let k4 = self.orbit_derivative(y + k3 * delta);
+        y + (k1 + 2. * k2 + 2. * k3 + k4) * delta / 6.
+    }
+
+    // the 5th order solution and its difference from the embedded 4th order one
+    fn dormand_prince(&self, u: f64, v: f64, delta: f64) -> (DVec2, DVec2) {
+        let y = DVec2::new(u, v);
+        let k1 = self.orbit_derivative(y);
+        let k2 = self.orbit_derivative(y + delta * (k1 / 5.));
+        let k3 = self.orbit_derivative(y + delta * (3. / 40. * k1 + 9. / 40. * k2));
+        let k4 = self.orbit_derivative(y + delta * (44. / 45. * k1 - 56. / 15. * k2 + 32. / 9. * k3));
+        let k5 = self.orbit_derivative(y + delta * (19372. / 6561. * k1 - 25360. / 2187. * k2 + 64448. / 6561. * k3 - 212. / 729. * k4));
+        let k6 = self.orbit_derivative(y + delta * (9017. / 3168. * k1 - 355. / 33. * k2 + 46732. / 5247. * k3 + 49. / 176. * k4 - 5103. / 18656. * k5));
+        let new_y = y + delta * (35. / 384. * k1 + 500. / 1113. * k3 + 125. / 192. * k4 - 2187. / 6784. * k5 + 11. / 84. * k6);
+        let k7 = self.orbit_derivative(new_y);
+
+        let error = delta * (71. / 57600. * k1 - 71. / 16695. * k3 + 71. / 1920. * k4 - 17253. / 339200. * k5 + 22. / 525. * k6 - 1. / 40. * k7);
+        (new_y, error)
+    }
+
+    fn integrate_step(&self, u: f64, v: f64, delta: f64) -> OrbitStep {
+        let step = |y: DVec2, delta: f64, next_delta: f64| OrbitStep { u: y.x, v: y.y, delta, next_delta };
+        match self.scene.integrator {
+            Integrator::Euler => step(self.euler(u, v, delta), delta, delta),
+            Integrator::Leapfrog => step(self.leapfrog(u, v, delta), delta, delta),
+            Integrator::Rk4 => step(self.rk4(u, v, delta), delta, delta),
+            Integrator::DormandPrince => {
+                let mut h = delta;
+                let mut attempt = self.dormand_prince(u, v, h);
+                for _ in 0..8 {
+                    let scale = INTEGRATOR_TOLERANCE * (DVec2::new(u, v).abs() + 1e-3);
+                    let error = (attempt.1.x.abs() / scale.x).max(attempt.1.y.abs() / scale.y).max(1e-6);
+                    if error <= 1. {
+                        return step(attempt.0, h, h * (0.9 * error.powf(-0.2)).min(5.));
+                    }
+                    h *= (0.9 * error.powf(-0.25)).max(0.2);
+                    attempt = self.dormand_prince(u, v, h);
+                }
+                step(attempt.0, h, h)
+            }
+        }
+    }
+    /* #endregion */
+
+    // the ray of a static camera on the exact orbit through it, None where the camera isn't on one that
+    // comes in from infinity; u at phi along the ray and the phi it escapes at, None for the rays that fall in
+    fn exact_orbit(&self, u0: f64, v0: f64) -> Option<(impl Fn(f64) -> f64, Option<f64>)> {
+        if !self.scene.exact || self.scene.q != 0. || self.scene.lambda != 0. || u0 >= self.horizon_u {
+            return None;
+        }
+
+        let geodesic = NullGeodesic::new(1. / (v0 * v0 + u0 * u0 * self.f(u0)).sqrt());
+        let camera_phi = geodesic.phi(u0)?;
+        // the rays going outwards run back along the orbit
+        let sign = if v0 < 0. { -1. } else { 1. };
+        let escape_phi = if v0 < 0. { Some(camera_phi) } else { geodesic.deflection().map(|deflection| PI + deflection - camera_phi) };
+        Some((move |phi: f64| geodesic.u(camera_phi + sign * phi), escape_phi))
+    }
+
+    fn march(&self, ray: DVec3) -> Marched {
+        let camera = &self.scene.camera;
+        let cam_pos = camera.pos;
+        let cam_normal = cam_pos.normalize();
+        // a radial ray has no plane of its own, one next to it is as good
+        let ray = if cam_normal.cross(ray).length_squared() < 1e-24 { (ray + 1e-9 * cam_normal.any_orthonormal_vector()).normalize() } else { ray };
+        let cam_tangent = cam_normal.cross(ray).cross(cam_normal).normalize();
+
+        let u0 = 1. / cam_pos.length();
+        let mut v0 = -u0 * (ray.dot(cam_normal) / ray.dot(cam_tangent));
+
+        let mut ray = ray;
+        let mut prev_pos;
+        let mut pos = cam_pos;
+        let mut phi = 0.;
+        let mut color = DVec4::new(0., 0., 0., 1.);
+        let black = Marched { color, captured: true, end_phi: 0., sky: None };
+
+        let mut sky_shift = camera_frequency_shift(camera, ray) / self.f(u0).sqrt();
+
+        // the camera falls radially with unit energy inside of the horizon, it would turn around before it got to
+        // f > 1 inside of the inner horizon of a charged hole and is at rest there
+        if u0 >= self.horizon_u {
+            let fall_speed = (1. - self.f(u0)).max(0.).sqrt();
+
+            let energy = 1. + ray.dot(cam_normal) * fall_speed;
+            if energy <= 0. {
+                return black;
+            }
+            sky_shift = camera_frequency_shift(camera, ray) / energy;
+
+            v0 = u0 * (-fall_speed - ray.dot(cam_normal)) / ray.dot(cam_tangent);
+        }
+
+        let mut u = u0;
+        let mut v = v0;
+
+        let impact_parameter = 1. / (v0 * v0 + u0 * u0 * self.f(u0)).max(1e-12).sqrt();
+        let l = -impact_parameter * cam_normal.cross(cam_tangent).y;
+
+        let exact = self.exact_orbit(u0, v0);
+        let accretion_disc_max_r = self.scene.accretion_disc_r + self.scene.accretion_disc_width;
+        let mut step_size = self.bending_step_size(u0);
+
+        if self.escape_u > 0. && u0 <= self.escape_u {
+            return black;
+        }
+
+        for _ in 0..self.scene.step_budget {
+            if u >= self.horizon_u && v > 0. {
+                return Marched { color, captured: true, end_phi: phi, sky: None };
+            }
+
+            if u <= self.escape_u || phi >= self.scene.max_orbits * 2. * PI {
+                break;
+            }
+
+            prev_pos = pos;
+            let prev_u = u;
+
+            let step = match &exact {
+                Some((exact_u, _)) => {
+                    let u = exact_u(phi + step_size);
+                    OrbitStep { u, v: (u - prev_u) / step_size, delta: step_size, next_delta: step_size }
+                }
+                None => self.integrate_step(u, v, step_size),
+            };
+            u = step.u;
+            v = step.v;
+            phi += step.delta;
+            step_size = if self.scene.integrator == Integrator::DormandPrince && exact.is_none() {
+                step.next_delta.clamp(self.default_step_size() / 64., self.default_step_size() * 2.)
+            } else {
+                self.bending_step_size(u)
+            };
+            pos = (phi.cos() * cam_normal + phi.sin() * cam_tangent) / u;
+            ray = (pos - prev_pos).normalize();
+
+            // accretion disc
+            let crosses = (cam_pos.y > 0. && prev_pos.y > 0. && pos.y < 0.) || (cam_pos.y < 0. && prev_pos.y < 0. && pos.y > 0.);
+            if crosses && ((-cam_pos.normalize()).dot(camera.z) > 0. || u > 1. / accretion_disc_max_r) {
+                let point = prev_pos - prev_pos.y / ray.y * ray;
+
+                let omega = self.keplerian_angular_velocity(1. / point.length());
+                let disc_shift = sky_shift * self.emitter_shift(point, omega, l);
+                if disc_shift > 0. {
+                    color += frequency_shift(self.shading.disc(point, omega, self.scene.accretion_disc_time), disc_shift);
+                }
+            }
+
+            if u <= self.escape_u {
+                // linear in between the steps unless the orbit is exact
+                let end_phi = match &exact {
+                    Some((_, Some(escape_phi))) => *escape_phi,
+                    _ => phi - step.delta * (u - self.escape_u) / (u - prev_u),
+                };
+                // past u = 0 pos is on the other side, the ray goes off where it was heading when u went through
+                // 0, like in trace_table of the shader
+                let direction = if self.escape_u > 0. { pos.normalize() } else { end_phi.cos() * cam_normal + end_phi.sin() * cam_tangent };
+                color += frequency_shift(self.shading.sky(direction), sky_shift);
+                return Marched { color, captured: false, end_phi, sky: Some((direction, sky_shift)) };
+            }
+        }
+
+        let direction = if self.escape_u > 0. { pos.normalize() } else { ray };
+        color += frequency_shift(self.shading.sky(direction), sky_shift);
+        Marched { color, captured: false, end_phi: phi, sky: Some((direction, sky_shift)) }
+    }
+}
+
+// samples^2 rays per pixel on a grid, in parallel; uv goes from -1 to 1 across the longer side like the
+// window does with ScalingMode::AutoMax
+pub fn render(scene: &Scene, shading: &dyn Shading, width: usize, height: usize, samples: usize) -> Frame {
+    let tracer = Tracer::new(scene, shading);
+    let samples = samples.max(1);
+    let half_size = width.max(height) as f64 / 2.;
+    let pixels = (0..width * height)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            let mut color = DVec4::ZERO;
+            for sample in 0..samples * samples {
+                let offset = DVec2::new((sample % samples) as f64 + 0.5, (sample / samples) as f64 + 0.5) / samples as f64;
+                let uv = DVec2::new(x as f64 + offset.x - width as f64 / 2., height as f64 / 2. - y as f64 - offset.y) / half_size;
+                color += tracer.march(construct_ray(&scene.camera, uv)).color;
+            }
+            color / (samples * samples) as f64
+        })
+        .collect();
+
+    Frame { width, height, pixels }
+}
+
+// a shading for the rays that only need to know where they end up
+struct Unlit;
+
+impl Shading for Unlit {
+    fn sky(&self, _: DVec3) -> DVec4 {
+        DVec4::ZERO
+    }
+
+    fn disc(&self, _: DVec3, _: f64, _: f64) -> DVec4 {
+        DVec4::ZERO
+    }
+}
+
+// the solid angle of the spherical triangle between three directions, van oosterom and strackee
+fn solid_angle(a: DVec3, b: DVec3, c: DVec3) -> f64 {
+    2. * a.dot(b.cross(c)).abs().atan2(1. + a.dot(b) + b.dot(c) + c.dot(a))
+}
+
+// whether p is in the triangle abc, all of them projected from the centre of the sphere onto the plane
+// touching it at the direction normal
+fn in_triangle(p: DVec3, [a, b, c]: [DVec3; 3], normal: DVec3) -> bool {
+    let project = |v: DVec3| v / v.dot(normal);
+    let (p, a, b, c) = (project(p), project(a), project(b), project(c));
+    let side = |from: DVec3, to: DVec3| (to - from).cross(p - from).dot(normal);
+    let (ab, bc, ca) = (side(a, b), side(b, c), side(c, a));
+    (ab >= 0. && bc >= 0. && ca >= 0.) || (ab <= 0. && bc <= 0. && ca <= 0.)
+}
+
+// the stars as points; the rays through the corners of a pixel mark out the patch of the sky it sees, a star
+// in the patch adds its light to the pixel times the magnification of the lensing, the solid angle of the pixel
+// over the one of the patch, so it is as bright as it should be in every image of it however the lensing
+// stretches them; pixels with a corner ray that doesn't reach the sky draw no stars
+pub fn render_stars(scene: &Scene, stars: &Stars, brightness: f64, width: usize, height: usize) -> Frame {
+    let tracer = Tracer::new(scene, &Unlit);
+    let camera = &scene.camera;
+    let half_size = width.max(height) as f64 / 2.;
+    let fov_mult = 1. / (camera.fov / 2.).tan();
+
+    let corner_uv = |i: usize| DVec2::new((i % (width + 1)) as f64 - width as f64 / 2., height as f64 / 2. - (i / (width + 1)) as f64) / half_size;
+    let corners: Vec<Option<(DVec3, f64)>> = (0..(width + 1) * (height + 1))
+        .into_par_iter()
+        .map(|i| tracer.march(construct_ray(camera, corner_uv(i))).sky)
+        .collect();
+
+    let pixels = (0..width * height)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            let indices = [y * (width + 1) + x, y * (width + 1) + x + 1, (y + 1) * (width + 1) + x + 1, (y + 1) * (width + 1) + x];
+            let Some(sky) = indices.iter().map(|&i| corners[i]).collect::<Option<Vec<_>>>() else {
+                return DVec4::ZERO;
+            };
+            let view = indices.map(|i| {
+                let uv = corner_uv(i);
+                (uv.x * camera.x + uv.y * camera.y + fov_mult * camera.z).normalize()
+            });
+            let patch = [sky[0].0, sky[1].0, sky[2].0, sky[3].0];
+
+            let center = patch.iter().sum::<DVec3>().normalize();
+            let radius = patch.iter().map(|corner| corner.angle_between(center)).fold(0., f64::max);
+            let patch_angle = solid_angle(patch[0], patch[1], patch[2]) + solid_angle(patch[0], patch[2], patch[3]);
+            if radius >= PI / 4. || patch_angle <= 0. {
+                return DVec4::ZERO;
+            }
+            let magnification = (solid_angle(view[0], view[1], view[2]) + solid_angle(view[0], view[2], view[3])) / patch_angle;
+            let shift = sky.iter().map(|(_, shift)| shift).sum::<f64>() / 4.;
+
+            let mut color = DVec4::ZERO;
+            for star in stars.around(center, radius) {
+                let first = in_triangle(star.direction, [patch[0], patch[1], patch[2]], center);
+                if first || in_triangle(star.direction, [patch[0], patch[2], patch[3]], center) {
+                    color += frequency_shift((star.color(brightness) * magnification).extend(0.), shift);
+                }
+            }
+            color
+        })
+        .collect();
+
+    Frame { width, height, pixels }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{catalogue::Star, elliptic::CRITICAL_IMPACT_PARAMETER};
+
+    const DEFAULT_STEP_BUDGET: u32 = 200;
+
+    // a white sky and no disc
+    struct White;
+
+    impl Shading for White {
+        fn sky(&self, _: DVec3) -> DVec4 {
+            DVec4::ONE
+        }
+
+        fn disc(&self, _: DVec3, _: f64, _: f64) -> DVec4 {
+            DVec4::ZERO
+        }
+    }
+
+    fn scene(cam_pos: DVec3, integrator: Integrator) -> Scene {
+        let z = -cam_pos.normalize();
+        let x = z.cross(DVec3::Y).normalize();
+        Scene {
+            q: 0.,
+            lambda: 0.,
+            camera: Camera { pos: cam_pos, x, y: x.cross(z), z, fov: PI / 2., velocity: DVec3::ZERO },
+            accretion_disc_r: 6.,
+            accretion_disc_width: 12.,
+            accretion_disc_time: 0.,
+            integrator,
+            step_budget: DEFAULT_STEP_BUDGET,
+            max_orbits: 2.,
+            exact: false,
+        }
+    }
+
+    // how far around the rays from a camera at r = 30 go before they escape, against the exact orbits
+    #[test]
+    fn deflection_angles() {
+        let r = 30.;
+        let cam_pos = DVec3::new(r, 0., 0.);
+        for (integrator, step_budget, tolerance) in [(Integrator::Leapfrog, 2000, 1e-2), (Integrator::Rk4, 2000, 1e-3), (Integrator::DormandPrince, 2000, 1e-3)] {
+            let scene = Scene { step_budget, ..scene(cam_pos, integrator) };
+            let tracer = Tracer::new(&scene, &White);
+            for alpha in [0.5, 1.5, 2., 2.7, 2.9] {
+                let ray = DVec3::new(f64::cos(alpha), 0., -f64::sin(alpha));
+                let marched = tracer.march(ray);
+                assert!(!marched.captured, "{integrator:?} alpha {alpha}");
+
+                let u0 = 1. / r;
+                let v0 = -u0 / alpha.tan();
+                let geodesic = NullGeodesic::new(1. / (v0 * v0 + u0 * u0 * (1. - 2. * u0)).sqrt());
+                let camera_phi = geodesic.phi(u0).unwrap();
+                let end_phi = if v0 < 0. { camera_phi } else { geodesic.end_phi() - camera_phi };
+                assert!((marched.end_phi - end_phi).abs() < tolerance, "{integrator:?} alpha {alpha} {} {end_phi}", marched.end_phi);
+            }
+        }
+    }
+
+    #[test]
+    fn exact_orbits_look_like_integrated_ones() {
+        let exact = Scene { exact: true, ..scene(DVec3::new(0., 3., 25.), Integrator::Rk4) };
+        let integrated = Scene { step_budget: 1000, ..scene(DVec3::new(0., 3., 25.), Integrator::Rk4) };
+        let a = render(&exact, &White, 32, 32, 1);
+        let b = render(&integrated, &White, 32, 32, 1);
+
+        let differing = a.pixels.iter().zip(&b.pixels).filter(|(a, b)| (**a - **b).abs().max_element() > 1e-3).count();
+        assert!(differing <= 2, "{differing} pixels differ");
+    }
+
+    // the shadow of a schwarzschild black hole seen from far away has the radius 3 sqrt(3) M
+    #[test]
+    fn shadow_size() {
+        let r = 1000.;
+        let fov = 0.02;
+        let width = 401;
+        let mut scene = scene(DVec3::new(0., 0., r), Integrator::Leapfrog);
+        scene.camera.fov = fov;
+        // the rays just inside of the edge go around the photon sphere for longer than the steps of 2 orbits reach
+        scene.max_orbits = 4.;
+        let frame = render(&scene, &White, width, 1, 1);
+
+        let dark = frame.pixels.iter().filter(|color| color.x < 0.5).count() as f64;
+        let pixel = 2. * (fov / 2.).tan() / width as f64;
+        let expected = 2. * CRITICAL_IMPACT_PARAMETER * (1. - 2. / r).sqrt() / r / pixel;
+        assert!((dark - expected).abs() <= 2., "{dark} dark pixels, {expected} expected");
+    }
+
+    // rays that pass far from the hole go off where they were heading, a little bent towards it
+    #[test]
+    fn sky_directions() {
+        let tracer_scene = scene(DVec3::new(0., 0., 1000.), Integrator::Rk4);
+        let tracer = Tracer::new(&tracer_scene, &White);
+        let ray = DVec3::new(0.5, 0.2, -1.).normalize();
+        let (direction, _) = tracer.march(ray).sky.unwrap();
+        assert!(direction.dot(ray) > 0.999 && direction.x < ray.x, "{direction}");
+    }
+
+    // a star behind a black hole far away is seen on both sides of it, and the two images together are
+    // (u^2 + 2) / (u sqrt(u^2 + 4)) times as bright as the star, u is its angle from the hole in einstein
+    // radii sqrt(4 M / r)
+    #[test]
+    fn lensed_star() {
+        let r: f64 = 10000.;
+        let einstein_angle = (4. / r).sqrt();
+        let mut scene = Scene { exact: true, ..scene(DVec3::new(0., 0., r), Integrator::Rk4) };
+        scene.camera.fov = 0.12;
+
+        for u in [0.5f64, 1.5] {
+            let beta = u * einstein_angle;
+            let star = Star { direction: scene.camera.z * beta.cos() + scene.camera.x * beta.sin(), magnitude: 0., b_v: 0.65 };
+            let frame = render_stars(&scene, &Stars::new(vec![star]), 1., 201, 201);
+
+            let images: Vec<(usize, f64)> = frame.pixels.iter().enumerate().filter(|(_, color)| color.x > 0.).map(|(i, color)| (i % 201, color.x)).collect();
+            assert_eq!(images.len(), 2, "u {u}");
+            assert!(images[0].0 < 100 && images[1].0 > 100, "u {u}, {images:?}");
+
+            let magnification = images.iter().map(|(_, brightness)| brightness).sum::<f64>() / star.color(1.).x;
+            let expected = (u * u + 2.) / (u * (u * u + 4.).sqrt());
+            assert!((magnification / expected - 1.).abs() < 0.05, "u {u}, {magnification} instead of {expected}");
+        }
+    }
+
+    // close to the singularity of a charged hole f goes above 1, where a camera falling with unit energy can't be
+    #[test]
+    fn charged_interior_camera() {
+        let scene = Scene { q: 0.9, ..scene(DVec3::new(0., 0.1, 0.3), Integrator::Rk4) };
+        let frame = render(&scene, &White, 16, 16, 1);
+        assert!(frame.pixels.iter().all(|color| color.is_finite()));
+    }
+
+    #[test]
+    fn cubemap_faces() {
+        let (coords, face) = to_cubemap(DVec3::new(0.1, 1., -0.2));
+        assert_eq!(face, 2);
+        assert!((coords - DVec2::new(0.55, 0.6)).length() < 1e-12);
+
+        for v in [DVec3::X, DVec3::NEG_X, DVec3::Z, DVec3::NEG_Z, DVec3::NEG_Y] {
+            let (coords, _) = to_cubemap(v + DVec3::splat(0.3));
+            assert!(coords.min_element() >= 0. && coords.max_element() <= 1.);
+        }
+        assert_eq!(to_cubemap(DVec3::X).1, 0);
+        assert_eq!(to_cubemap(DVec3::NEG_Y).1, 3);
+        assert_eq!(to_cubemap(DVec3::NEG_Z).1, 4);
+        assert_eq!(to_cubemap(DVec3::Z).1, 5);
+
+        // the forward face isn't mirrored, right and up on the screen are right and up in the texture
+        let (centre, _) = to_cubemap(DVec3::NEG_Z);
+        let (right, _) = to_cubemap(DVec3::new(0.1, 0., -1.));
+        let (up, _) = to_cubemap(DVec3::new(0., 0.1, -1.));
+        assert!((centre - DVec2::splat(0.5)).length() < 1e-12);
+        assert!(right.x > centre.x && up.y < centre.y);
+
+        for v in [DVec3::new(0.3, -0.2, 0.9), DVec3::new(-1., 0.4, 0.1), DVec3::new(0.2, 0.8, -0.3), DVec3::new(0.1, -0.1, -0.5)] {
+            let (coords, face) = to_cubemap(v);
+            assert!((from_cubemap(coords, face) - v.normalize()).length() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn equirectangular() {
+        assert!((to_equirectangular(DVec3::NEG_Z) - DVec2::new(0.5, 0.5)).length() < 1e-12);
+        assert!((to_equirectangular(DVec3::X) - DVec2::new(0.75, 0.5)).length() < 1e-12);
+        // any longitude is the pole
+        assert!(to_equirectangular(DVec3::Y).y.abs() < 1e-12);
+        assert!((to_equirectangular(DVec3::NEG_Y).y - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn no_shift_at_rest() {
+        let color = DVec4::new(0.2, 0.5, 0.7, 1.);
+        assert!((frequency_shift(color, 1.) - color).abs().max_element() < 1e-12);
+    }
+}
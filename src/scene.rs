@@ -0,0 +1,356 @@
+use std::{fs, path::Path};
+
+use bevy::{asset::{AssetServer, Handle}, math::Vec3, render::texture::Image};
+use serde::{Deserialize, Serialize};
+use smooth_bevy_cameras::LookTransform;
+
+use crate::{catalogue::StarCatalogue, geodesic::Observer, keyframes::CameraPath, metric::UserDefined, skybox::{SkyboxProjection, SkyboxSource}, starfield::Starfield, AccretionDisc, CamData, Jets, RayTracing, SpacetimeMaterial, SpacetimeParams};
+
+// files of a newer version are refused, older ones are read with the defaults for what they don't have
+pub const SCENE_VERSION: u32 = 1;
+
+// everything the sidebar and the camera set, as toml; every table and field that is left out has the
+// default of the app
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneFile {
+    pub version: u32,
+    pub camera: SceneCamera,
+    pub spacetime: SpacetimeParams,
+    pub accretion_disc: SceneDisc,
+    pub jets: Jets,
+    pub skybox: SceneSkybox,
+    pub ray_tracing: RayTracing,
+    pub keyframes: CameraPath,
+}
+
+impl Default for SceneFile {
+    fn default() -> Self {
+        SceneFile {
+            version: SCENE_VERSION,
+            camera: SceneCamera::default(),
+            spacetime: SpacetimeParams::default(),
+            accretion_disc: SceneDisc::default(),
+            jets: Jets::default(),
+            skybox: SceneSkybox::default(),
+            ray_tracing: RayTracing::default(),
+            keyframes: CameraPath::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneCamera {
+    pub eye: Vec3,
+    pub target: Vec3,
+    pub fov: f32, // in degrees, across the longer side of the window
+    pub observer: Observer, // see CamData::observer
+    // relative to the static observer when the scene was saved, from the observer or a free fall; loading works it
+    // out again from the observer and doesn't resume a free fall
+    pub velocity: Vec3,
+}
+
+impl Default for SceneCamera {
+    fn default() -> Self {
+        SceneCamera {
+            eye: Vec3::new(0., 10., 40.),
+            target: Vec3::ZERO,
+            fov: 90.,
+            observer: Observer::default(),
+            velocity: Vec3::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneDisc {
+    pub texture: String, // relative to the assets directory
+    pub r: f32, // inner radius
+    pub width: f32,
+    pub intensity: f32,
+    pub time: f64, // coordinate time the disc has been turning for, see SpacetimeMaterial::disc_time
+    #[serde(flatten)]
+    pub params: AccretionDisc,
+}
+
+impl Default for SceneDisc {
+    fn default() -> Self {
+        SceneDisc {
+            texture: "images/accretion-disc/disc1.png".to_string(),
+            r: 6.,
+            width: 12.,
+            intensity: 0.8,
+            time: 0.,
+            params: AccretionDisc::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneSkybox {
+    pub path: String, // see SkyboxSource
+    pub projection: SkyboxProjection,
+    pub other_path: String, // the universe on the other side of a wormhole throat
+    pub other_projection: SkyboxProjection,
+    pub intensity: f32,
+    pub starfield: Starfield, // of the skyboxes with SkyboxProjection::Starfield
+    pub catalogue: StarCatalogue, // stars the render subcommand draws as points on top of the skybox
+}
+
+impl Default for SceneSkybox {
+    fn default() -> Self {
+        SceneSkybox {
+            path: "images/skybox/skybox1".to_string(),
+            projection: SkyboxProjection::Cube,
+            other_path: "images/skybox/test".to_string(),
+            other_projection: SkyboxProjection::Cube,
+            intensity: 0.7,
+            starfield: Starfield::default(),
+            catalogue: StarCatalogue::default(),
+        }
+    }
+}
+
+impl SceneSkybox {
+    pub fn sources(&self) -> [SkyboxSource; 2] {
+        [
+            SkyboxSource { path: self.path.clone(), projection: self.projection },
+            SkyboxSource { path: self.other_path.clone(), projection: self.other_projection },
+        ]
+    }
+}
+
+// the app only ever switches to a usable spacetime, so a scene has to bring one with the parameters in the ranges
+// the sidebar keeps them in
+fn validate(spacetime: &SpacetimeParams, path: &Path) -> Result<(), String> {
+    let out_of_range = |name: &str, value: f64| Err(format!("{name} = {value} in {} is out of range.", path.display()));
+    let a = spacetime.a;
+    if !spacetime.mass.is_finite() || spacetime.mass <= 0. {
+        return out_of_range("mass", spacetime.mass);
+    }
+    if !(-1. ..=1.).contains(&a) {
+        return out_of_range("a", a);
+    }
+    // a^2 + q^2 > M^2 would be a naked singularity
+    if spacetime.q.is_nan() || spacetime.q.abs() > (1. - a * a).sqrt() {
+        return out_of_range("q", spacetime.q);
+    }
+    if !(0. ..).contains(&spacetime.lambda) {
+        return out_of_range("lambda", spacetime.lambda);
+    }
+    if !(0. ..).contains(&spacetime.throat_radius) {
+        return out_of_range("throat_radius", spacetime.throat_radius);
+    }
+
+    if let Err(error) = UserDefined::parse(&spacetime.user_metric) {
+        return Err(format!("Invalid user-defined metric in {}: {error}", path.display()));
+    }
+    if spacetime.metric()?.horizons().is_none() {
+        return Err(format!("The spacetime parameters in {} don't describe a usable spacetime.", path.display()));
+    }
+    Ok(())
+}
+
+// the path of a texture the app loaded itself
+fn asset_path(handle: &Handle<Image>) -> Option<String> {
+    Some(handle.path()?.path().to_string_lossy().replace('\\', "/"))
+}
+
+impl SceneFile {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+        let mut scene: SceneFile = toml::from_str(&source).map_err(|error| format!("Invalid scene {}: {error}", path.display()))?;
+
+        if scene.version > SCENE_VERSION {
+            return Err(format!("{} is a scene of version {}, this build reads up to version {SCENE_VERSION}.", path.display(), scene.version));
+        }
+        validate(&scene.spacetime, path)?;
+        for keyframe in &scene.keyframes.keyframes {
+            validate(&keyframe.spacetime, path)?;
+        }
+
+        // the keyframes are expected in order
+        let mut keyframes = CameraPath::default();
+        for keyframe in scene.keyframes.keyframes.drain(..) {
+            keyframes.insert(keyframe);
+        }
+        Ok(SceneFile { keyframes, ..scene })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let source = toml::to_string_pretty(self).map_err(|error| format!("Failed to write the scene: {error}"))?;
+        fs::write(path, source).map_err(|error| format!("Failed to write {}: {error}", path.display()))
+    }
+
+    // what the app shows at the moment
+    #[allow(clippy::too_many_arguments)]
+    pub fn capture(
+        transform: &LookTransform,
+        cam_data: &CamData,
+        mat: &SpacetimeMaterial,
+        spacetime_params: &SpacetimeParams,
+        accretion_disc: &AccretionDisc,
+        jets: &Jets,
+        ray_tracing: &RayTracing,
+        skyboxes: &[SkyboxSource; 2],
+        starfield: &Starfield,
+        catalogue: &StarCatalogue,
+        camera_path: &CameraPath
+    ) -> Self {
+        let default = SceneFile::default();
+
+        SceneFile {
+            version: SCENE_VERSION,
+            camera: SceneCamera {
+                eye: transform.eye,
+                target: transform.target,
+                fov: mat.fov.to_degrees(),
+                observer: cam_data.observer,
+                velocity: cam_data.cam_velocity,
+            },
+            spacetime: spacetime_params.clone(),
+            accretion_disc: SceneDisc {
+                texture: asset_path(&mat.accretion_disc_texture).unwrap_or(default.accretion_disc.texture),
+                r: mat.accretion_disc_r,
+                width: mat.accretion_disc_width,
+                intensity: mat.accretion_disc_intensity,
+                time: mat.disc_time,
+                params: accretion_disc.clone(),
+            },
+            jets: jets.clone(),
+            skybox: SceneSkybox {
+                path: skyboxes[0].path.clone(),
+                projection: skyboxes[0].projection,
+                other_path: skyboxes[1].path.clone(),
+                other_projection: skyboxes[1].projection,
+                intensity: mat.skybox_intensity,
+                starfield: starfield.clone(),
+                catalogue: catalogue.clone(),
+            },
+            ray_tracing: ray_tracing.clone(),
+            keyframes: camera_path.clone(),
+        }
+    }
+
+    // the fields of the material that come from the file, the rest follow from the resources and the skyboxes
+    // from SceneSkybox::sources once they have loaded
+    pub fn apply_to_material(&self, mat: &mut SpacetimeMaterial, assets: &AssetServer) {
+        mat.skybox_intensity = self.skybox.intensity;
+
+        mat.fov = self.camera.fov.to_radians();
+
+        let disc = &self.accretion_disc;
+        mat.accretion_disc_texture = assets.load(disc.texture.clone());
+        mat.accretion_disc_r = disc.r;
+        mat.accretion_disc_width = disc.width;
+        mat.accretion_disc_intensity = disc.intensity;
+        mat.disc_time = disc.time;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{disc::{DiscEmission, DiscShape}, keyframes::Keyframe, metric::MetricKind};
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("grplayground-{}-{name}.toml", std::process::id()))
+    }
+
+    // a scene with something besides the defaults in every table
+    fn scene() -> SceneFile {
+        let mut scene = SceneFile::default();
+        scene.camera.eye = Vec3::new(1.5, -2.25, 30.);
+        scene.camera.fov = 60.;
+        scene.camera.observer = Observer::Zamo;
+        scene.spacetime = SpacetimeParams { mass: 2e31, a: 0.6, q: 0.8, lambda: 1e-4, ..SpacetimeParams::default() };
+        scene.spacetime.user_metric[2] = "r^2 + 1".to_string();
+        scene.accretion_disc.time = 123456.789;
+        scene.accretion_disc.params.emission = DiscEmission::Blackbody;
+        scene.accretion_disc.params.shape = DiscShape::Torus;
+        scene.jets.enabled = true;
+        scene.skybox.starfield.seed = 7;
+        scene.skybox.catalogue.path = "catalogues/bright.csv".to_string();
+        scene.ray_tracing.step_budget = 500;
+
+        for time in [2., 0.] {
+            let spacetime = SpacetimeParams { metric: MetricKind::EllisWormhole, throat_radius: time + 1., ..SpacetimeParams::default() };
+            scene.keyframes.insert(Keyframe { time, eye: Vec3::new(0., 1., time as f32 + 10.), target: Vec3::ZERO, fov: 90., spacetime });
+        }
+        scene
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round-trip");
+        for scene in [SceneFile::default(), scene()] {
+            scene.save(&path).unwrap();
+            let loaded = SceneFile::load(&path);
+            fs::remove_file(&path).unwrap();
+            assert_eq!(loaded.unwrap(), scene);
+        }
+    }
+
+    // what a file leaves out has the defaults, but one from a newer build might mean something else by it
+    #[test]
+    fn versions() {
+        let path = temp_path("versions");
+        fs::write(&path, "version = 0\n\n[camera]\nfov = 45.0\n").unwrap();
+        let older = SceneFile::load(&path);
+
+        fs::write(&path, format!("version = {}\n", SCENE_VERSION + 1)).unwrap();
+        let newer = SceneFile::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let older = older.unwrap();
+        assert_eq!(older.camera.fov, 45.);
+        assert_eq!(older.camera.eye, SceneCamera::default().eye);
+        assert_eq!(older.spacetime, SpacetimeParams::default());
+        assert!(newer.unwrap_err().contains(&format!("version {}", SCENE_VERSION + 1)));
+    }
+
+    #[test]
+    fn out_of_range() {
+        let path = Path::new("scene.toml");
+        assert!(validate(&SpacetimeParams::default(), path).is_ok());
+        // the largest charge the sidebar lets through
+        let a: f64 = 0.6;
+        assert!(validate(&SpacetimeParams { a, q: (1. - a * a).sqrt(), ..SpacetimeParams::default() }, path).is_ok());
+
+        let invalid = [
+            SpacetimeParams { mass: 0., ..SpacetimeParams::default() },
+            SpacetimeParams { mass: f64::NAN, ..SpacetimeParams::default() },
+            SpacetimeParams { a: 1.5, ..SpacetimeParams::default() },
+            SpacetimeParams { a: 0.6, q: 0.81, ..SpacetimeParams::default() },
+            SpacetimeParams { q: f64::NAN, ..SpacetimeParams::default() },
+            SpacetimeParams { lambda: -1e-3, ..SpacetimeParams::default() },
+            // a cosmological horizon inside of the black hole
+            SpacetimeParams { lambda: 0.2, ..SpacetimeParams::default() },
+            SpacetimeParams { throat_radius: -1., ..SpacetimeParams::default() },
+        ];
+        for spacetime in invalid {
+            assert!(validate(&spacetime, path).is_err(), "{spacetime:?}");
+        }
+
+        let mut spacetime = SpacetimeParams::default();
+        spacetime.user_metric[0] = "-(1-2/".to_string();
+        assert!(validate(&spacetime, path).unwrap_err().starts_with("Invalid user-defined metric in scene.toml"));
+    }
+
+    // the keyframes are checked like the scene
+    #[test]
+    fn invalid_keyframe() {
+        let path = temp_path("invalid-keyframe");
+        let mut scene = scene();
+        scene.keyframes.keyframes[1].spacetime.a = 2.;
+        scene.save(&path).unwrap();
+        let loaded = SceneFile::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(loaded.unwrap_err().starts_with("a = 2 in"));
+    }
+}
@@ -0,0 +1,173 @@
+use std::path::Path;
+
+use bevy::{asset::{AssetServer, Handle}, render::{render_asset::RenderAssetUsages, render_resource::{Extent3d, TextureDimension, TextureFormat, TextureViewDescriptor, TextureViewDimension}, texture::Image}};
+use image::Rgba32FImage;
+use serde::{Deserialize, Serialize};
+
+use crate::offline::linear_to_srgb;
+
+// the layers of a cube map in the order of wgpu, +x, -x, +y, -y, +z and -z; the cube map is left-handed like
+// the ones of bevy's skybox, so +z is forward, the way the camera looks at the start, and the faces look right
+// from the inside: the side faces have +y up, the top of up and the bottom of down border on the backward face
+pub const CUBE_FACES: [&str; 6] = ["right", "left", "up", "down", "forward", "backward"];
+
+// how the sky is painted around the scene
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SkyboxProjection {
+    #[default]
+    Cube,
+    Equirectangular, // longitude across and latitude down a 2:1 image, forward in the middle and right a quarter to the right of it
+    Starfield, // a cube map made from the parameters of starfield::Starfield instead of the path
+}
+
+impl SkyboxProjection {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SkyboxProjection::Cube => "Cube map",
+            SkyboxProjection::Equirectangular => "Equirectangular",
+            SkyboxProjection::Starfield => "Starfield",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            SkyboxProjection::Cube => SkyboxProjection::Equirectangular,
+            SkyboxProjection::Equirectangular => SkyboxProjection::Starfield,
+            SkyboxProjection::Starfield => SkyboxProjection::Cube,
+        }
+    }
+
+    // skybox_projection in shaders/common.wgsl
+    pub fn shader_value(&self) -> u32 {
+        match self {
+            SkyboxProjection::Cube | SkyboxProjection::Starfield => 0,
+            SkyboxProjection::Equirectangular => 1,
+        }
+    }
+}
+
+// a cube map is a directory with the faces of CUBE_FACES as png, a ktx2 or dds cube map, a single image
+// with the faces stacked from the top in the order of CUBE_FACES, or a spacescape project.xml drawn at the size
+// of Starfield::size; a panorama is a single image, hdr works too;
+// paths are relative to the assets directory
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SkyboxSource {
+    pub path: String,
+    pub projection: SkyboxProjection,
+}
+
+// the images a skybox waits for before it can be used
+#[derive(Debug, Clone)]
+pub enum SkyboxLoad {
+    Faces([Handle<Image>; 6]),
+    Cube(Handle<Image>),
+    Panorama(Handle<Image>),
+    Starfield, // waits for nothing
+    Spacescape, // read with std::fs, bevy has no loader for it
+}
+
+impl SkyboxLoad {
+    pub fn handles(&self) -> Vec<&Handle<Image>> {
+        match self {
+            SkyboxLoad::Faces(faces) => faces.iter().collect(),
+            SkyboxLoad::Cube(image) | SkyboxLoad::Panorama(image) => vec![image],
+            SkyboxLoad::Starfield | SkyboxLoad::Spacescape => vec![],
+        }
+    }
+}
+
+impl SkyboxSource {
+    pub fn is_directory(&self) -> bool {
+        Path::new(&self.path).extension().is_none()
+    }
+
+    pub fn is_spacescape(&self) -> bool {
+        Path::new(&self.path).extension().is_some_and(|extension| extension == "xml")
+    }
+
+    pub fn load(&self, assets: &AssetServer) -> SkyboxLoad {
+        match self.projection {
+            SkyboxProjection::Equirectangular => SkyboxLoad::Panorama(assets.load(self.path.clone())),
+            SkyboxProjection::Starfield => SkyboxLoad::Starfield,
+            SkyboxProjection::Cube if self.is_spacescape() => SkyboxLoad::Spacescape,
+            SkyboxProjection::Cube if self.is_directory() => SkyboxLoad::Faces(CUBE_FACES.map(|face| assets.load(format!("{}/{face}.png", self.path)))),
+            SkyboxProjection::Cube => SkyboxLoad::Cube(assets.load(self.path.clone())),
+        }
+    }
+}
+
+fn cube_view(mut image: Image) -> Image {
+    image.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::Cube),
+        ..Default::default()
+    });
+    image
+}
+
+// the six faces in one texture, they have to be square and of the same size and format
+pub fn assemble_cube(faces: [&Image; 6]) -> Result<Image, String> {
+    let descriptor = &faces[0].texture_descriptor;
+    let size = descriptor.size;
+    if size.width != size.height {
+        return Err(format!("The faces of a cube map have to be square, not {}x{}.", size.width, size.height));
+    }
+    if faces.iter().any(|face| face.texture_descriptor.size != size || face.texture_descriptor.format != descriptor.format) {
+        return Err("The faces of a cube map have to be of the same size and format.".to_string());
+    }
+
+    let mut image = Image::new(
+        Extent3d { width: size.width, height: size.height * 6, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        faces.iter().flat_map(|face| face.data.iter().copied()).collect(),
+        descriptor.format,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.reinterpret_stacked_2d_as_array(6);
+    Ok(cube_view(image))
+}
+
+// ktx2 and dds cube maps already have six layers, the stacked faces are made into them
+pub fn into_cube(mut image: Image) -> Result<Image, String> {
+    let size = image.texture_descriptor.size;
+    if size.depth_or_array_layers == 6 {
+        return Ok(cube_view(image));
+    }
+    if size.depth_or_array_layers != 1 || size.height != size.width * 6 {
+        return Err(format!("A cube map in a single image needs six layers or six square faces stacked on each other, not {}x{}x{}.", size.width, size.height, size.depth_or_array_layers));
+    }
+
+    image.reinterpret_stacked_2d_as_array(6);
+    Ok(cube_view(image))
+}
+
+// the linear faces of starfield::Starfield::generate as srgb, clamped like the textures of the other skyboxes
+pub fn cube_from_faces(faces: &[Rgba32FImage; 6]) -> Image {
+    let size = faces[0].width();
+    let channel = |value: f32| (linear_to_srgb(value.clamp(0., 1.) as f64) * 255.).round() as u8;
+    let data = faces
+        .iter()
+        .flat_map(|face| face.pixels())
+        .flat_map(|texel| [channel(texel.0[0]), channel(texel.0[1]), channel(texel.0[2]), 255])
+        .collect();
+
+    let mut image = Image::new(
+        Extent3d { width: size, height: size * 6, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.reinterpret_stacked_2d_as_array(6);
+    cube_view(image)
+}
+
+// black until the skybox has loaded, the cube binding can't take a 2d image
+pub fn placeholder_cube() -> Image {
+    cube_view(Image::new_fill(
+        Extent3d { width: 1, height: 1, depth_or_array_layers: 6 },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD,
+    ))
+}
@@ -0,0 +1,406 @@
+use std::{collections::HashMap, fs, path::Path, str::FromStr};
+
+use bevy::math::{DVec2, DVec3, DVec4};
+use image::{Rgba, Rgba32FImage};
+use rayon::prelude::*;
+
+use crate::{reference::{from_cubemap, to_cubemap}, starfield::{fractal_noise, hash, mix_seed, ridged_noise, splat, Random}};
+
+// the layers of a spacescape project.xml drawn again at any size, so a background can be kept as a small text
+// file; the layers are blended in the order of the file like spacescape does; the noise is value noise and the
+// billboards are round glows instead of their textures, so the clouds and the bright stars come out in other
+// shapes than in spacescape, but with its colours, blending and settings
+
+// the scene blend factors of ogre that spacescape writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendFactor {
+    One,
+    Zero,
+    SrcColour,
+    DestColour,
+    OneMinusSrcColour,
+    OneMinusDestColour,
+    SrcAlpha,
+    DestAlpha,
+    OneMinusSrcAlpha,
+    OneMinusDestAlpha,
+}
+
+impl FromStr for BlendFactor {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, String> {
+        Ok(match name {
+            "one" => BlendFactor::One,
+            "zero" => BlendFactor::Zero,
+            "src_colour" => BlendFactor::SrcColour,
+            "dest_colour" => BlendFactor::DestColour,
+            "one_minus_src_colour" => BlendFactor::OneMinusSrcColour,
+            "one_minus_dest_colour" => BlendFactor::OneMinusDestColour,
+            "src_alpha" => BlendFactor::SrcAlpha,
+            "dest_alpha" => BlendFactor::DestAlpha,
+            "one_minus_src_alpha" => BlendFactor::OneMinusSrcAlpha,
+            "one_minus_dest_alpha" => BlendFactor::OneMinusDestAlpha,
+            _ => return Err(format!("unknown blend factor {name}")),
+        })
+    }
+}
+
+impl BlendFactor {
+    fn factor(&self, src: DVec4, dest: DVec4) -> DVec4 {
+        match self {
+            BlendFactor::One => DVec4::ONE,
+            BlendFactor::Zero => DVec4::ZERO,
+            BlendFactor::SrcColour => src,
+            BlendFactor::DestColour => dest,
+            BlendFactor::OneMinusSrcColour => 1. - src,
+            BlendFactor::OneMinusDestColour => 1. - dest,
+            BlendFactor::SrcAlpha => DVec4::splat(src.w),
+            BlendFactor::DestAlpha => DVec4::splat(dest.w),
+            BlendFactor::OneMinusSrcAlpha => DVec4::splat(1. - src.w),
+            BlendFactor::OneMinusDestAlpha => DVec4::splat(1. - dest.w),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Noise {
+    pub ridged: bool, // fbm otherwise
+    pub seed: u64,
+    pub scale: f64,
+    pub octaves: u32,
+    pub lacunarity: f64,
+    pub gain: f64,
+    pub offset: f64, // of the ridged noise
+}
+
+impl Noise {
+    fn sample(&self, direction: DVec3) -> f64 {
+        let p = direction * 2. * self.scale;
+        if self.ridged {
+            ridged_noise(self.seed, p, self.octaves, self.lacunarity, self.gain, self.offset)
+        } else {
+            fractal_noise(self.seed, p, self.octaves, self.lacunarity, self.gain)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayerKind {
+    // stars of about a pixel each at any size, point_size scales their light by its square instead of spreading
+    // them over more pixels
+    Points { count: u32, point_size: f64 },
+    // bigger stars, min_size and max_size are the radii of their glows in radians
+    Billboards { count: u32, min_size: f64, max_size: f64 },
+    // clouds from outer_color where the noise is thin to inner_color where it is dense
+    Noise {
+        noise: Noise,
+        inner_color: DVec4,
+        outer_color: DVec4,
+        power: f64,
+        shelf: f64, // noise below it is empty space
+        dither: f64, // random noise added to break up banding
+    },
+}
+
+// the stars of points and billboards get colours from near_color to far_color as if they were at random
+// distances; a mask multiplies the layer with a second noise, which hides it where the mask is below the threshold
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layer {
+    pub name: String,
+    pub kind: LayerKind,
+    pub seed: u64,
+    pub near_color: DVec4,
+    pub far_color: DVec4,
+    pub source_blend: BlendFactor,
+    pub dest_blend: BlendFactor,
+    pub hdr_multiplier: f64,
+    pub hdr_power: f64,
+    pub mask: Option<(Noise, f64, f64)>, // the noise, its power and its threshold
+}
+
+// a point or billboard star
+struct Sprite {
+    direction: DVec3,
+    color: DVec4,
+    size: f64, // of the glow of a billboard in radians, pixels across of a point
+}
+
+// the text of the child elements of a layer
+struct Fields<'a> {
+    layer: String,
+    values: HashMap<&'a str, &'a str>,
+}
+
+impl Fields<'_> {
+    fn get<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        match self.values.get(name) {
+            Some(value) => value.trim().parse().map_err(|_| format!("Invalid {name} of the layer {}: {value}", self.layer)),
+            None => Ok(default),
+        }
+    }
+
+    fn color(&self, name: &str, default: DVec4) -> Result<DVec4, String> {
+        let Some(value) = self.values.get(name) else {
+            return Ok(default);
+        };
+        let channels: Vec<f64> = value.split_whitespace().map(str::parse).collect::<Result<_, _>>().map_err(|_| format!("Invalid {name} of the layer {}: {value}", self.layer))?;
+        match channels[..] {
+            [r, g, b] => Ok(DVec4::new(r, g, b, 1.)),
+            [r, g, b, a] => Ok(DVec4::new(r, g, b, a)),
+            _ => Err(format!("Invalid {name} of the layer {}: {value}", self.layer)),
+        }
+    }
+
+    // the noise of the layer or of its mask, with the names prefixed by mask
+    fn noise(&self, prefix: &str) -> Result<Noise, String> {
+        let name = |name: &str| if prefix.is_empty() { name.to_string() } else { format!("{prefix}{}{}", name[..1].to_uppercase(), &name[1..]) };
+        let noise_type: String = self.get(&name("noiseType"), "fbm".to_string())?;
+        if noise_type != "fbm" && noise_type != "ridged" {
+            return Err(format!("Unknown noise type of the layer {}: {noise_type}", self.layer));
+        }
+
+        Ok(Noise {
+            ridged: noise_type == "ridged",
+            seed: self.get(&name("seed"), 0)?,
+            scale: self.get(&name("scale"), 1.)?,
+            octaves: self.get(&name("octaves"), 1)?,
+            lacunarity: self.get(&name("lacunarity"), 2.)?,
+            gain: self.get(&name("gain"), 0.5)?,
+            offset: self.get(&name("offset"), 1.)?,
+        })
+    }
+}
+
+impl Layer {
+    fn parse(fields: &Fields) -> Result<Self, String> {
+        let layer_type: String = fields.get("type", String::new())?;
+        let kind = match layer_type.as_str() {
+            "points" => LayerKind::Points {
+                count: fields.get("numPoints", 0)?,
+                point_size: fields.get("pointSize", 1.)?,
+            },
+            "billboards" => LayerKind::Billboards {
+                count: fields.get("numBillboards", 0)?,
+                min_size: fields.get("minSize", 0.01)?,
+                max_size: fields.get("maxSize", 0.01)?,
+            },
+            "noise" => LayerKind::Noise {
+                noise: fields.noise("")?,
+                inner_color: fields.color("innerColor", DVec4::ONE)?,
+                outer_color: fields.color("outerColor", DVec4::new(0., 0., 0., 1.))?,
+                power: fields.get("powerAmount", 1.)?,
+                shelf: fields.get("shelfAmount", 0.)?,
+                dither: fields.get("ditherAmount", 0.)?,
+            },
+            _ => return Err(format!("Unknown type of the layer {}: {layer_type}", fields.layer)),
+        };
+
+        let mask = if fields.get("maskEnabled", false)? {
+            Some((fields.noise("mask")?, fields.get("maskPower", 1.)?, fields.get("maskThreshold", 0.)?))
+        } else {
+            None
+        };
+
+        Ok(Layer {
+            name: fields.layer.clone(),
+            kind,
+            seed: fields.get("seed", 0)?,
+            near_color: fields.color("nearColor", DVec4::ONE)?,
+            far_color: fields.color("farColor", DVec4::new(0., 0., 0., 1.))?,
+            source_blend: fields.get("sourceBlendFactor", BlendFactor::One)?,
+            dest_blend: fields.get("destBlendFactor", BlendFactor::One)?,
+            hdr_multiplier: fields.get("hdrMultiplier", 1.)?,
+            hdr_power: fields.get("hdrPower", 1.)?,
+            mask,
+        })
+    }
+
+    // uniform over the sphere
+    fn sprites(&self) -> Vec<Sprite> {
+        let (count, min_size, max_size) = match self.kind {
+            LayerKind::Points { count, point_size } => (count, point_size, point_size),
+            LayerKind::Billboards { count, min_size, max_size } => (count, min_size, max_size),
+            LayerKind::Noise { .. } => return vec![],
+        };
+
+        let mut random = Random(mix_seed(self.seed, u64::MAX));
+        (0..count)
+            .map(|_| {
+                let z = 2. * random.next_f64() - 1.;
+                let phi = 2. * std::f64::consts::PI * random.next_f64();
+                let direction = DVec3::new((1. - z * z).sqrt() * phi.cos(), z, (1. - z * z).sqrt() * phi.sin());
+                let color = self.near_color.lerp(self.far_color, random.next_f64());
+                let size = min_size + (max_size - min_size) * random.next_f64();
+                Sprite { direction, color, size }
+            })
+            .collect()
+    }
+
+    // the layer on a face before it is blended, rows from the top
+    fn render(&self, face: usize, size: u32, directions: &[DVec3], sprites: &[Sprite]) -> Vec<DVec4> {
+        let mut colors: Vec<DVec4> = match &self.kind {
+            LayerKind::Points { .. } => {
+                let mut image = Rgba32FImage::new(size, size);
+                for sprite in sprites {
+                    let (coords, sprite_face) = to_cubemap(sprite.direction);
+                    if sprite_face == face {
+                        splat(&mut image, coords, sprite.color * sprite.size * sprite.size);
+                    }
+                }
+                image.pixels().map(|texel| DVec4::from_array(texel.0.map(f64::from))).collect()
+            }
+            LayerKind::Billboards { .. } => directions
+                .iter()
+                .map(|direction| {
+                    sprites
+                        .iter()
+                        .map(|sprite| {
+                            let r = direction.angle_between(sprite.direction) / sprite.size.max(1e-6);
+                            sprite.color * (1. - r).max(0.).powi(2)
+                        })
+                        .sum()
+                })
+                .collect(),
+            LayerKind::Noise { noise, inner_color, outer_color, power, shelf, dither } => directions
+                .iter()
+                .enumerate()
+                .map(|(i, direction)| {
+                    let (x, y) = ((i % size as usize) as i64, (i / size as usize) as i64);
+                    let value = noise.sample(*direction) + (hash(self.seed, x, y, face as i64) - 0.5) * dither;
+                    let value = value.clamp(0., 1.).powf(*power);
+                    let density = if value < *shelf { 0. } else { (value - shelf) / (1. - shelf).max(1e-6) };
+                    outer_color.lerp(*inner_color, density)
+                })
+                .collect(),
+        };
+
+        for (color, direction) in colors.iter_mut().zip(directions) {
+            let rgb = color.truncate().max(DVec3::ZERO).powf(self.hdr_power) * self.hdr_multiplier;
+            *color = rgb.extend(color.w);
+            if let Some((mask, power, threshold)) = &self.mask {
+                let value = ((mask.sample(*direction) - threshold) / (1. - threshold).max(1e-6)).clamp(0., 1.);
+                *color *= value.powf(*power);
+            }
+        }
+        colors
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpacescapeProject {
+    pub layers: Vec<Layer>,
+}
+
+impl SpacescapeProject {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let document = roxmltree::Document::parse(source).map_err(|error| format!("Invalid spacescape project: {error}"))?;
+        let root = document.root_element();
+        if root.tag_name().name() != "spacescapelayers" {
+            return Err(format!("Not a spacescape project, the root element is {}.", root.tag_name().name()));
+        }
+
+        let mut layers = vec![];
+        for (index, node) in root.children().filter(|node| node.has_tag_name("layer")).enumerate() {
+            let values: HashMap<&str, &str> = node.children().filter(|child| child.is_element()).map(|child| (child.tag_name().name(), child.text().unwrap_or(""))).collect();
+            let layer = values.get("name").map_or(format!("{}", index + 1), |name| name.to_string());
+            layers.push(Layer::parse(&Fields { layer, values })?);
+        }
+        Ok(SpacescapeProject { layers })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|error| format!("Failed to read {}: {error}", path.display()))?;
+        SpacescapeProject::parse(&source).map_err(|error| format!("{}: {error}", path.display()))
+    }
+
+    // the faces in the order and orientation of skybox::CUBE_FACES, in linear colours
+    pub fn generate(&self, size: u32) -> [Rgba32FImage; 6] {
+        let size = size.max(1);
+        let sprites: Vec<Vec<Sprite>> = self.layers.iter().map(Layer::sprites).collect();
+
+        let faces: Vec<Rgba32FImage> = (0..6)
+            .into_par_iter()
+            .map(|face| {
+                let directions: Vec<DVec3> = (0..size * size)
+                    .map(|i| from_cubemap((DVec2::new((i % size) as f64, (i / size) as f64) + 0.5) / size as f64, face))
+                    .collect();
+
+                let mut dest = vec![DVec4::ZERO; directions.len()];
+                for (layer, sprites) in self.layers.iter().zip(&sprites) {
+                    for (dest, src) in dest.iter_mut().zip(layer.render(face, size, &directions, sprites)) {
+                        *dest = src * layer.source_blend.factor(src, *dest) + *dest * layer.dest_blend.factor(src, *dest);
+                    }
+                }
+
+                Rgba32FImage::from_fn(size, size, |x, y| {
+                    let color = dest[(y * size + x) as usize];
+                    Rgba([color.x as f32, color.y as f32, color.z as f32, 1.])
+                })
+            })
+            .collect();
+        faces.try_into().expect("Six faces.")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mean(faces: &[Rgba32FImage; 6]) -> DVec3 {
+        let texels: Vec<DVec3> = faces.iter().flat_map(|face| face.pixels()).map(|texel| DVec3::new(texel.0[0] as f64, texel.0[1] as f64, texel.0[2] as f64)).collect();
+        texels.iter().sum::<DVec3>() / texels.len() as f64
+    }
+
+    #[test]
+    fn reads_the_bundled_project() {
+        let project = SpacescapeProject::parse(include_str!("../assets/images/skybox/skybox1/project.xml")).unwrap();
+        assert_eq!(project.layers.len(), 4);
+        assert_eq!(project.layers[0].kind, LayerKind::Points { count: 5000, point_size: 1. });
+        assert_eq!(project.layers[1].kind, LayerKind::Billboards { count: 25, min_size: 0.05, max_size: 0.02 });
+        assert_eq!(project.layers[1].far_color, DVec4::new(1., 0.352941, 1., 1.));
+
+        let LayerKind::Noise { noise, inner_color, shelf, .. } = &project.layers[3].kind else {
+            panic!("{:?} isn't a noise layer", project.layers[3].kind);
+        };
+        assert_eq!(project.layers[3].name, "Green Nebula");
+        assert_eq!((noise.seed, noise.octaves, noise.ridged), (1, 12, false));
+        assert_eq!(*inner_color, DVec4::new(0., 0.439216, 0., 0.584314));
+        assert_eq!(*shelf, 0.5);
+        assert!(project.layers.iter().all(|layer| layer.source_blend == BlendFactor::One && layer.dest_blend == BlendFactor::One));
+    }
+
+    #[test]
+    fn refuses_what_it_cant_draw() {
+        assert!(SpacescapeProject::parse("<layers></layers>").is_err());
+        assert!(SpacescapeProject::parse("<spacescapelayers><layer><type>sprites</type></layer></spacescapelayers>").is_err());
+        assert!(SpacescapeProject::parse("<spacescapelayers><layer><type>points</type><numPoints>many</numPoints></layer></spacescapelayers>").is_err());
+    }
+
+    #[test]
+    fn blends_like_spacescape() {
+        let layer = |color: &str, source: &str, dest: &str| format!(
+            "<layer><type>noise</type><innerColor>{color}</innerColor><outerColor>{color}</outerColor><sourceBlendFactor>{source}</sourceBlendFactor><destBlendFactor>{dest}</destBlendFactor></layer>"
+        );
+        let project = |layers: &[String]| SpacescapeProject::parse(&format!("<spacescapelayers>{}</spacescapelayers>", layers.concat())).unwrap();
+
+        let red = layer("1 0 0 1", "one", "one");
+        let added = project(&[red.clone(), layer("0 0.5 0 1", "one", "one")]).generate(4);
+        assert!((mean(&added) - DVec3::new(1., 0.5, 0.)).length() < 1e-6);
+        let replaced = project(&[red.clone(), layer("0 0.5 0 1", "one", "zero")]).generate(4);
+        assert!((mean(&replaced) - DVec3::new(0., 0.5, 0.)).length() < 1e-6);
+        let mixed = project(&[red, layer("0 0 1 0.25", "src_alpha", "one_minus_src_alpha")]).generate(4);
+        assert!((mean(&mixed) - DVec3::new(0.75, 0., 0.25)).length() < 1e-6);
+    }
+
+    #[test]
+    fn any_resolution() {
+        // the stars stay a pixel large, so only the clouds keep their mean
+        let mut project = SpacescapeProject::parse(include_str!("../assets/images/skybox/skybox1/project.xml")).unwrap();
+        project.layers.retain(|layer| matches!(layer.kind, LayerKind::Noise { .. }));
+        let small = mean(&project.generate(16));
+        let large = mean(&project.generate(48));
+        assert!(small.is_finite() && small.length() > 0.);
+        assert!((small - large).length() < 0.1 * large.length(), "{small} {large}");
+    }
+}
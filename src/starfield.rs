@@ -0,0 +1,272 @@
+use std::f64::consts::PI;
+
+use bevy::math::{DVec2, DVec3, DVec4};
+use image::Rgba32FImage;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::reference::{blackbody_color, from_cubemap, to_cubemap};
+
+// a sky of point stars and clouds of glowing gas made from a seed, so new backgrounds don't need an external
+// tool; the faces are in the order and orientation of skybox::CUBE_FACES, in linear colours
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Starfield {
+    pub seed: u64,
+    pub size: u32, // of a face in pixels
+    pub stars: u32,
+    pub faintest_magnitude: f64, // the brightest stars are of magnitude 0
+    pub star_brightness: f64, // of a star of magnitude 0, summed over the pixels it falls on
+    pub nebulae: Vec<Nebula>,
+}
+
+impl Default for Starfield {
+    fn default() -> Self {
+        Starfield {
+            seed: 0,
+            size: 1024,
+            stars: 5000,
+            faintest_magnitude: 6.,
+            star_brightness: 1.,
+            nebulae: vec![
+                Nebula { seed: 0, inner_color: [0.44, 0.35, 1.], ..Nebula::default() },
+                Nebula { seed: 1, inner_color: [0., 0.44, 0.], ..Nebula::default() },
+            ],
+        }
+    }
+}
+
+// fractal noise over the sphere, coloured from outer_color where it is thin to inner_color where it is dense;
+// the layers are added on top of each other
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Nebula {
+    pub seed: u64, // on top of the one of the starfield
+    pub inner_color: [f32; 3],
+    pub outer_color: [f32; 3],
+    pub scale: f64, // frequency of the largest clouds, at 1 they are about as large as a face
+    pub octaves: u32,
+    pub lacunarity: f64, // frequency factor from one octave to the next
+    pub gain: f64, // amplitude factor from one octave to the next
+    pub shelf: f64, // noise below it is empty space, the noise is between 0 and 1
+    pub power: f64, // > 1 makes the edges of the clouds sharper
+    pub intensity: f64,
+}
+
+impl Default for Nebula {
+    fn default() -> Self {
+        Nebula {
+            seed: 0,
+            inner_color: [1., 1., 1.],
+            outer_color: [0., 0., 0.],
+            scale: 1.,
+            octaves: 8,
+            lacunarity: 2.,
+            gain: 0.5,
+            shelf: 0.5,
+            power: 1.,
+            intensity: 1.,
+        }
+    }
+}
+
+impl Nebula {
+    // one more layer for the sidebar, the colour is a random hue
+    pub fn random(seed: u64) -> Self {
+        let mut random = Random(seed);
+        let hue = random.next_f64() * 2. * PI;
+        let color = DVec3::new(hue.cos(), (hue - 2. * PI / 3.).cos(), (hue + 2. * PI / 3.).cos()) * 0.5 + 0.5;
+        Nebula { seed, inner_color: (color * 0.6).as_vec3().to_array(), ..Nebula::default() }
+    }
+
+    fn color(&self, seed: u64, direction: DVec3) -> DVec3 {
+        let seed = mix_seed(seed, self.seed);
+        let noise = fractal_noise(seed, direction * 2. * self.scale, self.octaves, self.lacunarity, self.gain);
+        let density = ((noise - self.shelf) / (1. - self.shelf).max(1e-6)).clamp(0., 1.).powf(self.power);
+        let inner = DVec3::from_array(self.inner_color.map(f64::from));
+        let outer = DVec3::from_array(self.outer_color.map(f64::from));
+        outer.lerp(inner, density) * self.intensity
+    }
+}
+
+// splitmix64
+pub struct Random(pub u64);
+
+impl Random {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // in [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+pub fn mix_seed(a: u64, b: u64) -> u64 {
+    Random(a ^ b.wrapping_mul(0x2545_f491_4f6c_dd1d)).next_u64()
+}
+
+pub fn hash(seed: u64, x: i64, y: i64, z: i64) -> f64 {
+    let key = (x as u64).wrapping_mul(0x8da6_b343) ^ (y as u64).wrapping_mul(0xd816_3841) ^ (z as u64).wrapping_mul(0xcb1a_b31f);
+    Random(seed ^ key).next_f64()
+}
+
+// value_noise of shaders/common.wgsl
+fn value_noise(seed: u64, p: DVec3) -> f64 {
+    let i = p.floor();
+    let t = p - i;
+    let s = t * t * (3. - 2. * t);
+    let (x, y, z) = (i.x as i64, i.y as i64, i.z as i64);
+
+    let mix = |a: f64, b: f64, t: f64| a + (b - a) * t;
+    let x00 = mix(hash(seed, x, y, z), hash(seed, x + 1, y, z), s.x);
+    let x10 = mix(hash(seed, x, y + 1, z), hash(seed, x + 1, y + 1, z), s.x);
+    let x01 = mix(hash(seed, x, y, z + 1), hash(seed, x + 1, y, z + 1), s.x);
+    let x11 = mix(hash(seed, x, y + 1, z + 1), hash(seed, x + 1, y + 1, z + 1), s.x);
+    mix(mix(x00, x10, s.y), mix(x01, x11, s.y), s.z)
+}
+
+// between 0 and 1, every octave with its own lattice so they don't line up at the origin
+pub fn fractal_noise(seed: u64, p: DVec3, octaves: u32, lacunarity: f64, gain: f64) -> f64 {
+    let mut sum = 0.;
+    let mut total = 0.;
+    let mut amplitude = 1.;
+    let mut frequency = 1.;
+    for octave in 0..octaves.max(1) {
+        sum += amplitude * value_noise(mix_seed(seed, octave as u64), p * frequency);
+        total += amplitude;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+    sum / total
+}
+
+// musgrave's ridged multifractal, sharp ridges where the noise crosses its middle, between 0 and 1;
+// every octave is weighted by the one before it, so the detail gathers on the ridges
+pub fn ridged_noise(seed: u64, p: DVec3, octaves: u32, lacunarity: f64, gain: f64, offset: f64) -> f64 {
+    let mut sum = 0.;
+    let mut total = 0.;
+    let mut amplitude = 1.;
+    let mut frequency = 1.;
+    let mut weight = 1.;
+    for octave in 0..octaves.max(1) {
+        let signal = (offset - (2. * value_noise(mix_seed(seed, octave as u64), p * frequency) - 1.).abs()).powi(2) * weight;
+        weight = (signal * 2.).clamp(0., 1.);
+        sum += amplitude * signal;
+        total += amplitude * offset * offset;
+        amplitude *= gain;
+        frequency *= lacunarity;
+    }
+    (sum / total.max(1e-9)).clamp(0., 1.)
+}
+
+// ballesteros' fit of the temperature in kelvin of a blackbody with the b - v colour index
+pub fn color_index_temperature(b_v: f64) -> f64 {
+    4600. * (1. / (0.92 * b_v + 1.7) + 1. / (0.92 * b_v + 0.62))
+}
+
+// adds a point source at the texture coordinates to the four pixels around it, so it doesn't jump from pixel
+// to pixel
+pub fn splat(face: &mut Rgba32FImage, coords: DVec2, color: DVec4) {
+    let size = face.width() as f64;
+    let pixel = coords * size - 0.5;
+    let corner = pixel.floor();
+    let t = pixel - corner;
+
+    for (dx, dy, weight) in [(0., 0., (1. - t.x) * (1. - t.y)), (1., 0., t.x * (1. - t.y)), (0., 1., (1. - t.x) * t.y), (1., 1., t.x * t.y)] {
+        let x = (corner.x + dx).clamp(0., size - 1.) as u32;
+        let y = (corner.y + dy).clamp(0., size - 1.) as u32;
+        let texel = face.get_pixel_mut(x, y);
+        for (channel, value) in texel.0.iter_mut().zip(color.to_array()) {
+            *channel += (value * weight) as f32;
+        }
+    }
+}
+
+impl Starfield {
+    // the nebulae the sidebar asks for, the ones it had are kept
+    pub fn set_nebula_count(&mut self, count: usize) {
+        self.nebulae.truncate(count);
+        let len = self.nebulae.len() as u64;
+        self.nebulae.extend((len..count as u64).map(Nebula::random));
+    }
+
+    pub fn generate(&self) -> [Rgba32FImage; 6] {
+        let size = self.size.max(1);
+        let mut faces: [Rgba32FImage; 6] = std::array::from_fn(|_| Rgba32FImage::new(size, size));
+
+        faces.par_iter_mut().enumerate().for_each(|(face, image)| {
+            for (x, y, texel) in image.enumerate_pixels_mut() {
+                let coords = DVec2::new(x as f64 + 0.5, y as f64 + 0.5) / size as f64;
+                let direction = from_cubemap(coords, face);
+                let color: DVec3 = self.nebulae.iter().map(|nebula| nebula.color(self.seed, direction)).sum();
+                texel.0 = color.extend(1.).as_vec4().to_array();
+            }
+        });
+
+        // uniform over the sphere, with n(< m) growing as 10^(0.6 m) like stars spread evenly through space
+        let mut random = Random(mix_seed(self.seed, u64::MAX));
+        let range = 10f64.powf(0.6 * self.faintest_magnitude.max(0.)) - 1.;
+        for _ in 0..self.stars {
+            let z = 2. * random.next_f64() - 1.;
+            let phi = 2. * PI * random.next_f64();
+            let direction = DVec3::new((1. - z * z).sqrt() * phi.cos(), z, (1. - z * z).sqrt() * phi.sin());
+
+            let magnitude = (1. + random.next_f64() * range).log10() / 0.6;
+            // b - v from -0.1 to 1.5, most of them around the sun's 0.7
+            let b_v = 1.6 * (random.next_f64() + random.next_f64()) / 2. - 0.1;
+            let color = blackbody_color(color_index_temperature(b_v)) * self.star_brightness * 10f64.powf(-0.4 * magnitude);
+            let (coords, face) = to_cubemap(direction);
+            splat(&mut faces[face], coords, color.extend(0.));
+        }
+
+        faces
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small() -> Starfield {
+        Starfield { size: 32, stars: 200, ..Starfield::default() }
+    }
+
+    #[test]
+    fn same_seed_same_sky() {
+        let a = small().generate();
+        let b = small().generate();
+        let c = Starfield { seed: 1, ..small() }.generate();
+        assert!(a.iter().zip(&b).all(|(a, b)| a == b));
+        assert!(a.iter().zip(&c).any(|(a, c)| a != c));
+    }
+
+    #[test]
+    fn stars_keep_their_light() {
+        let starfield = Starfield { stars: 20000, nebulae: vec![], ..small() };
+        let faces = starfield.generate();
+        let total: f64 = faces.iter().flat_map(|face| face.pixels()).map(|texel| texel.0[1] as f64).sum();
+
+        // 10^(-0.4 m) averages to 3 (10^(0.2 m_max) - 1) / (10^(0.6 m_max) - 1) over the magnitudes, and the
+        // green channel is between about 0.6 and 1 of the brightest one
+        let m = starfield.faintest_magnitude;
+        let mean = 3. * (10f64.powf(0.2 * m) - 1.) / (10f64.powf(0.6 * m) - 1.);
+        let expected = starfield.stars as f64 * mean;
+        assert!(total > 0.5 * expected && total < 1.2 * expected, "{total}, {expected}");
+    }
+
+    #[test]
+    fn nebulae_are_seamless() {
+        let nebula = Nebula::default();
+        let edge = DVec3::new(1., 0.3, -1.).normalize();
+        let (a, b) = (edge + DVec3::new(1e-9, 0., 0.), edge - DVec3::new(1e-9, 0., 0.));
+        assert_ne!(to_cubemap(a).1, to_cubemap(b).1);
+        assert!((nebula.color(0, a) - nebula.color(0, b)).length() < 1e-6);
+    }
+}